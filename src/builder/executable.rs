@@ -0,0 +1,193 @@
+use super::super::elf64::Elf64;
+use super::super::error::ElfError;
+use super::super::header::e_type::EType;
+use super::super::header::header::Elf64Ehdr;
+use super::super::program::p_type::PType;
+use super::super::program::program_header::{Elf64_Phdr, PFlags};
+use super::super::section::sh_type::ShType;
+use super::super::section::string_table::StringTableBuilder;
+use super::super::section::symbol::{Elf64_Rela, Elf64_Sym, SymbolBinding, SymbolType};
+use super::{gnu_stack, layout, load_count, relocate, write_file, BuilderSection, Segment};
+
+struct ExecutableSymbol {
+    name: String,
+    section: String,
+    offset: u64,
+    size: u64,
+    symbol_type: SymbolType,
+}
+
+/*
+ * builds an ET_EXEC that runs without ld.so. the elf header, the program
+ * headers and the read only sections share the first PT_LOAD, code and
+ * data get their own. relocations are resolved at build time against the
+ * symbols and the section names
+ */
+pub struct ExecutableBuilder {
+    base_address: u64,
+    entry: String,
+    gnu_stack: bool,
+    phdr: bool,
+    section_headers: bool,
+    symtab: bool,
+    sections: Vec<BuilderSection>,
+    symbols: Vec<ExecutableSymbol>,
+    relocations: Vec<(String, String, Elf64_Rela)>, /* Section, symbol and relocation */
+}
+
+impl Default for ExecutableBuilder {
+    fn default() -> Self {
+        Self {
+            base_address: 0x400000,
+            entry: "_start".to_string(),
+            gnu_stack: true,
+            phdr: false,
+            section_headers: true,
+            symtab: true,
+            sections: Vec::new(),
+            symbols: Vec::new(),
+            relocations: Vec::new(),
+        }
+    }
+}
+
+impl ExecutableBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn base_address(mut self, address: u64) -> Self {
+        self.base_address = address;
+        self
+    }
+    pub fn entry(mut self, symbol: &str) -> Self {
+        self.entry = symbol.to_string();
+        self
+    }
+    /*
+     * non executable stack, on by default
+     */
+    pub fn gnu_stack(mut self, enabled: bool) -> Self {
+        self.gnu_stack = enabled;
+        self
+    }
+    pub fn phdr(mut self, enabled: bool) -> Self {
+        self.phdr = enabled;
+        self
+    }
+    /*
+     * without section headers there is no .symtab either
+     */
+    pub fn section_headers(mut self, enabled: bool) -> Self {
+        self.section_headers = enabled;
+        self
+    }
+    pub fn symtab(mut self, enabled: bool) -> Self {
+        self.symtab = enabled;
+        self
+    }
+    section_methods!();
+    /*
+     * global symbol at offset in section
+     */
+    pub fn add_symbol(
+        mut self,
+        name: &str,
+        section: &str,
+        offset: u64,
+        size: u64,
+        symbol_type: SymbolType,
+    ) -> Self {
+        self.symbols.push(ExecutableSymbol {
+            name: name.to_string(),
+            section: section.to_string(),
+            offset,
+            size,
+            symbol_type,
+        });
+        self
+    }
+    fn symbol_address(&self, name: &str) -> Option<Result<u64, ElfError>> {
+        let symbol = self.symbols.iter().find(|s| s.name == name)?;
+        Some(
+            self.section(&symbol.section)
+                .map(|i| self.sections[i].addr + symbol.offset),
+        )
+    }
+    fn apply_relocations(&mut self) -> Result<(), ElfError> {
+        for (section, symbol, rela) in std::mem::take(&mut self.relocations) {
+            let index = self.section(&section)?;
+            let s = self.address_of(&symbol)?;
+            let place = self.sections[index].addr + rela.r_offset;
+            relocate(&mut self.sections[index].data, &rela, s, place)?;
+        }
+        Ok(())
+    }
+    fn symtab_sections(&self) -> Result<[BuilderSection; 2], ElfError> {
+        let mut strtab = StringTableBuilder::new();
+        self.symbols.iter().for_each(|s| strtab.add(&s.name));
+        strtab.finalize();
+        let mut symtab: Vec<u8> = Elf64_Sym::new(
+            "",
+            SymbolBinding::STB_LOCAL,
+            SymbolType::STT_NOTYPE,
+            0,
+            0,
+            0,
+        )
+        .into();
+        for symbol in &self.symbols {
+            let index = self.section(&symbol.section)?;
+            let mut s = Elf64_Sym::new(
+                &symbol.name,
+                SymbolBinding::STB_GLOBAL,
+                symbol.symbol_type,
+                index as u16 + 1,
+                self.sections[index].addr + symbol.offset,
+                symbol.size,
+            );
+            s.st_name = strtab.offset(&symbol.name)?;
+            symtab.extend(Into::<Vec<u8>>::into(&s));
+        }
+        Ok([
+            BuilderSection::new(".symtab", None, ShType::SHT_SYMTAB, 8, symtab)
+                .linked(".strtab", 1, 24),
+            BuilderSection::new(
+                ".strtab",
+                None,
+                ShType::SHT_STRTAB,
+                1,
+                strtab.data().to_vec(),
+            ),
+        ])
+    }
+    pub fn build(mut self) -> Result<Elf64, ElfError> {
+        let phnum = load_count(&self.sections) + self.phdr as usize + self.gnu_stack as usize;
+        let headers_end = 64 + 56 * phnum as u64;
+        let loads = layout(&mut self.sections, self.base_address, headers_end);
+        self.apply_relocations()?;
+        let mut programs: Vec<Elf64_Phdr> = Vec::new();
+        if self.phdr {
+            programs.push(Elf64_Phdr {
+                p_type: PType::PT_PHDR,
+                p_flags: PFlags::Read.into(),
+                p_offset: 64,
+                p_vaddr: self.base_address + 64,
+                p_paddr: self.base_address + 64,
+                p_filesz: 56 * phnum as u64,
+                p_memsz: 56 * phnum as u64,
+                p_align: 8,
+            });
+        }
+        programs.extend(loads);
+        if self.gnu_stack {
+            programs.push(gnu_stack());
+        }
+        if self.section_headers && self.symtab {
+            let symtab = self.symtab_sections()?;
+            self.sections.extend(symtab);
+        }
+        let mut header = Elf64Ehdr::new(EType::ET_EXEC);
+        header.e_entry = self.address_of(&self.entry)?;
+        write_file(header, &programs, &self.sections, self.section_headers)
+    }
+}
@@ -0,0 +1,430 @@
+use super::common::{write_at, PAGE_SIZE};
+use super::elf64::Elf64;
+use super::error::ElfError;
+use super::header::header::Elf64Ehdr;
+use super::program::p_type::PType;
+use super::program::program_header::{Elf64_Phdr, PFlags};
+use super::section::section_header::Elf64Shdr;
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+use super::section::string_table::StringTableBuilder;
+use super::section::symbol::{Elf64_Rela, RelocationType};
+use enumflags2::BitFlags;
+
+/*
+ * the methods adding sections and relocations and looking them up, the
+ * same in every builder with sections and relocations fields. the builder
+ * resolves its own symbols with symbol_address
+ */
+macro_rules! section_methods {
+    () => {
+        fn add(mut self, name: &str, segment: Segment, align: u64, data: Vec<u8>) -> Self {
+            self.sections.push(BuilderSection::new(
+                name,
+                Some(segment),
+                ShType::SHT_PROGBITS,
+                align,
+                data,
+            ));
+            self
+        }
+        pub fn add_code(self, name: &str, align: u64, data: Vec<u8>) -> Self {
+            self.add(name, Segment::Code, align, data)
+        }
+        pub fn add_rodata(self, name: &str, align: u64, data: Vec<u8>) -> Self {
+            self.add(name, Segment::ReadOnly, align, data)
+        }
+        pub fn add_data(self, name: &str, align: u64, data: Vec<u8>) -> Self {
+            self.add(name, Segment::Data, align, data)
+        }
+        pub fn add_bss(mut self, name: &str, align: u64, size: u64) -> Self {
+            self.sections
+                .push(BuilderSection::nobits(name, align, size));
+            self
+        }
+        /*
+         * r_offset is relative to section, symbol_index is ignored and the
+         * symbol is looked up by name first and then as a section name
+         */
+        pub fn add_relocation(
+            mut self,
+            section: &str,
+            symbol: &str,
+            relocation: Elf64_Rela,
+        ) -> Self {
+            self.relocations
+                .push((section.to_string(), symbol.to_string(), relocation));
+            self
+        }
+        fn section(&self, name: &str) -> Result<usize, ElfError> {
+            self.sections
+                .iter()
+                .position(|s| s.name == name)
+                .ok_or_else(|| ElfError::UnknownSection(name.to_string()))
+        }
+        fn address_of(&self, name: &str) -> Result<u64, ElfError> {
+            match self.symbol_address(name) {
+                Some(address) => address,
+                None => self
+                    .sections
+                    .iter()
+                    .find(|s| s.name == name)
+                    .map(|s| s.addr)
+                    .ok_or_else(|| ElfError::UnresolvedSymbol(name.to_string())),
+            }
+        }
+    };
+}
+
+pub mod executable;
+pub mod object;
+pub mod shared;
+pub mod stub;
+
+pub(crate) fn null_header() -> Elf64Shdr {
+    Elf64Shdr {
+        sh_name: 0,
+        sh_type: ShType::SHT_NULL,
+        sh_flags: BitFlags::empty(),
+        sh_addr: 0,
+        sh_offset: 0,
+        sh_size: 0,
+        sh_link: 0,
+        sh_info: 0,
+        sh_addralign: 0,
+        sh_entsize: 0,
+    }
+}
+
+pub(crate) fn align_up(v: u64, align: u64) -> u64 {
+    v.next_multiple_of(align.max(1))
+}
+
+/*
+ * the PT_LOAD a section is loaded in, in the order of the segments
+ */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Segment {
+    ReadOnly, // with the elf header and the program headers
+    Code,
+    Data,
+}
+
+impl Segment {
+    fn flags(&self) -> BitFlags<PFlags> {
+        match self {
+            Self::ReadOnly => PFlags::Read.into(),
+            Self::Code => PFlags::Read | PFlags::Execute,
+            Self::Data => PFlags::Read | PFlags::Write,
+        }
+    }
+    fn section_flags(&self) -> BitFlags<SHFlags> {
+        match self {
+            Self::ReadOnly => SHFlags::SHF_ALLOC.into(),
+            Self::Code => SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+            Self::Data => SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+        }
+    }
+}
+
+/*
+ * section of the executable and shared library builders, segment is None
+ * for the sections that are not loaded
+ */
+struct BuilderSection {
+    name: String,
+    segment: Option<Segment>,
+    sh_type: ShType,
+    link: String, /* Name of the sh_link section */
+    info: u32,
+    align: u64,
+    entsize: u64,
+    data: Vec<u8>,
+    size: u64, /* Larger than data for .bss */
+    tls: bool,
+    addr: u64,
+    offset: u64,
+}
+
+impl BuilderSection {
+    fn new(
+        name: &str,
+        segment: Option<Segment>,
+        sh_type: ShType,
+        align: u64,
+        data: Vec<u8>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            segment,
+            sh_type,
+            link: String::new(),
+            info: 0,
+            align,
+            entsize: 0,
+            size: data.len() as u64,
+            data,
+            tls: false,
+            addr: 0,
+            offset: 0,
+        }
+    }
+    fn nobits(name: &str, align: u64, size: u64) -> Self {
+        Self {
+            size,
+            ..Self::new(
+                name,
+                Some(Segment::Data),
+                ShType::SHT_NOBITS,
+                align,
+                Vec::new(),
+            )
+        }
+    }
+    fn linked(mut self, link: &str, info: u32, entsize: u64) -> Self {
+        self.link = link.to_string();
+        self.info = info;
+        self.entsize = entsize;
+        self
+    }
+    fn is_nobits(&self) -> bool {
+        self.sh_type == ShType::SHT_NOBITS
+    }
+    fn flags(&self) -> BitFlags<SHFlags> {
+        let flags = self.segment.map(|s| s.section_flags()).unwrap_or_default();
+        match self.tls {
+            true => flags | SHFlags::SHF_TLS,
+            false => flags,
+        }
+    }
+}
+
+/*
+ * number of PT_LOAD headers layout returns
+ */
+fn load_count(sections: &[BuilderSection]) -> usize {
+    let mut segments: Vec<Segment> = sections.iter().filter_map(|s| s.segment).collect();
+    segments.push(Segment::ReadOnly);
+    segments.sort();
+    segments.dedup();
+    segments.len()
+}
+
+/*
+ * sorts the sections by segment, keeping their order in a segment with
+ * the SHT_NOBITS ones last and the sections that are not loaded at the
+ * end, assigns offsets and addresses and returns the PT_LOAD headers.
+ * file offsets are packed and the addresses keep the page offset of their
+ * file offset, like ld does without -z separate-code
+ */
+fn layout(sections: &mut [BuilderSection], base: u64, headers_end: u64) -> Vec<Elf64_Phdr> {
+    sections.sort_by_key(|s| (s.segment.is_none(), s.segment, s.is_nobits()));
+    let mut loads = vec![Elf64_Phdr {
+        p_type: PType::PT_LOAD,
+        p_flags: Segment::ReadOnly.flags(),
+        p_offset: 0,
+        p_vaddr: base,
+        p_paddr: base,
+        p_filesz: headers_end,
+        p_memsz: headers_end,
+        p_align: PAGE_SIZE,
+    }];
+    let mut current = Segment::ReadOnly;
+    let mut offset = headers_end;
+    let mut addr = base + headers_end;
+    for section in sections.iter_mut() {
+        let segment = match section.segment {
+            Some(s) => s,
+            None => continue,
+        };
+        let nobits = section.is_nobits();
+        if segment != current {
+            current = segment;
+            offset = align_up(offset, section.align);
+            addr = align_up(addr, PAGE_SIZE) + offset % PAGE_SIZE;
+            loads.push(Elf64_Phdr {
+                p_type: PType::PT_LOAD,
+                p_flags: segment.flags(),
+                p_offset: offset,
+                p_vaddr: addr,
+                p_paddr: addr,
+                p_filesz: 0,
+                p_memsz: 0,
+                p_align: PAGE_SIZE,
+            });
+        }
+        let padding = align_up(addr, section.align) - addr;
+        addr += padding;
+        if !nobits {
+            offset += padding;
+        }
+        section.addr = addr;
+        section.offset = offset;
+        let load = loads.last_mut().unwrap();
+        if !nobits {
+            offset += section.size;
+            load.p_filesz = offset - load.p_offset;
+        }
+        addr += section.size;
+        load.p_memsz = addr - load.p_vaddr;
+    }
+    loads
+}
+
+/*
+ * a section that is laid out: file offset, address, size and alignment
+ */
+pub(crate) trait Placed {
+    fn placement(&self) -> (u64, u64, u64, u64);
+}
+
+impl Placed for BuilderSection {
+    fn placement(&self) -> (u64, u64, u64, u64) {
+        (self.offset, self.addr, self.size, self.align)
+    }
+}
+
+/*
+ * program header of a section that is already laid out
+ */
+pub(crate) fn section_program(
+    p_type: PType,
+    section: &impl Placed,
+    p_flags: BitFlags<PFlags>,
+) -> Elf64_Phdr {
+    let (offset, addr, size, align) = section.placement();
+    Elf64_Phdr {
+        p_type,
+        p_flags,
+        p_offset: offset,
+        p_vaddr: addr,
+        p_paddr: addr,
+        p_filesz: size,
+        p_memsz: size,
+        p_align: align,
+    }
+}
+
+fn gnu_stack() -> Elf64_Phdr {
+    Elf64_Phdr {
+        p_type: PType::PT_GNU_STACK,
+        p_flags: PFlags::Read | PFlags::Write,
+        p_offset: 0,
+        p_vaddr: 0,
+        p_paddr: 0,
+        p_filesz: 0,
+        p_memsz: 0,
+        p_align: 16,
+    }
+}
+
+/*
+ * applies a relocation whose symbol value is s to the field of data at
+ * r_offset, place is the address of the field
+ */
+fn relocate(data: &mut [u8], rela: &Elf64_Rela, s: u64, place: u64) -> Result<(), ElfError> {
+    let t = rela.relocation_type;
+    let value = match t {
+        /* calls to symbols of the file go to them directly, without a plt */
+        RelocationType::R_X86_64_PLT32 => Some(s.wrapping_add(rela.r_addend).wrapping_sub(place)),
+        _ => t.compute(s, rela.r_addend, place, 0),
+    }
+    .ok_or(ElfError::UnsupportedRelocation(t.into()))?;
+    if !t.fits(value) {
+        return Err(ElfError::RelocationOverflow(place));
+    }
+    let size = t
+        .field_size()
+        .ok_or(ElfError::UnsupportedRelocation(t.into()))?;
+    let offset = rela.r_offset as usize;
+    data.get_mut(offset..offset + size)
+        .ok_or(ElfError::RelocationOverflow(place))?
+        .copy_from_slice(&value.to_le_bytes()[..size]);
+    Ok(())
+}
+
+/*
+ * writes the headers, the laid out sections and, with section headers,
+ * the sections that are not loaded followed by .shstrtab. the sections
+ * get their index in the file from their position in sections
+ */
+fn write_file(
+    mut header: Elf64Ehdr,
+    programs: &[Elf64_Phdr],
+    sections: &[BuilderSection],
+    section_headers: bool,
+) -> Result<Elf64, ElfError> {
+    let mut out: Vec<u8> = Vec::new();
+    header.e_phoff = 64;
+    header.e_phnum = programs.len() as u16;
+    for (n, program) in programs.iter().enumerate() {
+        write_at(&mut out, 64 + 56 * n, &Into::<Vec<u8>>::into(program));
+    }
+    for section in sections.iter().filter(|s| s.segment.is_some()) {
+        write_at(&mut out, section.offset as usize, &section.data);
+    }
+    if section_headers {
+        let index = |name: &str| {
+            sections
+                .iter()
+                .position(|s| s.name == name)
+                .map(|i| i as u32 + 1)
+                .unwrap_or(0)
+        };
+        let mut shstrtab = StringTableBuilder::new();
+        sections.iter().for_each(|s| shstrtab.add(&s.name));
+        shstrtab.add(".shstrtab");
+        shstrtab.finalize();
+        let mut headers: Vec<Elf64Shdr> = vec![null_header()];
+        let mut offset = out.len() as u64;
+        for section in sections {
+            if section.segment.is_none() {
+                offset = align_up(offset, section.align);
+                write_at(&mut out, offset as usize, &section.data);
+            }
+            headers.push(Elf64Shdr {
+                sh_name: shstrtab.offset(&section.name)?,
+                sh_type: section.sh_type,
+                sh_flags: section.flags(),
+                sh_addr: section.addr,
+                sh_offset: match section.segment {
+                    Some(_) => section.offset,
+                    None => offset,
+                },
+                sh_size: section.size,
+                sh_link: index(&section.link),
+                sh_info: section.info,
+                sh_addralign: section.align,
+                sh_entsize: section.entsize,
+            });
+            if section.segment.is_none() {
+                offset += section.size;
+            }
+        }
+        let sh_name = shstrtab.offset(".shstrtab")?;
+        headers.push(Elf64Shdr {
+            sh_name,
+            sh_type: ShType::SHT_STRTAB,
+            sh_offset: offset,
+            sh_size: shstrtab.len() as u64,
+            sh_addralign: 1,
+            ..null_header()
+        });
+        write_at(&mut out, offset as usize, shstrtab.data());
+        offset += shstrtab.len() as u64;
+        header.e_shoff = align_up(offset, 8);
+        header.e_shnum = headers.len() as u16;
+        header.e_shstrndx = headers.len() as u16 - 1;
+        for (n, h) in headers.iter().enumerate() {
+            write_at(
+                &mut out,
+                header.e_shoff as usize + 64 * n,
+                &Into::<Vec<u8>>::into(h),
+            );
+        }
+    }
+    write_at(&mut out, 0, &Into::<Vec<u8>>::into(&header));
+    Elf64::parse(&out)
+        .map(|(_, elf)| elf)
+        .map_err(|_| ElfError::Truncated("built file"))
+}
@@ -0,0 +1,427 @@
+use super::super::common::write_at;
+use super::super::elf64::Elf64;
+use super::super::error::ElfError;
+use super::super::header::e_type::EType;
+use super::super::header::header::Elf64Ehdr;
+use super::super::section::section_header::Elf64Shdr;
+use super::super::section::sh_flags::SHFlags;
+use super::super::section::sh_type::ShType;
+use super::super::section::string_table::StringTableBuilder;
+use super::super::section::symbol::{
+    Elf64_Rela, Elf64_Sym, SectionIndex, SymbolBinding, SymbolType,
+};
+use super::null_header;
+use enumflags2::BitFlags;
+
+const SHN_COMMON: u16 = 0xfff2;
+
+/*
+ * section names with a meaning for the placement of a symbol, as printed
+ * by objdump
+ */
+pub const ABS_SECTION: &str = "*ABS*";
+pub const COMMON_SECTION: &str = "*COM*";
+
+struct ObjectSection {
+    name: String,
+    sh_type: ShType,
+    flags: BitFlags<SHFlags>,
+    align: u64,
+    data: Vec<u8>,
+    size: u64,
+    relocations: Vec<(String, Elf64_Rela)>, /* Symbol name and relocation */
+}
+
+struct ObjectSymbol {
+    name: String,
+    section: Option<String>,
+    value: u64,
+    size: u64,
+    symbol_type: SymbolType,
+    binding: SymbolBinding,
+}
+
+/*
+ * builds an ET_REL file the way gas lays them out: the sections in the
+ * order they were added, each .rela section after the section it applies
+ * to, then .symtab, .strtab and .shstrtab. .symtab starts with the file
+ * symbol and one STT_SECTION symbol per section, locals are before the
+ * global and weak symbols
+ */
+#[derive(Default)]
+pub struct ObjectBuilder {
+    file_name: Option<String>,
+    sections: Vec<ObjectSection>,
+    symbols: Vec<ObjectSymbol>,
+}
+
+/*
+ * the sh_type gas gives a section from its name
+ */
+fn section_type(name: &str) -> ShType {
+    match name {
+        ".init_array" => ShType::SHT_INIT_ARRAY,
+        ".fini_array" => ShType::SHT_FINI_ARRAY,
+        ".preinit_array" => ShType::SHT_PREINIT_ARRAY,
+        n if n.starts_with(".init_array.") => ShType::SHT_INIT_ARRAY,
+        n if n.starts_with(".fini_array.") => ShType::SHT_FINI_ARRAY,
+        n if n.starts_with(".note") && n != ".note.GNU-stack" => ShType::SHT_NOTE,
+        _ => ShType::SHT_PROGBITS,
+    }
+}
+
+impl ObjectBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /*
+     * name of the STT_FILE symbol, ld prints it in its messages
+     */
+    pub fn file_name(mut self, name: &str) -> Self {
+        self.file_name = Some(name.to_string());
+        self
+    }
+    pub fn add_section(
+        mut self,
+        name: &str,
+        flags: BitFlags<SHFlags>,
+        align: u64,
+        data: Vec<u8>,
+    ) -> Self {
+        self.sections.push(ObjectSection {
+            name: name.to_string(),
+            sh_type: section_type(name),
+            flags,
+            align,
+            size: data.len() as u64,
+            data,
+            relocations: Vec::new(),
+        });
+        self
+    }
+    /*
+     * SHT_NOBITS section like .bss, only its size is stored
+     */
+    pub fn add_nobits_section(
+        mut self,
+        name: &str,
+        flags: BitFlags<SHFlags>,
+        align: u64,
+        size: u64,
+    ) -> Self {
+        self.sections.push(ObjectSection {
+            name: name.to_string(),
+            sh_type: ShType::SHT_NOBITS,
+            flags,
+            align,
+            data: Vec::new(),
+            size,
+            relocations: Vec::new(),
+        });
+        self
+    }
+    /*
+     * section None is an undefined symbol, ABS_SECTION an absolute value
+     * and COMMON_SECTION a common symbol whose value is its alignment
+     */
+    pub fn add_symbol(
+        mut self,
+        name: &str,
+        section: Option<&str>,
+        value: u64,
+        size: u64,
+        symbol_type: SymbolType,
+        binding: SymbolBinding,
+    ) -> Self {
+        self.symbols.push(ObjectSymbol {
+            name: name.to_string(),
+            section: section.map(|s| s.to_string()),
+            value,
+            size,
+            symbol_type,
+            binding,
+        });
+        self
+    }
+    /*
+     * symbol_index of the relocation is ignored, the symbol is found by
+     * name among the symbols and then the section names (for section
+     * relative relocations). names that are neither become undefined
+     * global symbols like in gas
+     */
+    pub fn add_relocation(mut self, section: &str, symbol: &str, relocation: Elf64_Rela) -> Self {
+        if let Some(s) = self.sections.iter_mut().rev().find(|s| s.name == section) {
+            s.relocations.push((symbol.to_string(), relocation));
+        } else {
+            /* kept so build can report the missing section */
+            self.sections.push(ObjectSection {
+                name: section.to_string(),
+                sh_type: ShType::SHT_NULL,
+                flags: BitFlags::empty(),
+                align: 0,
+                data: Vec::new(),
+                size: 0,
+                relocations: Vec::new(),
+            });
+        }
+        self
+    }
+    /*
+     * file_index holds the index in the file of every added section
+     */
+    fn symbol_table(&self, file_index: &[u16]) -> Result<(Vec<Elf64_Sym>, usize), ElfError> {
+        let mut symbols = vec![Elf64_Sym::new(
+            "",
+            SymbolBinding::STB_LOCAL,
+            SymbolType::STT_NOTYPE,
+            0,
+            0,
+            0,
+        )];
+        if let Some(file) = &self.file_name {
+            symbols.push(Elf64_Sym::new(
+                file,
+                SymbolBinding::STB_LOCAL,
+                SymbolType::STT_FILE,
+                SectionIndex::SHN_ABS.into(),
+                0,
+                0,
+            ));
+        }
+        for index in file_index {
+            symbols.push(Elf64_Sym::new(
+                "",
+                SymbolBinding::STB_LOCAL,
+                SymbolType::STT_SECTION,
+                *index,
+                0,
+                0,
+            ));
+        }
+        let mut globals: Vec<Elf64_Sym> = Vec::new();
+        for symbol in &self.symbols {
+            let defined = symbol.section.is_some();
+            let duplicate = defined
+                && self
+                    .symbols
+                    .iter()
+                    .filter(|s| s.name == symbol.name && s.section.is_some())
+                    .count()
+                    > 1;
+            if duplicate && symbol.binding != SymbolBinding::STB_LOCAL {
+                return Err(ElfError::DuplicateSymbol(symbol.name.clone()));
+            }
+            let st_shndx = match symbol.section.as_deref() {
+                None => 0,
+                Some(ABS_SECTION) => SectionIndex::SHN_ABS.into(),
+                Some(COMMON_SECTION) => SHN_COMMON,
+                Some(name) => self
+                    .sections
+                    .iter()
+                    .position(|s| s.name == name)
+                    .map(|i| file_index[i])
+                    .ok_or_else(|| ElfError::UnknownSection(name.to_string()))?,
+            };
+            let s = Elf64_Sym::new(
+                &symbol.name,
+                symbol.binding,
+                symbol.symbol_type,
+                st_shndx,
+                symbol.value,
+                symbol.size,
+            );
+            match symbol.binding {
+                SymbolBinding::STB_LOCAL => symbols.push(s),
+                _ => globals.push(s),
+            }
+        }
+        let first_global = symbols.len();
+        symbols.extend(globals);
+        for section in &self.sections {
+            for (name, _) in &section.relocations {
+                let known = symbols.iter().any(|s| s.name == *name)
+                    || self.sections.iter().any(|s| s.name == *name);
+                if !known {
+                    symbols.push(Elf64_Sym::new(
+                        name,
+                        SymbolBinding::STB_GLOBAL,
+                        SymbolType::STT_NOTYPE,
+                        0,
+                        0,
+                        0,
+                    ));
+                }
+            }
+        }
+        Ok((symbols, first_global))
+    }
+    /*
+     * index in .symtab of a symbol name, or of the STT_SECTION symbol of
+     * the i-th added section at first_section_symbol + i
+     */
+    fn symbol_index(&self, symbols: &[Elf64_Sym], name: &str, first_section_symbol: usize) -> u32 {
+        match symbols
+            .iter()
+            .position(|s| s.name == name && s.symbol_type != SymbolType::STT_FILE)
+        {
+            Some(i) => i as u32,
+            None => {
+                let section = self.sections.iter().position(|s| s.name == name).unwrap();
+                (first_section_symbol + section) as u32
+            }
+        }
+    }
+    pub fn build(mut self) -> Result<Elf64, ElfError> {
+        if let Some(s) = self.sections.iter().find(|s| s.sh_type == ShType::SHT_NULL) {
+            return Err(ElfError::UnknownSection(s.name.clone()));
+        }
+        /* without it ld warns about an executable stack */
+        if !self.sections.iter().any(|s| s.name == ".note.GNU-stack") {
+            self = self.add_section(".note.GNU-stack", BitFlags::empty(), 1, Vec::new());
+        }
+        /*
+         * section indexes in the file: user sections with their .rela
+         * sections right after them
+         */
+        let mut file_index: Vec<u16> = Vec::new();
+        let mut next = 1u16;
+        for section in &self.sections {
+            file_index.push(next);
+            next += 1 + !section.relocations.is_empty() as u16;
+        }
+        let symtab_index = next as u32;
+        let (mut symbols, first_global) = self.symbol_table(&file_index)?;
+        let first_section_symbol = 1 + self.file_name.is_some() as usize;
+        let mut strtab = StringTableBuilder::new();
+        symbols.iter().for_each(|s| strtab.add(&s.name));
+        strtab.finalize();
+        for s in symbols.iter_mut() {
+            s.st_name = strtab.offset(&s.name)?;
+        }
+        let mut shstrtab = StringTableBuilder::new();
+        for section in &self.sections {
+            shstrtab.add(&section.name);
+            if !section.relocations.is_empty() {
+                shstrtab.add(&format!(".rela{}", section.name));
+            }
+        }
+        for name in [".symtab", ".strtab", ".shstrtab"] {
+            shstrtab.add(name);
+        }
+        shstrtab.finalize();
+        let mut out: Vec<u8> = Vec::new();
+        let mut offset = 64u64;
+        let mut headers: Vec<Elf64Shdr> = vec![null_header()];
+        let mut place = |headers: &mut Vec<Elf64Shdr>, mut header: Elf64Shdr, data: &[u8]| {
+            offset = offset.next_multiple_of(header.sh_addralign.max(1));
+            header.sh_offset = offset;
+            if header.sh_type != ShType::SHT_NOBITS {
+                write_at(&mut out, offset as usize, data);
+                offset += data.len() as u64;
+            }
+            headers.push(header);
+        };
+        for (i, section) in self.sections.iter().enumerate() {
+            let header = Elf64Shdr {
+                sh_name: shstrtab.offset(&section.name)?,
+                sh_type: section.sh_type,
+                sh_flags: section.flags,
+                sh_addr: 0,
+                sh_offset: 0,
+                sh_size: section.size,
+                sh_link: 0,
+                sh_info: 0,
+                sh_addralign: section.align,
+                sh_entsize: match section.sh_type {
+                    ShType::SHT_INIT_ARRAY | ShType::SHT_FINI_ARRAY | ShType::SHT_PREINIT_ARRAY => {
+                        8
+                    }
+                    _ => 0,
+                },
+            };
+            place(&mut headers, header, &section.data);
+            if section.relocations.is_empty() {
+                continue;
+            }
+            let data: Vec<u8> = section
+                .relocations
+                .iter()
+                .map(|(name, r)| {
+                    let index = self.symbol_index(&symbols, name, first_section_symbol);
+                    Elf64_Rela::new(r.r_offset, r.relocation_type, index, r.r_addend)
+                })
+                .flat_map::<Vec<u8>, _>(|r| (&r).into())
+                .collect();
+            let header = Elf64Shdr {
+                sh_name: shstrtab.offset(&format!(".rela{}", section.name))?,
+                sh_type: ShType::SHT_RELA,
+                sh_flags: SHFlags::SHF_INFO_LINK.into(),
+                sh_addr: 0,
+                sh_offset: 0,
+                sh_size: data.len() as u64,
+                sh_link: symtab_index,
+                sh_info: file_index[i] as u32,
+                sh_addralign: 8,
+                sh_entsize: 24,
+            };
+            place(&mut headers, header, &data);
+        }
+        let symtab: Vec<u8> = symbols
+            .iter()
+            .flat_map::<Vec<u8>, _>(|s| s.into())
+            .collect();
+        let header = Elf64Shdr {
+            sh_name: shstrtab.offset(".symtab")?,
+            sh_type: ShType::SHT_SYMTAB,
+            sh_flags: BitFlags::empty(),
+            sh_addr: 0,
+            sh_offset: 0,
+            sh_size: symtab.len() as u64,
+            sh_link: symtab_index + 1,
+            sh_info: first_global as u32,
+            sh_addralign: 8,
+            sh_entsize: 24,
+        };
+        place(&mut headers, header, &symtab);
+        let header = Elf64Shdr {
+            sh_name: shstrtab.offset(".strtab")?,
+            sh_type: ShType::SHT_STRTAB,
+            sh_flags: BitFlags::empty(),
+            sh_addr: 0,
+            sh_offset: 0,
+            sh_size: strtab.len() as u64,
+            sh_link: 0,
+            sh_info: 0,
+            sh_addralign: 1,
+            sh_entsize: 0,
+        };
+        place(&mut headers, header, strtab.data());
+        let sh_name = shstrtab.offset(".shstrtab")?;
+        let header = Elf64Shdr {
+            sh_name,
+            sh_type: ShType::SHT_STRTAB,
+            sh_flags: BitFlags::empty(),
+            sh_addr: 0,
+            sh_offset: 0,
+            sh_size: shstrtab.len() as u64,
+            sh_link: 0,
+            sh_info: 0,
+            sh_addralign: 1,
+            sh_entsize: 0,
+        };
+        place(&mut headers, header, shstrtab.data());
+        let mut header = Elf64Ehdr::new(EType::ET_REL);
+        header.e_shoff = offset.next_multiple_of(8);
+        header.e_shnum = headers.len() as u16;
+        header.e_shstrndx = headers.len() as u16 - 1;
+        let mut shoff = header.e_shoff as usize;
+        for h in &headers {
+            write_at(&mut out, shoff, &Into::<Vec<u8>>::into(h));
+            shoff += 64;
+        }
+        write_at(&mut out, 0, &Into::<Vec<u8>>::into(&header));
+        Elf64::parse(&out)
+            .map(|(_, elf)| elf)
+            .map_err(|_| ElfError::Truncated("built object"))
+    }
+}
@@ -0,0 +1,660 @@
+use super::super::elf64::Elf64;
+use super::super::error::ElfError;
+use super::super::header::e_ident::EI_OSABI;
+use super::super::header::e_type::EType;
+use super::super::header::header::Elf64Ehdr;
+use super::super::program::p_type::PType;
+use super::super::program::program_header::PFlags;
+use super::super::section::elf64_dyn::{DynamicSection, DynamicTag, Elf64_Dyn};
+use super::super::section::sh_type::ShType;
+use super::super::section::string_table::StringTableBuilder;
+use super::super::section::symbol::{
+    Elf64_Rela, Elf64_Sym, RelocationType, SectionIndex, SymbolBinding, SymbolType,
+    SymbolVisibility,
+};
+use super::super::section::version::{
+    Elf64_Verdef, SymbolVersion, VERSYM_HIDDEN, VER_FLG_BASE, VER_NDX_GLOBAL,
+};
+use super::object::ABS_SECTION;
+use super::{
+    gnu_stack, layout, load_count, relocate, section_program, write_file, BuilderSection, Segment,
+};
+
+const DF_TEXTREL: u64 = 4;
+const BLOOM_SHIFT: u32 = 6;
+
+/*
+ * hash of DT_HASH tables
+ */
+pub fn elf_hash(name: &str) -> u32 {
+    let mut h: u32 = 0;
+    for c in name.bytes() {
+        h = (h << 4).wrapping_add(c as u32);
+        let g = h & 0xf0000000;
+        h ^= g >> 24;
+        h &= !g;
+    }
+    h
+}
+
+/*
+ * hash of DT_GNU_HASH tables
+ */
+pub fn gnu_hash(name: &str) -> u32 {
+    name.bytes()
+        .fold(5381u32, |h, c| h.wrapping_mul(33).wrapping_add(c as u32))
+}
+
+struct SharedSymbol {
+    name: String,
+    section: String,
+    offset: u64,
+    size: u64,
+    symbol_type: SymbolType,
+    binding: SymbolBinding,
+    visibility: SymbolVisibility,
+    version: Option<SymbolVersion>,
+}
+
+/*
+ * builds an ET_DYN that ld.so can load. the defined global and weak
+ * symbols are exported through .dynsym with .hash and .gnu.hash tables.
+ * R_X86_64_64 and R_X86_64_GLOB_DAT against a symbol of the library
+ * become an R_X86_64_RELATIVE, the narrower absolute relocations cannot
+ * hold an address of a library loaded anywhere and are rejected like ld
+ * does, the other ones are pc relative and resolved at build time.
+ * relocations against names that are not defined here import them from
+ * the DT_NEEDED libraries, only R_X86_64_64 and R_X86_64_GLOB_DAT can do
+ * that. the versions of the exported symbols are defined in
+ * .gnu.version_d
+ */
+#[derive(Default)]
+pub struct SharedLibraryBuilder {
+    soname: Option<String>,
+    needed: Vec<String>,
+    sections: Vec<BuilderSection>,
+    symbols: Vec<SharedSymbol>,
+    relocations: Vec<(String, String, Elf64_Rela)>, /* Section, symbol and relocation */
+}
+
+/*
+ * relocation left to ld.so
+ */
+struct DynamicRelocation {
+    section: String,
+    r_offset: u64,
+    relocation_type: RelocationType,
+    symbol: String, /* Imported symbol, empty for R_X86_64_RELATIVE */
+    target: String, /* Symbol of the R_X86_64_64 an R_X86_64_RELATIVE replaces */
+    r_addend: u64,
+}
+
+impl SharedLibraryBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn soname(mut self, name: &str) -> Self {
+        self.soname = Some(name.to_string());
+        self
+    }
+    pub fn needed(mut self, library: &str) -> Self {
+        self.needed.push(library.to_string());
+        self
+    }
+    section_methods!();
+    /*
+     * SHT_NOBITS section of the PT_TLS segment, the value of the symbols
+     * in it is their offset in the segment
+     */
+    pub fn add_tbss(mut self, name: &str, align: u64, size: u64) -> Self {
+        let mut section = BuilderSection::nobits(name, align, size);
+        section.tls = true;
+        self.sections.push(section);
+        self
+    }
+    /*
+     * symbol at offset in section, or with the value offset in ABS_SECTION.
+     * the STB_LOCAL ones are only used by the relocations and are not
+     * exported, neither are the STV_HIDDEN and STV_INTERNAL ones. a
+     * foo@VER or foo@@VER name gives the symbol that version like .symver
+     * does
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_symbol(
+        mut self,
+        name: &str,
+        section: &str,
+        offset: u64,
+        size: u64,
+        symbol_type: SymbolType,
+        binding: SymbolBinding,
+        visibility: SymbolVisibility,
+    ) -> Self {
+        let (name, version) = match name.split_once('@') {
+            Some((base, v)) => (
+                base,
+                Some(SymbolVersion {
+                    name: v.trim_start_matches('@').to_string(),
+                    hidden: !v.starts_with('@'),
+                    file: None,
+                }),
+            ),
+            None => (name, None),
+        };
+        self.symbols.push(SharedSymbol {
+            name: name.to_string(),
+            section: section.to_string(),
+            offset,
+            size,
+            symbol_type,
+            binding,
+            visibility,
+            version,
+        });
+        self
+    }
+    fn is_defined(&self, name: &str) -> bool {
+        self.symbols.iter().any(|s| s.name == name) || self.sections.iter().any(|s| s.name == name)
+    }
+    /*
+     * st_shndx and st_value of a symbol once the sections are laid out
+     */
+    fn symbol_value(&self, symbol: &SharedSymbol) -> Result<(u16, u64), ElfError> {
+        if symbol.section == ABS_SECTION {
+            return Ok((SectionIndex::SHN_ABS.into(), symbol.offset));
+        }
+        let index = self.section(&symbol.section)?;
+        let section = &self.sections[index];
+        let base = match section.tls {
+            true => self.tls_start(),
+            false => 0,
+        };
+        Ok((index as u16 + 1, section.addr - base + symbol.offset))
+    }
+    fn tls_start(&self) -> u64 {
+        self.sections
+            .iter()
+            .find(|s| s.tls)
+            .map(|s| s.addr)
+            .unwrap_or(0)
+    }
+    fn symbol_address(&self, name: &str) -> Option<Result<u64, ElfError>> {
+        let symbol = self.symbols.iter().find(|s| s.name == name)?;
+        Some(self.symbol_value(symbol).map(|(_, value)| value))
+    }
+    /*
+     * .dynsym entries: the imports and then the exports sorted by their
+     * .gnu.hash bucket, with the index of the exported symbol. returns the
+     * entries, the number of imports and the number of buckets
+     */
+    fn dynamic_symbols(&self) -> (Vec<(String, Option<usize>)>, usize, u32) {
+        let mut imports: Vec<(String, Option<usize>)> = Vec::new();
+        for (_, symbol, _) in &self.relocations {
+            if !self.is_defined(symbol) && !imports.iter().any(|(n, _)| n == symbol) {
+                imports.push((symbol.clone(), None));
+            }
+        }
+        let mut exports: Vec<(String, Option<usize>)> = Vec::new();
+        for (i, symbol) in self.symbols.iter().enumerate() {
+            let duplicate = self.symbols[..i]
+                .iter()
+                .any(|s| s.name == symbol.name && s.version == symbol.version);
+            let hidden = matches!(
+                symbol.visibility,
+                SymbolVisibility::STV_HIDDEN | SymbolVisibility::STV_INTERNAL
+            );
+            if symbol.binding != SymbolBinding::STB_LOCAL && !hidden && !duplicate {
+                exports.push((symbol.name.clone(), Some(i)));
+            }
+        }
+        let buckets = (exports.len() as u32 / 2).max(1);
+        exports.sort_by_key(|(name, _)| gnu_hash(name) % buckets);
+        let count = imports.len();
+        imports.extend(exports);
+        (imports, count, buckets)
+    }
+    /*
+     * names of the versions of the exported symbols, they get the version
+     * indexes from 2
+     */
+    fn version_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for symbol in &self.symbols {
+            if let Some(v) = &symbol.version {
+                if !names.contains(&v.name) {
+                    names.push(v.name.clone());
+                }
+            }
+        }
+        names
+    }
+    fn versym_section(&self, entries: &[(String, Option<usize>)], versions: &[String]) -> Vec<u8> {
+        let mut out: Vec<u8> = vec![0; 2];
+        for (_, index) in entries {
+            let version = index.and_then(|i| self.symbols[i].version.as_ref());
+            let versym = match version {
+                Some(v) => {
+                    let ndx = versions.iter().position(|n| *n == v.name).unwrap() as u16 + 2;
+                    match v.hidden {
+                        true => ndx | VERSYM_HIDDEN,
+                        false => ndx,
+                    }
+                }
+                None => VER_NDX_GLOBAL,
+            };
+            out.extend(versym.to_le_bytes());
+        }
+        out
+    }
+    /*
+     * the base version named after the file followed by the versions, each
+     * with a single Elf64_Verdaux
+     */
+    fn verdef_section(names: &[(String, u32)]) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        for (i, (name, offset)) in names.iter().enumerate() {
+            let verdef = Elf64_Verdef {
+                vd_version: 1,
+                vd_flags: match i {
+                    0 => VER_FLG_BASE,
+                    _ => 0,
+                },
+                vd_ndx: i as u16 + 1,
+                vd_cnt: 1,
+                vd_hash: elf_hash(name),
+                vd_aux: 20,
+                vd_next: match i + 1 == names.len() {
+                    true => 0,
+                    false => 28,
+                },
+            };
+            out.extend(Into::<Vec<u8>>::into(&verdef));
+            out.extend(offset.to_le_bytes());
+            out.extend(0u32.to_le_bytes());
+        }
+        out
+    }
+    fn dynamic_relocations(&self) -> Result<Vec<DynamicRelocation>, ElfError> {
+        let mut relocations: Vec<DynamicRelocation> = Vec::new();
+        for (section, symbol, rela) in &self.relocations {
+            self.section(section)?;
+            let section = section.clone();
+            let t = rela.relocation_type;
+            let relocation = match (self.is_defined(symbol), t) {
+                (true, RelocationType::R_X86_64_64 | RelocationType::R_X86_64_GLOB_DAT) => {
+                    DynamicRelocation {
+                    section,
+                    r_offset: rela.r_offset,
+                    relocation_type: RelocationType::R_X86_64_RELATIVE,
+                    symbol: String::new(),
+                    r_addend: rela.r_addend,
+                        target: symbol.clone(),
+                    }
+                }
+                (
+                    true,
+                    RelocationType::R_X86_64_32
+                    | RelocationType::R_X86_64_32S
+                    | RelocationType::R_X86_64_16
+                    | RelocationType::R_X86_64_8,
+                ) => {
+                    return Err(ElfError::Link(vec![format!(
+                        "relocation {:?} against symbol `{}' can not be used when making a shared object; recompile with -fPIC",
+                        t, symbol
+                    )]))
+                }
+                (true, _) => continue,
+                (false, RelocationType::R_X86_64_64 | RelocationType::R_X86_64_GLOB_DAT) => {
+                    DynamicRelocation {
+                        section,
+                        r_offset: rela.r_offset,
+                        relocation_type: t,
+                        symbol: symbol.clone(),
+                        r_addend: rela.r_addend,
+                        target: String::new(),
+                    }
+                }
+                (false, _) => return Err(ElfError::UnsupportedRelocation(t.into())),
+            };
+            relocations.push(relocation);
+        }
+        /* ld.so counts the leading R_X86_64_RELATIVE with DT_RELACOUNT */
+        relocations.sort_by_key(|r| r.relocation_type != RelocationType::R_X86_64_RELATIVE);
+        Ok(relocations)
+    }
+    fn hash_section(names: &[String]) -> Vec<u8> {
+        let buckets = names.len().max(1) as u32;
+        let mut bucket = vec![0u32; buckets as usize];
+        let mut chain = vec![0u32; names.len() + 1];
+        for (i, name) in names.iter().enumerate() {
+            let b = (elf_hash(name) % buckets) as usize;
+            chain[i + 1] = bucket[b];
+            bucket[b] = i as u32 + 1;
+        }
+        [buckets, chain.len() as u32]
+            .into_iter()
+            .chain(bucket)
+            .chain(chain)
+            .flat_map(|v| v.to_le_bytes())
+            .collect()
+    }
+    fn gnu_hash_section(names: &[String], imports: usize, buckets: u32) -> Vec<u8> {
+        let exports = &names[imports..];
+        let symoffset = imports as u32 + 1;
+        let bloom_size = (exports.len() / 32 + 1).next_power_of_two();
+        let mut bloom = vec![0u64; bloom_size];
+        let mut bucket = vec![0u32; buckets as usize];
+        let mut chain: Vec<u32> = Vec::new();
+        for (i, name) in exports.iter().enumerate() {
+            let h = gnu_hash(name);
+            bloom[(h as usize / 64) % bloom_size] |=
+                (1 << (h % 64)) | (1 << ((h >> BLOOM_SHIFT) % 64));
+            let b = (h % buckets) as usize;
+            if bucket[b] == 0 {
+                bucket[b] = symoffset + i as u32;
+            }
+            /* the last symbol of a bucket has the low bit set */
+            let last = exports
+                .get(i + 1)
+                .map(|n| gnu_hash(n) % buckets != b as u32)
+                .unwrap_or(true);
+            chain.push((h & !1) | last as u32);
+        }
+        let mut out: Vec<u8> = [buckets, symoffset, bloom_size as u32, BLOOM_SHIFT]
+            .into_iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        out.extend(bloom.into_iter().flat_map(|v| v.to_le_bytes()));
+        out.extend(
+            bucket
+                .into_iter()
+                .chain(chain)
+                .flat_map(|v| v.to_le_bytes()),
+        );
+        out
+    }
+    fn set_data(&mut self, name: &str, data: Vec<u8>) {
+        if let Some(section) = self.sections.iter_mut().find(|s| s.name == name) {
+            section.data = data;
+        }
+    }
+    pub fn build(mut self) -> Result<Elf64, ElfError> {
+        let (entries, imports, buckets) = self.dynamic_symbols();
+        let names: Vec<String> = entries.iter().map(|(n, _)| n.clone()).collect();
+        let relocations = self.dynamic_relocations()?;
+        let versions = self.version_names();
+        let mut dynstr = StringTableBuilder::new();
+        self.needed.iter().for_each(|n| dynstr.add(n));
+        self.soname.iter().for_each(|n| dynstr.add(n));
+        names.iter().for_each(|n| dynstr.add(n));
+        versions.iter().for_each(|v| dynstr.add(v));
+        dynstr.finalize();
+        let needed: Vec<u32> = self
+            .needed
+            .iter()
+            .map(|n| dynstr.offset(n))
+            .collect::<Result<_, _>>()?;
+        let soname = self.soname.as_ref().map(|n| dynstr.offset(n)).transpose()?;
+        let name_offsets: Vec<u32> = names
+            .iter()
+            .map(|n| dynstr.offset(n))
+            .collect::<Result<_, _>>()?;
+        let verdefs: Vec<(String, u32)> = match versions.is_empty() {
+            true => Vec::new(),
+            false => {
+                let base = self.soname.clone().unwrap_or_default();
+                let mut verdefs = vec![(base, soname.unwrap_or(0))];
+                for v in &versions {
+                    verdefs.push((v.clone(), dynstr.offset(v)?));
+                }
+                verdefs
+            }
+        };
+        let tls = self.sections.iter().any(|s| s.tls);
+        let textrel = relocations.iter().any(|r| {
+            self.sections
+                .iter()
+                .any(|s| s.name == r.section && s.segment != Some(Segment::Data))
+        });
+        /*
+         * .dynamic entries, the addresses are filled in after the layout
+         */
+        let mut dynamic: Vec<(DynamicTag, u64)> = Vec::new();
+        dynamic.extend(needed.iter().map(|n| (DynamicTag::DT_NEEDED, *n as u64)));
+        dynamic.extend(soname.map(|n| (DynamicTag::DT_SONAME, n as u64)));
+        dynamic.extend([
+            (DynamicTag::DT_HASH, 0),
+            (DynamicTag::DT_GNU_HASH, 0),
+            (DynamicTag::DT_STRTAB, 0),
+            (DynamicTag::DT_SYMTAB, 0),
+            (DynamicTag::DT_STRSZ, dynstr.len() as u64),
+            (DynamicTag::DT_SYMENT, 24),
+        ]);
+        if !verdefs.is_empty() {
+            dynamic.extend([
+                (DynamicTag::DT_VERSYM, 0),
+                (DynamicTag::DT_VERDEF, 0),
+                (DynamicTag::DT_VERDEFNUM, verdefs.len() as u64),
+            ]);
+        }
+        if !relocations.is_empty() {
+            dynamic.extend([
+                (DynamicTag::DT_RELA, 0),
+                (DynamicTag::DT_RELASZ, 24 * relocations.len() as u64),
+                (DynamicTag::DT_RELAENT, 24),
+            ]);
+        }
+        if textrel {
+            dynamic.push((DynamicTag::DT_TEXTREL, 0));
+            dynamic.push((DynamicTag::DT_FLAGS, DF_TEXTREL));
+        }
+        let relative = relocations
+            .iter()
+            .filter(|r| r.relocation_type == RelocationType::R_X86_64_RELATIVE)
+            .count();
+        if relative != 0 {
+            dynamic.push((DynamicTag::DT_RELA_COUNT, relative as u64));
+        }
+        dynamic.push((DynamicTag::DT_NULL, 0));
+        /*
+         * the tables go before the sections of the library, sorting by
+         * segment puts .dynamic at the start of the data segment
+         */
+        let dynsym_size = 24 * (names.len() + 1);
+        let mut synthetic = vec![
+            BuilderSection::new(
+                ".hash",
+                Some(Segment::ReadOnly),
+                ShType::SHT_HASH,
+                8,
+                Self::hash_section(&names),
+            )
+            .linked(".dynsym", 0, 4),
+            BuilderSection::new(
+                ".gnu.hash",
+                Some(Segment::ReadOnly),
+                ShType::SHT_GNU_hash,
+                8,
+                Self::gnu_hash_section(&names, imports, buckets),
+            )
+            .linked(".dynsym", 0, 0),
+            BuilderSection::new(
+                ".dynsym",
+                Some(Segment::ReadOnly),
+                ShType::SHT_DYNSYM,
+                8,
+                vec![0; dynsym_size],
+            )
+            .linked(".dynstr", 1, 24),
+            BuilderSection::new(
+                ".dynstr",
+                Some(Segment::ReadOnly),
+                ShType::SHT_STRTAB,
+                1,
+                dynstr.data().to_vec(),
+            ),
+        ];
+        if !verdefs.is_empty() {
+            synthetic.push(
+                BuilderSection::new(
+                    ".gnu.version",
+                    Some(Segment::ReadOnly),
+                    ShType::SHT_GNU_versym,
+                    2,
+                    self.versym_section(&entries, &versions),
+                )
+                .linked(".dynsym", 0, 2),
+            );
+            synthetic.push(
+                BuilderSection::new(
+                    ".gnu.version_d",
+                    Some(Segment::ReadOnly),
+                    ShType::SHT_GNU_verdef,
+                    8,
+                    Self::verdef_section(&verdefs),
+                )
+                .linked(".dynstr", verdefs.len() as u32, 0),
+            );
+        }
+        if !relocations.is_empty() {
+            synthetic.push(
+                BuilderSection::new(
+                    ".rela.dyn",
+                    Some(Segment::ReadOnly),
+                    ShType::SHT_RELA,
+                    8,
+                    vec![0; 24 * relocations.len()],
+                )
+                .linked(".dynsym", 0, 24),
+            );
+        }
+        synthetic.push(
+            BuilderSection::new(
+                ".dynamic",
+                Some(Segment::Data),
+                ShType::SHT_DYNAMIC,
+                8,
+                vec![0; 16 * dynamic.len()],
+            )
+            .linked(".dynstr", 0, 16),
+        );
+        self.sections.splice(0..0, synthetic);
+        let phnum = load_count(&self.sections) + 2 + tls as usize;
+        let loads = layout(&mut self.sections, 0, 64 + 56 * phnum as u64);
+        /*
+         * the sections are in their final order now
+         */
+        let mut dynsym: Vec<u8> = vec![0; 24];
+        for (i, (name, index)) in entries.iter().enumerate() {
+            let mut s = match index {
+                Some(index) => {
+                    let symbol = &self.symbols[*index];
+                    let (st_shndx, value) = self.symbol_value(symbol)?;
+                    let mut s = Elf64_Sym::new(
+                        name,
+                        symbol.binding,
+                        symbol.symbol_type,
+                        st_shndx,
+                        value,
+                        symbol.size,
+                    );
+                    s.st_other = symbol.visibility.into();
+                    s
+                }
+                None => Elf64_Sym::new(
+                    name,
+                    SymbolBinding::STB_GLOBAL,
+                    SymbolType::STT_NOTYPE,
+                    0,
+                    0,
+                    0,
+                ),
+            };
+            s.st_name = name_offsets[i];
+            dynsym.extend(Into::<Vec<u8>>::into(&s));
+        }
+        let mut rela_dyn: Vec<u8> = Vec::new();
+        for r in &relocations {
+            let place = self.sections[self.section(&r.section)?].addr + r.r_offset;
+            let rela = match r.relocation_type {
+                RelocationType::R_X86_64_RELATIVE => Elf64_Rela::new(
+                    place,
+                    r.relocation_type,
+                    0,
+                    self.address_of(&r.target)?.wrapping_add(r.r_addend),
+                ),
+                t => {
+                    let index = names.iter().position(|n| *n == r.symbol).unwrap() + 1;
+                    Elf64_Rela::new(place, t, index as u32, r.r_addend)
+                }
+            };
+            rela_dyn.extend(Into::<Vec<u8>>::into(&rela));
+        }
+        for (section, symbol, rela) in std::mem::take(&mut self.relocations) {
+            let is_dynamic = !self.is_defined(&symbol)
+                || matches!(
+                    rela.relocation_type,
+                    RelocationType::R_X86_64_64 | RelocationType::R_X86_64_GLOB_DAT
+                );
+            if is_dynamic {
+                continue;
+            }
+            let index = self.section(&section)?;
+            let s = self.address_of(&symbol)?;
+            let place = self.sections[index].addr + rela.r_offset;
+            relocate(&mut self.sections[index].data, &rela, s, place)?;
+        }
+        let address = |name: &str| {
+            self.sections
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| s.addr)
+                .unwrap_or(0)
+        };
+        let dynamic = DynamicSection {
+            entries: dynamic
+                .into_iter()
+                .map(|(d_tag, value)| Elf64_Dyn {
+                    d_tag,
+                    val_ptr: match d_tag {
+                        DynamicTag::DT_HASH => address(".hash"),
+                        DynamicTag::DT_GNU_HASH => address(".gnu.hash"),
+                        DynamicTag::DT_STRTAB => address(".dynstr"),
+                        DynamicTag::DT_SYMTAB => address(".dynsym"),
+                        DynamicTag::DT_RELA => address(".rela.dyn"),
+                        DynamicTag::DT_VERSYM => address(".gnu.version"),
+                        DynamicTag::DT_VERDEF => address(".gnu.version_d"),
+                        _ => value,
+                    },
+                })
+                .collect(),
+        };
+        self.set_data(".dynsym", dynsym);
+        self.set_data(".rela.dyn", rela_dyn);
+        self.set_data(".dynamic", dynamic.into());
+        let mut programs = loads;
+        let dynamic_section = &self.sections[self.section(".dynamic")?];
+        programs.push(section_program(
+            PType::PT_DYNAMIC,
+            dynamic_section,
+            PFlags::Read | PFlags::Write,
+        ));
+        let tls_sections: Vec<&BuilderSection> = self.sections.iter().filter(|s| s.tls).collect();
+        if let (Some(first), Some(last)) = (tls_sections.first(), tls_sections.last()) {
+            let mut program = section_program(PType::PT_TLS, *first, PFlags::Read.into());
+            program.p_memsz = last.addr + last.size - first.addr;
+            program.p_filesz = 0;
+            program.p_align = tls_sections.iter().map(|s| s.align).max().unwrap_or(1);
+            programs.push(program);
+        }
+        programs.push(gnu_stack());
+        let mut header = Elf64Ehdr::new(EType::ET_DYN);
+        /* like ld, files using GNU symbol types are marked */
+        if self.symbols.iter().any(|s| {
+            s.symbol_type == SymbolType::STT_GNU_IFUNC || s.binding == SymbolBinding::STB_GNU_UNIQUE
+        }) {
+            header.e_ident.set_os_abi(EI_OSABI::ELFOSABI_GNU);
+        }
+        write_file(header, &programs, &self.sections, true)
+    }
+}
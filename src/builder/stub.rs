@@ -0,0 +1,157 @@
+use super::super::elf64::Elf64;
+use super::super::error::ElfError;
+use super::super::section::sh_flags::SHFlags;
+use super::super::section::sh_type::ShType;
+use super::super::section::symbol::{SectionIndex, SymbolType};
+use super::object::ABS_SECTION;
+use super::shared::SharedLibraryBuilder;
+
+const SLOT_SIZE: u64 = 16;
+
+/*
+ * code of the functions of a stub library
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StubBody {
+    Trap,   // ud2
+    Return, // returns 0
+}
+
+impl StubBody {
+    fn code(&self) -> &'static [u8] {
+        match self {
+            Self::Trap => &[0x0f, 0x0b],
+            Self::Return => &[0x31, 0xc0, 0xc3],
+        }
+    }
+}
+
+/*
+ * where the stub places the symbols of one kind, symbols at the same
+ * address in the library stay aliases in the stub
+ */
+struct StubSection {
+    addresses: Vec<(u64, u64)>, /* Address in the library and offset in the stub */
+    size: u64,
+    align: u64,
+}
+
+impl StubSection {
+    fn new() -> Self {
+        Self {
+            addresses: Vec::new(),
+            size: 0,
+            align: 1,
+        }
+    }
+    fn place(&mut self, address: u64, size: u64, align: u64) -> u64 {
+        if let Some((_, offset)) = self.addresses.iter().find(|(a, _)| *a == address) {
+            return *offset;
+        }
+        let offset = self.size.next_multiple_of(align);
+        self.addresses.push((address, offset));
+        self.size = offset + size.max(1);
+        self.align = self.align.max(align);
+        offset
+    }
+}
+
+impl Elf64 {
+    /*
+     * a shared library with the SONAME and the exported .dynsym symbols of
+     * this one, with their types, bindings, visibilities, sizes and
+     * versions, to link against in its place. functions get a body in
+     * .text, data symbols space in .rodata or .bss with the alignment their
+     * address had and thread local ones in .tbss. the DT_NEEDED entries are
+     * not kept, the stub has no undefined symbols
+     */
+    pub fn stub_library(&self, body: StubBody) -> Result<Elf64, ElfError> {
+        if !self
+            .sections
+            .iter()
+            .any(|s| s.header.sh_type == ShType::SHT_DYNSYM)
+        {
+            return Err(ElfError::UnknownSection(".dynsym".to_string()));
+        }
+        let mut text = StubSection::new();
+        let mut rodata = StubSection::new();
+        let mut bss = StubSection::new();
+        let mut tbss = StubSection::new();
+        let mut symbols: Vec<(String, &'static str, u64, u64, SymbolType, _, _)> = Vec::new();
+        for entry in self.exported_symbols() {
+            let symbol = entry.symbol;
+            let section = self.sections.get(symbol.st_shndx as usize);
+            let flags = section.map(|s| s.header.sh_flags).unwrap_or_default();
+            let executable = flags.contains(SHFlags::SHF_EXECINSTR);
+            /* the largest power of two dividing the address, at most the section alignment */
+            let align = match symbol.st_value {
+                0 => 1,
+                v => 1 << v.trailing_zeros().min(12),
+            }
+            .min(section.map(|s| s.header.sh_addralign).unwrap_or(1).max(1));
+            let (section, offset) = match symbol.symbol_type {
+                _ if entry.section_index() == SectionIndex::SHN_ABS => {
+                    (ABS_SECTION, symbol.st_value)
+                }
+                SymbolType::STT_TLS => {
+                    (".tbss", tbss.place(symbol.st_value, symbol.st_size, align))
+                }
+                SymbolType::STT_FUNC | SymbolType::STT_GNU_IFUNC => {
+                    (".text", text.place(symbol.st_value, SLOT_SIZE, SLOT_SIZE))
+                }
+                SymbolType::STT_NOTYPE if executable => {
+                    (".text", text.place(symbol.st_value, SLOT_SIZE, SLOT_SIZE))
+                }
+                /* ld moves the copies of read only data to .data.rel.ro */
+                _ if !flags.contains(SHFlags::SHF_WRITE) => (
+                    ".rodata",
+                    rodata.place(symbol.st_value, symbol.st_size, align),
+                ),
+                _ => (".bss", bss.place(symbol.st_value, symbol.st_size, align)),
+            };
+            symbols.push((
+                entry.versioned_name(),
+                section,
+                offset,
+                symbol.st_size,
+                symbol.symbol_type,
+                symbol.symbol_binding,
+                entry.visibility(),
+            ));
+        }
+        let mut code: Vec<u8> = Vec::new();
+        for (_, offset) in &text.addresses {
+            code.resize(*offset as usize, 0xcc);
+            code.extend(body.code());
+        }
+        code.resize(text.size as usize, 0xcc);
+        let mut builder = SharedLibraryBuilder::new();
+        if let Some(soname) = self.soname() {
+            builder = builder.soname(&soname);
+        }
+        if text.size != 0 {
+            builder = builder.add_code(".text", SLOT_SIZE, code);
+        }
+        if rodata.size != 0 {
+            builder = builder.add_rodata(".rodata", rodata.align, vec![0; rodata.size as usize]);
+        }
+        if tbss.size != 0 {
+            builder = builder.add_tbss(".tbss", tbss.align, tbss.size);
+        }
+        if bss.size != 0 {
+            builder = builder.add_bss(".bss", bss.align, bss.size);
+        }
+        for (name, section, offset, size, symbol_type, binding, visibility) in symbols {
+            builder = builder.add_symbol(
+                &name,
+                section,
+                offset,
+                size,
+                symbol_type,
+                binding,
+                visibility,
+            );
+        }
+        builder.build()
+    }
+}
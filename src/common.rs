@@ -57,6 +57,9 @@ impl RawBinaryData {
     pub fn len(&self) -> usize {
         self.inner.len()
     }
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
     pub fn push(&mut self, value: u8) {
         self.inner.push(value);
     }
@@ -92,3 +95,18 @@ impl Debug for RawBinaryData {
         Ok(())
     }
 }
+
+/*
+ * page size of x86-64, used for segment alignment and memory images
+ */
+pub const PAGE_SIZE: u64 = 0x1000;
+
+/*
+ * writes data at offset growing the output with zeros when needed
+ */
+pub(crate) fn write_at(out: &mut Vec<u8>, offset: usize, data: &[u8]) {
+    if out.len() < offset + data.len() {
+        out.resize(offset + data.len(), 0);
+    }
+    out[offset..offset + data.len()].copy_from_slice(data);
+}
@@ -0,0 +1,270 @@
+use super::super::common::RawBinaryData;
+use super::super::elf64::Elf64;
+use super::super::error::ElfError;
+use super::super::header::e_type::EType;
+use super::super::header::header::Elf64Ehdr;
+use super::super::note::n_type::CoreNoteType;
+use super::super::note::note::{Note, NoteSection};
+use super::super::program::p_type::PType;
+use super::super::program::program::Program;
+use super::super::program::program_header::{Elf64_Phdr, PFlags};
+use super::super::section::section::{Section, SectionData, UnImplementedSection};
+use super::super::section::section_header::Elf64Shdr;
+use super::super::section::sh_type::ShType;
+use super::core_file::CoreThread;
+use super::nt_file::{FileMapping, NtFile};
+use super::prstatus::{Elf64_Prpsinfo, Elf64_Prstatus};
+use enumflags2::BitFlags;
+
+struct CoreRegion {
+    start: u64,
+    size: u64,
+    flags: BitFlags<PFlags>,
+    data: Vec<u8>,
+}
+
+/*
+ * builds an ET_CORE file laid out the way the linux kernel writes them:
+ * file header, program headers, one PT_NOTE segment and then one page
+ * aligned PT_LOAD segment per memory region
+ */
+pub struct CoreBuilder {
+    header: Elf64Ehdr,
+    process: Option<Elf64_Prpsinfo>,
+    threads: Vec<CoreThread>,
+    files: NtFile,
+    auxv: Vec<(u64, u64)>,
+    siginfo: Option<Vec<u8>>,
+    regions: Vec<CoreRegion>,
+}
+
+impl Default for CoreBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const PN_XNUM: usize = 0xFFFF;
+
+impl CoreBuilder {
+    pub fn new() -> Self {
+        Self {
+            header: Elf64Ehdr::new(EType::ET_CORE),
+            process: None,
+            threads: Vec::new(),
+            files: NtFile::default(),
+            auxv: Vec::new(),
+            siginfo: None,
+            regions: Vec::new(),
+        }
+    }
+    pub fn page_size(mut self, page_size: u64) -> Self {
+        self.files.page_size = page_size;
+        self
+    }
+    pub fn process(mut self, info: Elf64_Prpsinfo) -> Self {
+        self.process = Some(info);
+        self
+    }
+    /*
+     * the first thread added is the one debuggers select as the
+     * crashing thread
+     */
+    pub fn add_thread(mut self, mut status: Elf64_Prstatus, fpregs: Option<Vec<u8>>) -> Self {
+        status.pr_fpvalid = fpregs.is_some() as u32;
+        self.threads.push(CoreThread {
+            status,
+            fpregs,
+            xstate: None,
+        });
+        self
+    }
+    pub fn add_memory_region(mut self, start: u64, flags: BitFlags<PFlags>, data: Vec<u8>) -> Self {
+        self.regions.push(CoreRegion {
+            start,
+            size: data.len() as u64,
+            flags,
+            data,
+        });
+        self
+    }
+    /*
+     * region that shows up in the mappings of the process but whose contents
+     * are not in the core file (p_filesz is zero)
+     */
+    pub fn add_unreadable_region(mut self, start: u64, size: u64, flags: BitFlags<PFlags>) -> Self {
+        self.regions.push(CoreRegion {
+            start,
+            size,
+            flags,
+            data: Vec::new(),
+        });
+        self
+    }
+    pub fn add_file_mapping(mut self, mapping: FileMapping) -> Self {
+        self.files.mappings.push(mapping);
+        self
+    }
+    pub fn auxv(mut self, entries: Vec<(u64, u64)>) -> Self {
+        self.auxv = entries;
+        self
+    }
+    pub fn siginfo(mut self, raw: Vec<u8>) -> Self {
+        self.siginfo = Some(raw);
+        self
+    }
+    fn notes(&self) -> NoteSection {
+        let mut notes: Vec<Note> = Vec::new();
+        for (i, thread) in self.threads.iter().enumerate() {
+            notes.push(Note::new(
+                "CORE",
+                CoreNoteType::NT_PRSTATUS.into(),
+                (&thread.status).into(),
+            ));
+            if i == 0 {
+                self.process_notes(&mut notes);
+            }
+            if let Some(fpregs) = &thread.fpregs {
+                notes.push(Note::new(
+                    "CORE",
+                    CoreNoteType::NT_PRFPREG.into(),
+                    fpregs.clone(),
+                ));
+            }
+            if let Some(xstate) = &thread.xstate {
+                notes.push(Note::new(
+                    "LINUX",
+                    CoreNoteType::NT_X86_XSTATE.into(),
+                    xstate.clone(),
+                ));
+            }
+        }
+        if self.threads.is_empty() {
+            self.process_notes(&mut notes);
+        }
+        NoteSection { align: 4, notes }
+    }
+    fn process_notes(&self, notes: &mut Vec<Note>) {
+        if let Some(process) = &self.process {
+            notes.push(Note::new(
+                "CORE",
+                CoreNoteType::NT_PRPSINFO.into(),
+                process.into(),
+            ));
+        }
+        if let Some(siginfo) = &self.siginfo {
+            notes.push(Note::new(
+                "CORE",
+                CoreNoteType::NT_SIGINFO.into(),
+                siginfo.clone(),
+            ));
+        }
+        if !self.auxv.is_empty() {
+            let auxv = self
+                .auxv
+                .iter()
+                .flat_map(|(k, v)| [k.to_le_bytes(), v.to_le_bytes()])
+                .flatten()
+                .collect();
+            notes.push(Note::new("CORE", CoreNoteType::NT_AUXV.into(), auxv));
+        }
+        if !self.files.mappings.is_empty() {
+            notes.push(Note::new(
+                "CORE",
+                CoreNoteType::NT_FILE.into(),
+                (&self.files).into(),
+            ));
+        }
+    }
+    /*
+     * the page size has to be a power of two, the regions are aligned to it
+     * and the file offsets of the mappings are a multiple of it
+     */
+    pub fn build(self) -> Result<Elf64, ElfError> {
+        let page_size = self.files.page_size;
+        if !page_size.is_power_of_two() {
+            return Err(ElfError::InvalidPageSize(page_size));
+        }
+        if let Some(m) = self
+            .files
+            .mappings
+            .iter()
+            .find(|m| m.file_offset % page_size != 0)
+        {
+            return Err(ElfError::UnalignedFileOffset(m.file_offset));
+        }
+        let notes: Vec<u8> = (&self.notes()).into();
+        let mut header = self.header;
+        let number_of_headers = self.regions.len() + 1;
+        let mut offset =
+            header.e_ehsize as u64 + (header.e_phentsize as usize * number_of_headers) as u64;
+        header.e_phoff = header.e_ehsize as u64;
+        let mut programs: Vec<Program> = Vec::new();
+        programs.push(Program {
+            header: Elf64_Phdr {
+                p_type: PType::PT_NOTE,
+                p_flags: BitFlags::empty(),
+                p_offset: offset,
+                p_vaddr: 0,
+                p_paddr: 0,
+                p_filesz: notes.len() as u64,
+                p_memsz: 0,
+                p_align: 4,
+            },
+            data: notes.into(),
+        });
+        offset += programs[0].header.p_filesz;
+        for region in self.regions {
+            offset = offset.next_multiple_of(page_size);
+            programs.push(Program {
+                header: Elf64_Phdr {
+                    p_type: PType::PT_LOAD,
+                    p_flags: region.flags,
+                    p_offset: offset,
+                    p_vaddr: region.start,
+                    p_paddr: 0,
+                    p_filesz: region.data.len() as u64,
+                    p_memsz: region.size,
+                    p_align: page_size,
+                },
+                data: region.data.into(),
+            });
+            offset += programs.last().unwrap().header.p_filesz;
+        }
+        let mut sections: Vec<Section> = Vec::new();
+        if number_of_headers >= PN_XNUM {
+            /*
+             * too many program headers for e_phnum, the real number goes
+             * into sh_info of the first section header
+             */
+            header.e_phnum = PN_XNUM as u16;
+            header.e_shnum = 1;
+            header.e_shoff = offset.next_multiple_of(8);
+            sections.push(Section {
+                header: Elf64Shdr {
+                    sh_name: 0,
+                    sh_type: ShType::SHT_NULL,
+                    sh_flags: BitFlags::empty(),
+                    sh_addr: 0,
+                    sh_offset: 0,
+                    sh_size: 0,
+                    sh_link: 0,
+                    sh_info: number_of_headers as u32,
+                    sh_addralign: 0,
+                    sh_entsize: 0,
+                },
+                name: String::new(),
+                data: SectionData::Unknown(UnImplementedSection {
+                    data: RawBinaryData::default(),
+                }),
+            });
+        } else {
+            header.e_phnum = number_of_headers as u16;
+        }
+        Ok(Elf64 {
+            header,
+            sections,
+            programs,
+        })
+    }
+}
@@ -0,0 +1,133 @@
+use super::super::elf64::Elf64;
+use super::super::header::e_type::EType;
+use super::super::note::n_type::CoreNoteType;
+use super::super::program::p_type::PType;
+use super::super::program::program_header::PFlags;
+use super::nt_file::{FileMapping, NtFile};
+use super::prstatus::{Elf64_Prpsinfo, Elf64_Prstatus};
+use enumflags2::BitFlags;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoreThread {
+    pub status: Elf64_Prstatus,
+    pub fpregs: Option<Vec<u8>>, /* Raw fxsave area from NT_PRFPREG */
+    pub xstate: Option<Vec<u8>>, /* Raw xsave area from NT_X86_XSTATE */
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CoreMemoryRegion<'a> {
+    pub start: u64,
+    pub size: u64, /* Size in memory, may be bigger than data if the region was not dumped */
+    pub flags: BitFlags<PFlags>,
+    pub data: &'a [u8],
+}
+
+impl CoreMemoryRegion<'_> {
+    pub fn contains(&self, addr: u64) -> bool {
+        addr >= self.start && addr - self.start < self.size
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CoreFile<'a> {
+    pub process: Option<Elf64_Prpsinfo>,
+    pub threads: Vec<CoreThread>,
+    pub files: Vec<FileMapping>,
+    pub auxv: Vec<(u64, u64)>,
+    pub siginfo: Option<Vec<u8>>,
+    pub regions: Vec<CoreMemoryRegion<'a>>,
+}
+
+impl<'a> CoreFile<'a> {
+    pub fn parse(elf: &'a Elf64) -> Option<Self> {
+        if elf.header.e_type != EType::ET_CORE {
+            return None;
+        }
+        let mut core = Self::default();
+        for program in &elf.programs {
+            match program.header.p_type {
+                PType::PT_LOAD => core.regions.push(CoreMemoryRegion {
+                    start: program.header.p_vaddr,
+                    size: program.header.p_memsz,
+                    flags: program.header.p_flags,
+                    data: &program.data.inner,
+                }),
+                PType::PT_NOTE => {
+                    for note in program.notes().notes {
+                        let desc = &note.desc.inner;
+                        match (note.name.as_str(), CoreNoteType::from(note.header.n_type)) {
+                            ("CORE", CoreNoteType::NT_PRSTATUS) => {
+                                if let Ok((_, status)) = Elf64_Prstatus::parse(desc) {
+                                    core.threads.push(CoreThread {
+                                        status,
+                                        fpregs: None,
+                                        xstate: None,
+                                    });
+                                }
+                            }
+                            /*
+                             * register notes other than prstatus belong to
+                             * the thread of the last prstatus note
+                             */
+                            ("CORE", CoreNoteType::NT_PRFPREG) => {
+                                if let Some(thread) = core.threads.last_mut() {
+                                    thread.fpregs = Some(desc.clone());
+                                }
+                            }
+                            ("LINUX", CoreNoteType::NT_X86_XSTATE) => {
+                                if let Some(thread) = core.threads.last_mut() {
+                                    thread.xstate = Some(desc.clone());
+                                }
+                            }
+                            ("CORE", CoreNoteType::NT_PRPSINFO) => {
+                                if let Ok((_, info)) = Elf64_Prpsinfo::parse(desc) {
+                                    core.process = Some(info);
+                                }
+                            }
+                            ("CORE", CoreNoteType::NT_FILE) => {
+                                if let Ok((_, files)) = NtFile::parse(desc) {
+                                    core.files.extend(files.mappings);
+                                }
+                            }
+                            ("CORE", CoreNoteType::NT_AUXV) => {
+                                core.auxv.extend(desc.chunks_exact(16).map(|c| {
+                                    (
+                                        u64::from_le_bytes(c[..8].try_into().unwrap()),
+                                        u64::from_le_bytes(c[8..].try_into().unwrap()),
+                                    )
+                                }));
+                            }
+                            ("CORE", CoreNoteType::NT_SIGINFO) => {
+                                core.siginfo = Some(desc.clone());
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(core)
+    }
+    pub fn region_for_address(&self, addr: u64) -> Option<&CoreMemoryRegion<'a>> {
+        self.regions.iter().find(|r| r.contains(addr))
+    }
+    /*
+     * returns None if any byte of the range is not in the core file,
+     * bytes that are in a region but were not dumped read as zero
+     */
+    pub fn read_memory(&self, addr: u64, len: usize) -> Option<Vec<u8>> {
+        let mut out: Vec<u8> = Vec::with_capacity(len);
+        while out.len() < len {
+            let current = addr.checked_add(out.len() as u64)?;
+            let region = self.region_for_address(current)?;
+            let start = (current - region.start) as usize;
+            let available = (region.size as usize - start).min(len - out.len());
+            for i in start..start + available {
+                out.push(region.data.get(i).copied().unwrap_or(0));
+            }
+        }
+        Some(out)
+    }
+}
@@ -0,0 +1,4 @@
+pub mod builder;
+pub mod core_file;
+pub mod nt_file;
+pub mod prstatus;
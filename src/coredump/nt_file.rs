@@ -0,0 +1,105 @@
+use nom::multi::count;
+use nom::number::complete::le_u64;
+use nom::sequence;
+use nom::IResult;
+use serde::Serialize;
+use std::ffi::CStr;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMapping {
+    pub start: u64,       /* Start address of the mapping */
+    pub end: u64,         /* End address of the mapping (exclusive) */
+    pub file_offset: u64, /* Offset of the mapping inside the file in bytes */
+    pub path: String,
+}
+
+/*
+ * descriptor of the NT_FILE note:
+ *   count, page_size, count * (start, end, offset in pages), count * filename
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct NtFile {
+    pub page_size: u64,
+    pub mappings: Vec<FileMapping>,
+}
+
+impl Default for NtFile {
+    fn default() -> Self {
+        Self {
+            page_size: 0x1000,
+            mappings: Vec::new(),
+        }
+    }
+}
+
+impl NtFile {
+    pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
+        let (remaining, (number, page_size)) = sequence::tuple((le_u64, le_u64))(raw)?;
+        if page_size == 0 {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                raw,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+        let (mut remaining, ranges) =
+            count(sequence::tuple((le_u64, le_u64, le_u64)), number as usize)(remaining)?;
+        let mut mappings: Vec<FileMapping> = Vec::new();
+        for (start, end, page_offset) in ranges {
+            let path = match CStr::from_bytes_until_nul(remaining) {
+                Ok(s) => s,
+                Err(_) => {
+                    return Err(nom::Err::Error(nom::error::Error::new(
+                        remaining,
+                        nom::error::ErrorKind::Eof,
+                    )))
+                }
+            };
+            remaining = &remaining[path.to_bytes().len() + 1..];
+            let file_offset = match page_offset.checked_mul(page_size) {
+                Some(o) => o,
+                None => {
+                    return Err(nom::Err::Error(nom::error::Error::new(
+                        remaining,
+                        nom::error::ErrorKind::Verify,
+                    )))
+                }
+            };
+            mappings.push(FileMapping {
+                start,
+                end,
+                file_offset,
+                path: path.to_string_lossy().into_owned(),
+            });
+        }
+        Ok((
+            remaining,
+            Self {
+                page_size,
+                mappings,
+            },
+        ))
+    }
+}
+
+impl Into<Vec<u8>> for &NtFile {
+    fn into(self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        out.extend((self.mappings.len() as u64).to_le_bytes());
+        out.extend(self.page_size.to_le_bytes());
+        for m in &self.mappings {
+            out.extend(m.start.to_le_bytes());
+            out.extend(m.end.to_le_bytes());
+            out.extend(
+                m.file_offset
+                    .checked_div(self.page_size)
+                    .unwrap_or(0)
+                    .to_le_bytes(),
+            );
+        }
+        for m in &self.mappings {
+            out.extend(m.path.as_bytes());
+            out.push(0);
+        }
+        out
+    }
+}
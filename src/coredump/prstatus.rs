@@ -0,0 +1,345 @@
+#![allow(non_camel_case_types)]
+
+use nom::bytes::complete::take;
+use nom::number::complete::{le_u16, le_u32, le_u64, le_u8};
+use nom::IResult;
+use serde::Serialize;
+use std::ffi::CStr;
+
+/*
+ * register layout of struct user_regs_struct on x86_64 linux,
+ * this is the pr_reg field of the NT_PRSTATUS note
+ */
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct UserRegs {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub rbp: u64,
+    pub rbx: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rax: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub orig_rax: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub eflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+    pub fs_base: u64,
+    pub gs_base: u64,
+    pub ds: u64,
+    pub es: u64,
+    pub fs: u64,
+    pub gs: u64,
+}
+
+const USER_REGS_COUNT: usize = 27;
+
+impl UserRegs {
+    pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
+        let mut regs = [0u64; USER_REGS_COUNT];
+        let mut remaining = raw;
+        for reg in regs.iter_mut() {
+            let (r, v) = le_u64(remaining)?;
+            *reg = v;
+            remaining = r;
+        }
+        Ok((remaining, Self::from(regs)))
+    }
+}
+
+impl From<[u64; USER_REGS_COUNT]> for UserRegs {
+    fn from(v: [u64; USER_REGS_COUNT]) -> Self {
+        Self {
+            r15: v[0],
+            r14: v[1],
+            r13: v[2],
+            r12: v[3],
+            rbp: v[4],
+            rbx: v[5],
+            r11: v[6],
+            r10: v[7],
+            r9: v[8],
+            r8: v[9],
+            rax: v[10],
+            rcx: v[11],
+            rdx: v[12],
+            rsi: v[13],
+            rdi: v[14],
+            orig_rax: v[15],
+            rip: v[16],
+            cs: v[17],
+            eflags: v[18],
+            rsp: v[19],
+            ss: v[20],
+            fs_base: v[21],
+            gs_base: v[22],
+            ds: v[23],
+            es: v[24],
+            fs: v[25],
+            gs: v[26],
+        }
+    }
+}
+
+impl Into<[u64; USER_REGS_COUNT]> for &UserRegs {
+    fn into(self) -> [u64; USER_REGS_COUNT] {
+        [
+            self.r15,
+            self.r14,
+            self.r13,
+            self.r12,
+            self.rbp,
+            self.rbx,
+            self.r11,
+            self.r10,
+            self.r9,
+            self.r8,
+            self.rax,
+            self.rcx,
+            self.rdx,
+            self.rsi,
+            self.rdi,
+            self.orig_rax,
+            self.rip,
+            self.cs,
+            self.eflags,
+            self.rsp,
+            self.ss,
+            self.fs_base,
+            self.gs_base,
+            self.ds,
+            self.es,
+            self.fs,
+            self.gs,
+        ]
+    }
+}
+
+impl Into<Vec<u8>> for &UserRegs {
+    fn into(self) -> Vec<u8> {
+        let regs: [u64; USER_REGS_COUNT] = self.into();
+        regs.iter().flat_map(|r| r.to_le_bytes()).collect()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Timeval {
+    pub tv_sec: u64,
+    pub tv_usec: u64,
+}
+
+fn parse_timeval(raw: &[u8]) -> IResult<&[u8], Timeval> {
+    let (remaining, tv_sec) = le_u64(raw)?;
+    let (remaining, tv_usec) = le_u64(remaining)?;
+    Ok((remaining, Timeval { tv_sec, tv_usec }))
+}
+
+/*
+ * struct elf_prstatus as written by the linux kernel on x86_64 (336 bytes)
+ */
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Elf64_Prstatus {
+    pub si_signo: u32,   /* Signal number */
+    pub si_code: u32,    /* Extra code */
+    pub si_errno: u32,   /* Errno */
+    pub pr_cursig: u16,  /* Current signal */
+    pub pr_sigpend: u64, /* Set of pending signals */
+    pub pr_sighold: u64, /* Set of held signals */
+    pub pr_pid: u32,     /* Thread id */
+    pub pr_ppid: u32,
+    pub pr_pgrp: u32,
+    pub pr_sid: u32,
+    pub pr_utime: Timeval,  /* User time */
+    pub pr_stime: Timeval,  /* System time */
+    pub pr_cutime: Timeval, /* Cumulative user time */
+    pub pr_cstime: Timeval, /* Cumulative system time */
+    pub pr_reg: UserRegs,   /* General purpose registers */
+    pub pr_fpvalid: u32,    /* True if the thread has a NT_PRFPREG note */
+}
+
+pub const PRSTATUS_SIZE: usize = 336;
+
+impl Elf64_Prstatus {
+    pub fn new(pid: u32, regs: UserRegs) -> Self {
+        Self {
+            pr_pid: pid,
+            pr_reg: regs,
+            ..Default::default()
+        }
+    }
+    pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
+        let (remaining, si_signo) = le_u32(raw)?;
+        let (remaining, si_code) = le_u32(remaining)?;
+        let (remaining, si_errno) = le_u32(remaining)?;
+        let (remaining, pr_cursig) = le_u16(remaining)?;
+        let (remaining, _) = take(2usize)(remaining)?;
+        let (remaining, pr_sigpend) = le_u64(remaining)?;
+        let (remaining, pr_sighold) = le_u64(remaining)?;
+        let (remaining, pr_pid) = le_u32(remaining)?;
+        let (remaining, pr_ppid) = le_u32(remaining)?;
+        let (remaining, pr_pgrp) = le_u32(remaining)?;
+        let (remaining, pr_sid) = le_u32(remaining)?;
+        let (remaining, pr_utime) = parse_timeval(remaining)?;
+        let (remaining, pr_stime) = parse_timeval(remaining)?;
+        let (remaining, pr_cutime) = parse_timeval(remaining)?;
+        let (remaining, pr_cstime) = parse_timeval(remaining)?;
+        let (remaining, pr_reg) = UserRegs::parse(remaining)?;
+        let (remaining, pr_fpvalid) = le_u32(remaining)?;
+        let (remaining, _) = take(4usize)(remaining)?;
+        Ok((
+            remaining,
+            Self {
+                si_signo,
+                si_code,
+                si_errno,
+                pr_cursig,
+                pr_sigpend,
+                pr_sighold,
+                pr_pid,
+                pr_ppid,
+                pr_pgrp,
+                pr_sid,
+                pr_utime,
+                pr_stime,
+                pr_cutime,
+                pr_cstime,
+                pr_reg,
+                pr_fpvalid,
+            },
+        ))
+    }
+}
+
+impl Into<Vec<u8>> for &Elf64_Prstatus {
+    fn into(self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        out.extend(self.si_signo.to_le_bytes());
+        out.extend(self.si_code.to_le_bytes());
+        out.extend(self.si_errno.to_le_bytes());
+        out.extend(self.pr_cursig.to_le_bytes());
+        out.extend([0u8; 2]);
+        out.extend(self.pr_sigpend.to_le_bytes());
+        out.extend(self.pr_sighold.to_le_bytes());
+        out.extend(self.pr_pid.to_le_bytes());
+        out.extend(self.pr_ppid.to_le_bytes());
+        out.extend(self.pr_pgrp.to_le_bytes());
+        out.extend(self.pr_sid.to_le_bytes());
+        for t in [self.pr_utime, self.pr_stime, self.pr_cutime, self.pr_cstime] {
+            out.extend(t.tv_sec.to_le_bytes());
+            out.extend(t.tv_usec.to_le_bytes());
+        }
+        out.extend::<Vec<u8>>((&self.pr_reg).into());
+        out.extend(self.pr_fpvalid.to_le_bytes());
+        out.extend([0u8; 4]);
+        out
+    }
+}
+
+/*
+ * struct elf_prpsinfo as written by the linux kernel on x86_64 (136 bytes)
+ */
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Elf64_Prpsinfo {
+    pub pr_state: u8, /* Numeric process state */
+    pub pr_sname: u8, /* Char for pr_state (R, S, D, T, Z) */
+    pub pr_zomb: u8,  /* Zombie */
+    pub pr_nice: u8,  /* Nice value */
+    pub pr_flag: u64, /* Flags */
+    pub pr_uid: u32,
+    pub pr_gid: u32,
+    pub pr_pid: u32,
+    pub pr_ppid: u32,
+    pub pr_pgrp: u32,
+    pub pr_sid: u32,
+    pub pr_fname: String,  /* Filename of executable, at most 16 bytes */
+    pub pr_psargs: String, /* Initial part of the argument list, at most 80 bytes */
+}
+
+pub const PRPSINFO_SIZE: usize = 136;
+
+fn fixed_string(raw: &[u8]) -> String {
+    match CStr::from_bytes_until_nul(raw) {
+        Ok(s) => s.to_string_lossy().into_owned(),
+        Err(_) => String::from_utf8_lossy(raw).into_owned(),
+    }
+}
+
+fn fixed_bytes(value: &str, len: usize) -> Vec<u8> {
+    let mut out = Vec::from(value.as_bytes());
+    // keep at least one null byte at the end like the kernel does
+    out.truncate(len - 1);
+    out.resize(len, 0);
+    out
+}
+
+impl Elf64_Prpsinfo {
+    pub fn new(pid: u32, fname: &str, psargs: &str) -> Self {
+        Self {
+            pr_sname: b'R',
+            pr_pid: pid,
+            pr_fname: fname.to_string(),
+            pr_psargs: psargs.to_string(),
+            ..Default::default()
+        }
+    }
+    pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
+        let (remaining, pr_state) = le_u8(raw)?;
+        let (remaining, pr_sname) = le_u8(remaining)?;
+        let (remaining, pr_zomb) = le_u8(remaining)?;
+        let (remaining, pr_nice) = le_u8(remaining)?;
+        let (remaining, _) = take(4usize)(remaining)?;
+        let (remaining, pr_flag) = le_u64(remaining)?;
+        let (remaining, pr_uid) = le_u32(remaining)?;
+        let (remaining, pr_gid) = le_u32(remaining)?;
+        let (remaining, pr_pid) = le_u32(remaining)?;
+        let (remaining, pr_ppid) = le_u32(remaining)?;
+        let (remaining, pr_pgrp) = le_u32(remaining)?;
+        let (remaining, pr_sid) = le_u32(remaining)?;
+        let (remaining, pr_fname) = take(16usize)(remaining)?;
+        let (remaining, pr_psargs) = take(80usize)(remaining)?;
+        Ok((
+            remaining,
+            Self {
+                pr_state,
+                pr_sname,
+                pr_zomb,
+                pr_nice,
+                pr_flag,
+                pr_uid,
+                pr_gid,
+                pr_pid,
+                pr_ppid,
+                pr_pgrp,
+                pr_sid,
+                pr_fname: fixed_string(pr_fname),
+                pr_psargs: fixed_string(pr_psargs),
+            },
+        ))
+    }
+}
+
+impl Into<Vec<u8>> for &Elf64_Prpsinfo {
+    fn into(self) -> Vec<u8> {
+        let mut out: Vec<u8> = vec![self.pr_state, self.pr_sname, self.pr_zomb, self.pr_nice];
+        out.extend([0u8; 4]);
+        out.extend(self.pr_flag.to_le_bytes());
+        out.extend(self.pr_uid.to_le_bytes());
+        out.extend(self.pr_gid.to_le_bytes());
+        out.extend(self.pr_pid.to_le_bytes());
+        out.extend(self.pr_ppid.to_le_bytes());
+        out.extend(self.pr_pgrp.to_le_bytes());
+        out.extend(self.pr_sid.to_le_bytes());
+        out.extend(fixed_bytes(&self.pr_fname, 16));
+        out.extend(fixed_bytes(&self.pr_psargs, 80));
+        out
+    }
+}
@@ -0,0 +1,56 @@
+use nom::bytes::complete::{take, take_until};
+use nom::number::complete::le_u32;
+use nom::IResult;
+use serde::Serialize;
+
+/*
+ * contents of the .gnu_debuglink section:
+ * null terminated file name, zero padding to 4 bytes, crc32 of the debug file
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DebugLink {
+    pub filename: String,
+    pub crc: u32,
+}
+
+/*
+ * gdb calls it gnu_debuglink_crc32 but it is the same crc32 used by zlib
+ */
+pub fn debuglink_crc(data: &[u8]) -> u32 {
+    crc32fast::hash(data)
+}
+
+impl DebugLink {
+    pub fn new(filename: &str, debug_file: &[u8]) -> Self {
+        Self {
+            filename: filename.to_string(),
+            crc: debuglink_crc(debug_file),
+        }
+    }
+    pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
+        let (remaining, filename) = take_until(&[0u8][..])(raw)?;
+        let name_len = filename.len() + 1;
+        let (remaining, _) = take(name_len + (4 - name_len % 4) % 4 - filename.len())(remaining)?;
+        let (remaining, crc) = le_u32(remaining)?;
+        Ok((
+            remaining,
+            Self {
+                filename: String::from_utf8_lossy(filename).into_owned(),
+                crc,
+            },
+        ))
+    }
+    pub fn matches(&self, debug_file: &[u8]) -> bool {
+        debuglink_crc(debug_file) == self.crc
+    }
+}
+
+impl Into<Vec<u8>> for &DebugLink {
+    fn into(self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::from(self.filename.as_bytes());
+        out.push(0);
+        out.resize(out.len().next_multiple_of(4), 0);
+        out.extend(self.crc.to_le_bytes());
+        out
+    }
+}
@@ -0,0 +1,130 @@
+use super::super::elf64::Elf64;
+use super::super::header::header::Elf64Ehdr;
+use super::debuglink::DebugLink;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub struct DebugFile {
+    pub path: PathBuf,
+    pub elf: Elf64,
+}
+
+/*
+ * finds separate debug files the same way gdb does:
+ *   1. <debug dir>/.build-id/xx/yyyy.debug for every debug directory
+ *   2. <dir of file>/<debuglink>
+ *   3. <dir of file>/.debug/<debuglink>
+ *   4. <debug dir>/<dir of file>/<debuglink> for every debug directory
+ * build id candidates must carry the same build id and debuglink
+ * candidates must match the crc stored in .gnu_debuglink
+ */
+pub struct DebugFileLocator {
+    pub debug_directories: Vec<PathBuf>,
+}
+
+impl Default for DebugFileLocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebugFileLocator {
+    pub fn new() -> Self {
+        Self {
+            debug_directories: vec![PathBuf::from("/usr/lib/debug")],
+        }
+    }
+    pub fn with_directories<T: Into<PathBuf>>(directories: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            debug_directories: directories.into_iter().map(|d| d.into()).collect(),
+        }
+    }
+    pub fn build_id_path(directory: &Path, build_id: &[u8]) -> Option<PathBuf> {
+        let (first, rest) = build_id.split_first()?;
+        if rest.is_empty() {
+            return None;
+        }
+        let rest: String = rest.iter().map(|b| format!("{:02x}", b)).collect();
+        Some(
+            directory
+                .join(".build-id")
+                .join(format!("{:02x}", first))
+                .join(format!("{}.debug", rest)),
+        )
+    }
+    pub fn debuglink_paths(&self, link: &DebugLink, file_path: &Path) -> Vec<PathBuf> {
+        let file_path = fs::canonicalize(file_path).unwrap_or(file_path.to_path_buf());
+        let directory = file_path.parent().unwrap_or(Path::new("/"));
+        let mut out: Vec<PathBuf> = vec![
+            directory.join(&link.filename),
+            directory.join(".debug").join(&link.filename),
+        ];
+        for debug_directory in &self.debug_directories {
+            let relative = directory.strip_prefix("/").unwrap_or(directory);
+            out.push(debug_directory.join(relative).join(&link.filename));
+        }
+        out.retain(|p| p != &file_path);
+        out
+    }
+    pub fn find_by_build_id(&self, build_id: &[u8]) -> Option<DebugFile> {
+        for directory in &self.debug_directories {
+            let path = match Self::build_id_path(directory, build_id) {
+                Some(p) => p,
+                None => continue,
+            };
+            let elf = match read_elf(&path) {
+                Some((_, elf)) => elf,
+                None => continue,
+            };
+            if elf.build_id().as_deref() == Some(build_id) {
+                return Some(DebugFile { path, elf });
+            }
+        }
+        None
+    }
+    pub fn find_by_debuglink(&self, link: &DebugLink, file_path: &Path) -> Option<DebugFile> {
+        for path in self.debuglink_paths(link, file_path) {
+            if let Some((raw, elf)) = read_elf(&path) {
+                if link.matches(&raw) {
+                    return Some(DebugFile { path, elf });
+                }
+            }
+        }
+        None
+    }
+    /*
+     * file_path is the path of the file elf was read from, debuglink
+     * candidates are relative to its directory
+     */
+    pub fn find(&self, elf: &Elf64, file_path: impl AsRef<Path>) -> Option<DebugFile> {
+        if let Some(build_id) = elf.build_id() {
+            if let Some(found) = self.find_by_build_id(&build_id) {
+                return Some(found);
+            }
+        }
+        let link = elf.debuglink()?;
+        self.find_by_debuglink(&link, file_path.as_ref())
+    }
+}
+
+/*
+ * candidates are arbitrary files, the header tables must lie inside the
+ * file before it is handed to the parser
+ */
+fn read_elf(path: &Path) -> Option<(Vec<u8>, Elf64)> {
+    let raw = fs::read(path).ok()?;
+    let (_, header) = Elf64Ehdr::parse(&raw).ok()?;
+    let fits = |offset: u64, count: u16, size: u16| {
+        (count as u64)
+            .checked_mul(size as u64)
+            .and_then(|n| n.checked_add(offset))
+            .is_some_and(|end| end <= raw.len() as u64)
+    };
+    if !fits(header.e_shoff, header.e_shnum, header.e_shentsize)
+        || !fits(header.e_phoff, header.e_phnum, header.e_phentsize)
+    {
+        return None;
+    }
+    let (_, elf) = Elf64::parse(&raw).ok()?;
+    Some((raw, elf))
+}
@@ -0,0 +1,2 @@
+pub mod debuglink;
+pub mod locator;
@@ -1,11 +1,20 @@
 #![allow(non_camel_case_types)]
 
-use std::collections::HashMap;
-
-use super::common::ParserIn;
+use super::common::{write_at, ParserIn};
+use super::coredump::core_file::CoreFile;
+use super::debuginfo::debuglink::DebugLink;
+use super::error::ElfError;
 use super::header::header::Elf64Ehdr;
+use super::note::n_type::GnuNoteType;
+use super::note::note::Note;
+use super::program::p_type::PType;
 use super::program::program::Program;
-use super::section::section::{Section, SectionData};
+use super::section::elf64_dyn::{DynamicSection, DynamicTag};
+use super::section::group::GroupSection;
+use super::section::section::{RelaSection, Section, SectionData};
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+use super::section::symbol::{demangle, Elf64_Rela, Elf64_Sym, SymbolType};
 use nom::multi::count;
 use nom::IResult;
 use serde::Serialize;
@@ -20,39 +29,382 @@ pub struct Elf64 {
 
 const PN_XNUM: u16 = 0xFFFF;
 
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ReportOptions {
+    pub demangle: bool, /* add demangled_name next to the name of every symbol */
+}
+
+/*
+ * symbols are the only objects with both st_info and name
+ */
+fn add_demangled_names(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if map.contains_key("st_info") {
+                if let Some(serde_json::Value::String(name)) = map.get("name") {
+                    let demangled = demangle(name).unwrap_or_else(|| name.clone());
+                    map.insert("demangled_name".into(), demangled.into());
+                }
+            }
+            map.values_mut().for_each(add_demangled_names);
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(add_demangled_names),
+        _ => (),
+    }
+}
+
+/*
+ * names of the symbols of a symbol table from the string table sh_link
+ */
+pub(crate) fn resolve_symbol_names(sections: &mut [Section], table: usize) {
+    let link = sections[table].header.sh_link as usize;
+    if link >= sections.len() {
+        return;
+    }
+    let names: Vec<Option<String>> = match (&sections[table].data, &sections[link].data) {
+        (SectionData::Symbol(symbols), SectionData::String(strings)) => {
+            let strings = strings.lookup();
+            symbols
+                .symbols
+                .iter()
+                .map(|s| strings.get(s.st_name as usize))
+                .collect()
+        }
+        _ => return,
+    };
+    if let SectionData::Symbol(symbols) = &mut sections[table].data {
+        for (symbol, name) in symbols.symbols.iter_mut().zip(names) {
+            symbol.name = name.unwrap_or_default();
+        }
+    }
+}
+
+/*
+ * a defined function or object symbol whose range holds addr
+ */
+pub(crate) fn covers(s: &Elf64_Sym, addr: u64) -> bool {
+    s.st_shndx != 0
+        && matches!(s.symbol_type, SymbolType::STT_FUNC | SymbolType::STT_OBJECT)
+        && s.st_value <= addr
+        && addr < s.st_value.saturating_add(s.st_size.max(1))
+}
+
 impl Elf64 {
     pub fn json_report(&self) -> String {
         serde_json::to_string_pretty(self).unwrap()
     }
+    pub fn json_report_with_options(&self, options: &ReportOptions) -> String {
+        let mut value = serde_json::to_value(self).unwrap();
+        if options.demangle {
+            add_demangled_names(&mut value);
+        }
+        serde_json::to_string_pretty(&value).unwrap()
+    }
+    pub fn core(&self) -> Option<CoreFile<'_>> {
+        CoreFile::parse(self)
+    }
+    /*
+     * the build id note is looked up in the note sections first and in the
+     * PT_NOTE segments if the section headers are stripped
+     */
+    pub fn build_id(&self) -> Option<Vec<u8>> {
+        let is_build_id = |n: &Note| {
+            n.name == "GNU" && GnuNoteType::from(n.header.n_type) == GnuNoteType::NT_GNU_BUILD_ID
+        };
+        for section in &self.sections {
+            if let SectionData::Note(notes) = &section.data {
+                if let Some(note) = notes.notes.iter().find(|n| is_build_id(n)) {
+                    return Some(note.desc.inner.clone());
+                }
+            }
+        }
+        for program in &self.programs {
+            if program.header.p_type == PType::PT_NOTE {
+                if let Some(note) = program.notes().notes.into_iter().find(is_build_id) {
+                    return Some(note.desc.inner);
+                }
+            }
+        }
+        None
+    }
+    pub fn debuglink(&self) -> Option<DebugLink> {
+        let section = self.sections.iter().find(|s| s.name == ".gnu_debuglink")?;
+        let raw: Vec<u8> = (&section.data).into();
+        DebugLink::parse(&raw).ok().map(|(_, link)| link)
+    }
+    pub fn mini_debuginfo(&self) -> Option<&Elf64> {
+        self.sections.iter().find_map(|s| match &s.data {
+            SectionData::MiniDebugInfo(m) => m.elf.as_deref(),
+            _ => None,
+        })
+    }
+    /*
+     * symbols used for address lookups: .symtab, then the .symtab of the
+     * MiniDebugInfo elf (stripped distro binaries), then .dynsym
+     */
+    pub fn lookup_symbols(&self) -> Vec<&Elf64_Sym> {
+        let mut out: Vec<&Elf64_Sym> = self.symbol_table(ShType::SHT_SYMTAB).collect();
+        if let Some(mini) = self.mini_debuginfo() {
+            out.extend(mini.symbol_table(ShType::SHT_SYMTAB));
+        }
+        out.extend(self.symbol_table(ShType::SHT_DYNSYM));
+        out
+    }
+    fn symbol_table(&self, sh_type: ShType) -> impl Iterator<Item = &Elf64_Sym> {
+        self.sections
+            .iter()
+            .filter(move |s| s.header.sh_type == sh_type)
+            .filter_map(|s| match &s.data {
+                SectionData::Symbol(t) => Some(&t.symbols),
+                _ => None,
+            })
+            .flatten()
+    }
+    pub(crate) fn linked_symbols(&self, link: usize) -> Option<&Vec<Elf64_Sym>> {
+        match &self.sections.get(link)?.data {
+            SectionData::Symbol(s) => Some(&s.symbols),
+            _ => None,
+        }
+    }
+    pub fn symbol_for_address(&self, addr: u64) -> Option<&Elf64_Sym> {
+        self.lookup_symbols().into_iter().find(|s| covers(s, addr))
+    }
+    /*
+     * the dynamic table from the SHT_DYNAMIC section, or from PT_DYNAMIC when
+     * the section headers are stripped
+     */
+    pub fn dynamic(&self) -> Option<DynamicSection> {
+        let section = self.sections.iter().find_map(|s| match &s.data {
+            SectionData::Dynamic(d) => Some(d.clone()),
+            _ => None,
+        });
+        section.or_else(|| {
+            self.programs
+                .iter()
+                .find(|p| p.header.p_type == PType::PT_DYNAMIC)
+                .map(|p| DynamicSection::from(&p.data.inner))
+        })
+    }
+    /*
+     * DT_SONAME read from the string table linked to .dynamic
+     */
+    pub fn soname(&self) -> Option<String> {
+        let offset = self.dynamic()?.get(DynamicTag::DT_SONAME)?;
+        let dynamic = self
+            .sections
+            .iter()
+            .find(|s| s.header.sh_type == ShType::SHT_DYNAMIC)?;
+        match &self.sections.get(dynamic.header.sh_link as usize)?.data {
+            SectionData::String(strings) => strings.get(offset as usize),
+            _ => None,
+        }
+    }
+    /*
+     * relocations of the table described by a pair of dynamic tags, read
+     * through the segments like ld.so does
+     */
+    pub(crate) fn dynamic_rela(
+        &self,
+        dynamic: &DynamicSection,
+        start_tag: DynamicTag,
+        size_tag: DynamicTag,
+    ) -> Vec<Elf64_Rela> {
+        match (dynamic.get(start_tag), dynamic.get(size_tag)) {
+            (Some(start), Some(size)) => self
+                .read_at_address(start, size as usize)
+                .map(|raw| RelaSection::from(raw).rela_entries)
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+    /*
+     * .dynsym entry found through DT_SYMTAB, with its name from DT_STRTAB
+     */
+    pub(crate) fn dynamic_symbol(&self, dynamic: &DynamicSection, index: u32) -> Option<Elf64_Sym> {
+        if index == 0 {
+            return None;
+        }
+        let symtab = dynamic.get(DynamicTag::DT_SYMTAB)?;
+        let syment = dynamic.get(DynamicTag::DT_SYMENT).unwrap_or(24);
+        let raw = self.read_at_address(symtab + syment * index as u64, 24)?;
+        let (_, mut symbol) = Elf64_Sym::parse(raw).ok()?;
+        let strtab = dynamic.get(DynamicTag::DT_STRTAB)?;
+        let strsz = dynamic.get(DynamicTag::DT_STRSZ)?;
+        let strings = self.read_at_address(strtab, strsz as usize)?;
+        let name = strings.get(symbol.st_name as usize..)?;
+        let end = name.iter().position(|c| *c == 0)?;
+        symbol.name = String::from_utf8_lossy(&name[..end]).into_owned();
+        Some(symbol)
+    }
+    /*
+     * the .dynsym entries found through DT_SYMTAB, their number is nchain
+     * of DT_HASH or the end of the last DT_GNU_HASH chain
+     */
+    pub(crate) fn dynamic_table_symbols(&self, dynamic: &DynamicSection) -> Vec<Elf64_Sym> {
+        let count = self.dynamic_symbol_count(dynamic).unwrap_or(0);
+        (1..count)
+            .map_while(|i| self.dynamic_symbol(dynamic, i))
+            .collect()
+    }
+    fn dynamic_symbol_count(&self, dynamic: &DynamicSection) -> Option<u32> {
+        let word = |addr: u64| {
+            self.read_at_address(addr, 4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        };
+        if let Some(hash) = dynamic.get(DynamicTag::DT_HASH) {
+            return word(hash.checked_add(4)?);
+        }
+        let hash = dynamic.get(DynamicTag::DT_GNU_HASH)?;
+        let (nbuckets, symoffset, bloom_size) = (word(hash)?, word(hash + 4)?, word(hash + 8)?);
+        let buckets = hash + 16 + 8 * bloom_size as u64;
+        let last = (0..nbuckets as u64)
+            .filter_map(|b| word(buckets + 4 * b))
+            .max()
+            .filter(|last| *last >= symoffset);
+        let mut index = match last {
+            Some(last) => last,
+            None => return Some(symoffset),
+        };
+        let chains = buckets + 4 * nbuckets as u64;
+        /* the last chain ends with an entry that has the low bit set */
+        while word(chains + 4 * (index - symoffset) as u64)? & 1 == 0 {
+            index = index.checked_add(1)?;
+        }
+        Some(index + 1)
+    }
+    /*
+     * file contents at a virtual address, found through the PT_LOAD segments
+     */
+    pub fn read_at_address(&self, addr: u64, len: usize) -> Option<&[u8]> {
+        self.programs
+            .iter()
+            .filter(|p| p.header.p_type == PType::PT_LOAD)
+            .find(|p| addr >= p.header.p_vaddr && addr < p.header.p_vaddr + p.header.p_filesz)
+            .and_then(|p| {
+                let start = (addr - p.header.p_vaddr) as usize;
+                p.data.inner.get(start..start + len)
+            })
+    }
+    pub fn groups(&self) -> impl Iterator<Item = (usize, &GroupSection)> {
+        self.sections
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| match &s.data {
+                SectionData::Group(g) => Some((i, g)),
+                _ => None,
+            })
+    }
+    /*
+     * the signature of a group is symbol sh_info of the symbol table sh_link
+     */
+    pub fn group_signature(&self, group: usize) -> Option<&Elf64_Sym> {
+        let header = &self.sections.get(group)?.header;
+        match &self.sections.get(header.sh_link as usize)?.data {
+            SectionData::Symbol(symbols) => symbols.symbols.get(header.sh_info as usize),
+            _ => None,
+        }
+    }
+    /*
+     * assemblers may use a section symbol as signature, the name of the
+     * group is then the name of that section
+     */
+    pub fn group_signature_name(&self, group: usize) -> Option<String> {
+        let symbol = self.group_signature(group)?;
+        match symbol.symbol_type {
+            SymbolType::STT_SECTION => self
+                .sections
+                .get(symbol.st_shndx as usize)
+                .map(|s| s.name.clone()),
+            _ => Some(symbol.name.clone()),
+        }
+    }
+    pub fn group_of(&self, section: usize) -> Option<usize> {
+        if !self
+            .sections
+            .get(section)?
+            .header
+            .sh_flags
+            .contains(SHFlags::SHF_GROUP)
+        {
+            return None;
+        }
+        self.groups()
+            .find(|(_, g)| g.members.contains(&(section as u32)))
+            .map(|(i, _)| i)
+    }
+    fn group_mut(&mut self, group: usize) -> Result<&mut GroupSection, ElfError> {
+        match self.sections.get_mut(group).map(|s| &mut s.data) {
+            Some(SectionData::Group(g)) => Ok(g),
+            _ => Err(ElfError::InvalidSection(group)),
+        }
+    }
+    /*
+     * a section can only be in one group, it is taken out of its old group
+     */
+    pub fn add_group_member(&mut self, group: usize, section: usize) -> Result<(), ElfError> {
+        if section >= self.sections.len() || section == group {
+            return Err(ElfError::InvalidSection(section));
+        }
+        self.group_mut(group)?;
+        if let Some(old) = self.group_of(section) {
+            if old == group {
+                return Ok(());
+            }
+            self.remove_group_member(old, section)?;
+        }
+        let members = {
+            let g = self.group_mut(group)?;
+            g.members.push(section as u32);
+            g.members.len()
+        };
+        self.sections[group].header.sh_size = 4 * (members as u64 + 1);
+        self.sections[section].header.sh_flags |= SHFlags::SHF_GROUP;
+        Ok(())
+    }
+    pub fn remove_group_member(&mut self, group: usize, section: usize) -> Result<(), ElfError> {
+        let members = {
+            let g = self.group_mut(group)?;
+            let before = g.members.len();
+            g.members.retain(|m| *m as usize != section);
+            if g.members.len() == before {
+                return Err(ElfError::InvalidSection(section));
+            }
+            g.members.len()
+        };
+        self.sections[group].header.sh_size = 4 * (members as u64 + 1);
+        self.sections[section].header.sh_flags &= !SHFlags::SHF_GROUP;
+        Ok(())
+    }
     pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
         let (remaining, header) = Elf64Ehdr::parse(raw)?;
         let (_, mut sections) = count(Section::parse, header.e_shnum as usize)(ParserIn::from((
             raw,
             &raw[header.e_shoff as usize..],
         )))?;
-        let mut section_names: Option<HashMap<usize, String>> = None;
+        let mut section_names: Vec<Option<String>> = Vec::new();
         if sections.len() > header.e_shstrndx as usize {
-            // if let SectionData::String(s) = sections[header.e_shstrndx as usize].data.clone() {
-            //     section_names = Some(&s.strings);
-            // }
-            match &sections[header.e_shstrndx as usize].data {
-                SectionData::String(s) => {
-                    section_names = Some(s.strings.clone());
-                }
-                _ => {}
+            if let SectionData::String(s) = &sections[header.e_shstrndx as usize].data {
+                let s = s.lookup();
+                section_names = sections
+                    .iter()
+                    .map(|section| s.get(section.header.sh_name as usize))
+                    .collect();
             }
         }
-
+        for (section, name) in sections.iter_mut().zip(section_names) {
+            if let Some(v) = name {
+                section.name = v;
+            }
+        }
+        for i in 0..sections.len() {
+            resolve_symbol_names(&mut sections, i);
+        }
         for section in &mut sections {
-            match &section_names {
-                Some(sn) => {
-                    if let Some(v) = sn.get(&(section.header.sh_name as usize)) {
-                        section.name = v.clone();
-                    }
+            if section.name == ".gnu_debugdata" {
+                if let SectionData::Unknown(raw) = &section.data {
+                    section.data = SectionData::MiniDebugInfo(raw.data.inner.as_slice().into());
                 }
-                _ => {}
             }
-            // todo resolve symbol names from strtab
         }
         let mut number_of_headers: u32 = header.e_phnum as u32;
         if header.e_phnum == PN_XNUM {
@@ -83,3 +435,36 @@ impl<T: AsRef<[u8]>> From<&T> for Elf64 {
         f.1
     }
 }
+
+/*
+ * packs the elf at the offsets stored in the headers, nothing is laid out
+ * here so e_phoff, e_shoff, p_offset and sh_offset must already be set
+ */
+impl Into<Vec<u8>> for &Elf64 {
+    fn into(self) -> Vec<u8> {
+        let mut out: Vec<u8> = (&self.header).into();
+        let mut phoff = self.header.e_phoff as usize;
+        for program in &self.programs {
+            write_at(&mut out, phoff, &Into::<Vec<u8>>::into(&program.header));
+            write_at(
+                &mut out,
+                program.header.p_offset as usize,
+                &program.data.inner,
+            );
+            phoff += self.header.e_phentsize as usize;
+        }
+        let mut shoff = self.header.e_shoff as usize;
+        for section in &self.sections {
+            write_at(&mut out, shoff, &Into::<Vec<u8>>::into(&section.header));
+            if !matches!(
+                section.header.sh_type,
+                ShType::SHT_NOBITS | ShType::SHT_NULL
+            ) {
+                let data: Vec<u8> = (&section.data).into();
+                write_at(&mut out, section.header.sh_offset as usize, &data);
+            }
+            shoff += self.header.e_shentsize as usize;
+        }
+        out
+    }
+}
@@ -0,0 +1,79 @@
+use super::header::e_type::EType;
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum ElfError {
+    Truncated(&'static str),     // input ended before the named structure
+    UnsupportedCompression(u32), // ch_type of Elf64_Chdr
+    Compression(String),         // error from the zlib/zstd coder
+    SizeMismatch { expected: u64, actual: u64 },
+    InvalidSection(usize), // index out of range or section of the wrong type
+    Unmapped(u64),         // address outside of every mapping of a memory image
+    UnsupportedRelocation(u32), // r_type the operation cannot apply
+    UnresolvedSymbol(String), // undefined symbol without a definition
+    RelocationOverflow(u64), // relocated value does not fit in the field at this place
+    Os(String),            // system call failure
+    Link(Vec<String>),     // messages of the static linker
+    UnknownSection(String), // no section with this name
+    DuplicateSymbol(String), // symbol defined twice in the same file
+    UnknownNeeded(String), // no DT_NEEDED entry naming this library
+    SymbolInRemovedSection(String), // relocations use a symbol of a section being removed
+    InvalidSegment(usize), // index of a program header the kernel would not load
+    UnsupportedRelocationTable(&'static str), // dynamic relocation table format the operation cannot apply
+    InvalidPageSize(u64),                     // zero or not a power of two
+    WrongFileType { expected: EType, actual: EType },
+    MissingString(String), // string not added to a string table before it was finalized
+    RelocationOutOfSection(u64), // r_offset of a field that does not fit in the section it patches
+    UnalignedFileOffset(u64), // NT_FILE mapping offset that is not a multiple of the page size
+}
+
+impl Display for ElfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truncated(what) => write!(f, "truncated {}", what),
+            Self::UnsupportedCompression(t) => write!(f, "unsupported compression type {}", t),
+            Self::Compression(e) => write!(f, "compression failed: {}", e),
+            Self::SizeMismatch { expected, actual } => {
+                write!(f, "expected {} bytes but got {}", expected, actual)
+            }
+            Self::InvalidSection(i) => write!(f, "invalid section index {}", i),
+            Self::Unmapped(a) => write!(f, "address {:#x} is not mapped", a),
+            Self::UnsupportedRelocation(t) => write!(f, "unsupported relocation type {}", t),
+            Self::UnresolvedSymbol(name) => write!(f, "undefined symbol {}", name),
+            Self::RelocationOverflow(p) => write!(f, "relocation overflow at {:#x}", p),
+            Self::Os(e) => write!(f, "{}", e),
+            Self::Link(diagnostics) => write!(f, "{}", diagnostics.join("\n")),
+            Self::UnknownSection(name) => write!(f, "no section named {}", name),
+            Self::DuplicateSymbol(name) => write!(f, "symbol {} is already defined", name),
+            Self::UnknownNeeded(name) => write!(f, "{} is not a needed library", name),
+            Self::SymbolInRemovedSection(name) => {
+                write!(f, "symbol {} required but not present", name)
+            }
+            Self::InvalidSegment(i) => write!(f, "invalid segment {}", i),
+            Self::UnsupportedRelocationTable(t) => {
+                write!(f, "unsupported relocation table format {}", t)
+            }
+            Self::InvalidPageSize(size) => write!(f, "invalid page size {:#x}", size),
+            Self::WrongFileType { expected, actual } => {
+                write!(f, "expected a {:?} file but got {:?}", expected, actual)
+            }
+            Self::MissingString(s) => write!(f, "{:?} is not in the string table", s),
+            Self::UnalignedFileOffset(offset) => {
+                write!(
+                    f,
+                    "file offset {:#x} is not a multiple of the page size",
+                    offset
+                )
+            }
+            Self::RelocationOutOfSection(offset) => {
+                write!(
+                    f,
+                    "relocation at {:#x} is past the end of its section",
+                    offset
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ElfError {}
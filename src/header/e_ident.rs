@@ -83,11 +83,12 @@ impl Into<u8> for EI_DATA {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[repr(u8)]
-enum EI_OSABI {
+pub enum EI_OSABI {
     ELFOSABI_SYSV = 0, // System V ABI
     ELFOSABI_HPUX = 1, // HP-UX operating system
+    ELFOSABI_GNU = 3,  // GNU extensions, STT_GNU_IFUNC and STB_GNU_UNIQUE
     UNSPECIFIED(u8),
     ELFOSABI_STANDALONE = 255, // Standalone (embedded) application
 }
@@ -104,6 +105,7 @@ impl From<u8> for EI_OSABI {
         match value {
             0 => Self::ELFOSABI_SYSV,
             1 => Self::ELFOSABI_HPUX,
+            3 => Self::ELFOSABI_GNU,
             255 => Self::ELFOSABI_STANDALONE,
             _ => Self::UNSPECIFIED(value),
         }
@@ -114,6 +116,7 @@ impl Into<u8> for EI_OSABI {
     fn into(self) -> u8 {
         match self {
             Self::ELFOSABI_HPUX => 1,
+            Self::ELFOSABI_GNU => 3,
             Self::ELFOSABI_STANDALONE => 255,
             Self::ELFOSABI_SYSV => 0,
             Self::UNSPECIFIED(v) => v,
@@ -134,6 +137,12 @@ pub struct EIdent {
     EI_NIDENT: u8, // 16 Size of e_ident[]
 }
 
+impl Default for EIdent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EIdent {
     pub fn new() -> Self {
         Self {
@@ -148,6 +157,12 @@ impl EIdent {
             UNSPECIFIED: [0; 5],
         }
     }
+    pub fn os_abi(&self) -> EI_OSABI {
+        self.EI_OSABI
+    }
+    pub fn set_os_abi(&mut self, abi: EI_OSABI) {
+        self.EI_OSABI = abi;
+    }
     pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
         let (remaining, _magic) = tag([0x7f, 69, 76, 70])(raw)?;
         let (remaining, class) = EI_CLASS::parse(remaining)?;
@@ -181,9 +196,9 @@ impl Into<Vec<u8>> for EIdent {
         out.extend(self.EI_MAGIC);
         out.push(self.EI_CLASS.into());
         out.push(self.EI_DATA.into());
-        out.push(self.EI_VERSION.into());
+        out.push(self.EI_VERSION);
         out.push(self.EI_OSABI.into());
-        out.push(self.EI_ABIVERSION.into());
+        out.push(self.EI_ABIVERSION);
         out.push(self.EI_PAD);
         out.extend(self.UNSPECIFIED);
         out.push(self.EI_NIDENT);
@@ -197,9 +212,9 @@ impl Into<Vec<u8>> for &EIdent {
         out.extend(self.EI_MAGIC);
         out.push(self.EI_CLASS.into());
         out.push(self.EI_DATA.into());
-        out.push(self.EI_VERSION.into());
+        out.push(self.EI_VERSION);
         out.push(self.EI_OSABI.into());
-        out.push(self.EI_ABIVERSION.into());
+        out.push(self.EI_ABIVERSION);
         out.push(self.EI_PAD);
         out.extend(self.UNSPECIFIED);
         out.push(self.EI_NIDENT);
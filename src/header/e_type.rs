@@ -4,7 +4,7 @@ use nom::number;
 use nom::IResult;
 use serde::Serialize;
 
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[repr(u16)]
 pub enum EType {
     /*
@@ -58,7 +58,7 @@ impl Into<Vec<u8>> for &EVersion {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Elf64Ehdr {
     pub e_ident: EIdent,     /* ELF identification */
     pub e_type: EType,       /* Object file type */
@@ -0,0 +1,275 @@
+use super::common::write_at;
+use super::elf64::Elf64;
+use super::error::ElfError;
+use super::header::e_type::EType;
+use super::program::p_type::PType;
+use super::program::program_header::PFlags;
+use enumflags2::BitFlags;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub use super::common::PAGE_SIZE;
+
+fn page_down(v: u64) -> u64 {
+    v & !(PAGE_SIZE - 1)
+}
+
+fn page_up(v: u64) -> u64 {
+    page_down(v.saturating_add(PAGE_SIZE - 1))
+}
+
+/*
+ * pages of [start, end) share the permissions, only the pages that were
+ * written hold data, the others read as zeros
+ */
+#[derive(Debug, Clone)]
+struct Mapping {
+    end: u64,
+    flags: BitFlags<PFlags>,
+}
+
+/*
+ * contiguous pages with the same permissions
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryRegion {
+    pub start: u64,
+    pub end: u64,
+    pub flags: BitFlags<PFlags>,
+    pub relro: bool, /* Made read only by ld.so once relocations are applied */
+}
+
+/*
+ * sparse, page granular copy of a loaded elf. permissions are recorded but
+ * not enforced, write is the loader's view of memory before the final
+ * mprotect calls
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MemoryImage {
+    pub load_bias: u64, /* Added to every link time address */
+    pub entry: u64,
+    pub relro: Vec<(u64, u64)>, /* Page aligned [start, end) of PT_GNU_RELRO */
+    mappings: BTreeMap<u64, Mapping>,
+    pages: BTreeMap<u64, Box<[u8]>>,
+}
+
+impl MemoryImage {
+    fn mapping(&self, addr: u64) -> Option<&Mapping> {
+        self.mappings
+            .range(..=addr)
+            .next_back()
+            .map(|(_, m)| m)
+            .filter(|m| addr < m.end)
+    }
+    pub fn is_mapped(&self, addr: u64) -> bool {
+        self.mapping(addr).is_some()
+    }
+    pub fn permissions(&self, addr: u64) -> Option<BitFlags<PFlags>> {
+        self.mapping(addr).map(|m| m.flags)
+    }
+    pub fn is_relro(&self, addr: u64) -> bool {
+        self.relro.iter().any(|(s, e)| *s <= addr && addr < *e)
+    }
+    /*
+     * bytes at addr, None if any of them is not mapped
+     */
+    pub fn read(&self, addr: u64, len: usize) -> Option<Vec<u8>> {
+        let mut out: Vec<u8> = Vec::with_capacity(len);
+        let mut cursor = addr;
+        while out.len() < len {
+            self.mapping(cursor)?;
+            let start = (cursor - page_down(cursor)) as usize;
+            let count = (PAGE_SIZE as usize - start).min(len - out.len());
+            match self.pages.get(&page_down(cursor)) {
+                Some(page) => out.extend_from_slice(&page[start..start + count]),
+                None => out.resize(out.len() + count, 0),
+            }
+            cursor = cursor.checked_add(count as u64)?;
+        }
+        Some(out)
+    }
+    pub fn read_u64(&self, addr: u64) -> Option<u64> {
+        self.read(addr, 8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+    }
+    pub fn write(&mut self, addr: u64, data: &[u8]) -> Result<(), ElfError> {
+        let mut cursor = addr;
+        while cursor < addr.saturating_add(data.len() as u64) {
+            cursor = self.mapping(cursor).ok_or(ElfError::Unmapped(cursor))?.end;
+        }
+        let mut written = 0usize;
+        while written < data.len() {
+            let cursor = addr + written as u64;
+            let page = self
+                .pages
+                .entry(page_down(cursor))
+                .or_insert_with(|| vec![0u8; PAGE_SIZE as usize].into_boxed_slice());
+            let start = (cursor - page_down(cursor)) as usize;
+            let count = (PAGE_SIZE as usize - start).min(data.len() - written);
+            page[start..start + count].copy_from_slice(&data[written..written + count]);
+            written += count;
+        }
+        Ok(())
+    }
+    pub fn write_u64(&mut self, addr: u64, value: u64) -> Result<(), ElfError> {
+        self.write(addr, &value.to_le_bytes())
+    }
+    pub fn regions(&self) -> Vec<MemoryRegion> {
+        let mut out: Vec<MemoryRegion> = Vec::new();
+        for (start, mapping) in &self.mappings {
+            /* a mapping is split where PT_GNU_RELRO starts or ends */
+            let mut cuts: Vec<u64> = self
+                .relro
+                .iter()
+                .flat_map(|(s, e)| [*s, *e])
+                .filter(|c| start < c && *c < mapping.end)
+                .chain([*start, mapping.end])
+                .collect();
+            cuts.sort_unstable();
+            cuts.dedup();
+            for range in cuts.windows(2) {
+                let relro = self.is_relro(range[0]);
+                match out.last_mut() {
+                    Some(r)
+                        if r.end == range[0] && r.flags == mapping.flags && r.relro == relro =>
+                    {
+                        r.end = range[1]
+                    }
+                    _ => out.push(MemoryRegion {
+                        start: range[0],
+                        end: range[1],
+                        flags: mapping.flags,
+                        relro,
+                    }),
+                }
+            }
+        }
+        out
+    }
+    /*
+     * pages of a later segment replace the ones already mapped, like
+     * mmap with MAP_FIXED
+     */
+    fn map(&mut self, start: u64, end: u64, flags: BitFlags<PFlags>) {
+        if start >= end {
+            return;
+        }
+        let overlapping: Vec<u64> = self
+            .mappings
+            .range(..end)
+            .filter(|(_, m)| m.end > start)
+            .map(|(s, _)| *s)
+            .collect();
+        for s in overlapping {
+            let m = self.mappings.remove(&s).unwrap();
+            if s < start {
+                self.mappings.insert(
+                    s,
+                    Mapping {
+                        end: start,
+                        flags: m.flags,
+                    },
+                );
+            }
+            if m.end > end {
+                self.mappings.insert(end, m);
+            }
+        }
+        self.mappings.insert(start, Mapping { end, flags });
+        let written: Vec<u64> = self.pages.range(start..end).map(|(a, _)| *a).collect();
+        for addr in written {
+            self.pages.remove(&addr);
+        }
+    }
+}
+
+impl Elf64 {
+    /*
+     * maps the PT_LOAD segments the way the kernel binfmt_elf does. file
+     * pages are mapped whole, the bytes around a segment in its first and
+     * last page are taken from the other segments that cover them in the
+     * file and are zero otherwise. after p_filesz of a segment with .bss
+     * they are zero up to p_memsz. ET_EXEC files are loaded at their
+     * link addresses, for ET_DYN base is aligned down to the largest p_align.
+     * like the kernel a PT_LOAD with more file than memory, an offset that
+     * is not congruent to its address or an end past the address space is
+     * refused
+     */
+    pub fn load_image(&self, base: u64) -> Result<MemoryImage, ElfError> {
+        let mut image = MemoryImage::default();
+        let loads: Vec<_> = self
+            .programs
+            .iter()
+            .filter(|p| p.header.p_type == PType::PT_LOAD)
+            .collect();
+        if self.header.e_type == EType::ET_DYN {
+            let align = loads
+                .iter()
+                .map(|p| p.header.p_align)
+                .filter(|a| a.is_power_of_two())
+                .fold(PAGE_SIZE, u64::max);
+            let first = loads.iter().map(|p| p.header.p_vaddr).min().unwrap_or(0);
+            image.load_bias = (base & !(align - 1)).wrapping_sub(page_down(first));
+        }
+        /*
+         * the file as seen through the segments, enough to fill the
+         * partial pages around each PT_LOAD
+         */
+        let mut file: Vec<u8> = Vec::new();
+        for program in self.programs.iter().filter(|p| !p.data.inner.is_empty()) {
+            write_at(
+                &mut file,
+                program.header.p_offset as usize,
+                &program.data.inner,
+            );
+        }
+        for (index, program) in self.programs.iter().enumerate() {
+            let h = &program.header;
+            if h.p_type != PType::PT_LOAD {
+                continue;
+            }
+            let memory_end = h
+                .p_vaddr
+                .checked_add(h.p_memsz)
+                .and_then(|e| e.checked_add(PAGE_SIZE - 1));
+            if h.p_filesz > h.p_memsz
+                || h.p_offset % PAGE_SIZE != h.p_vaddr % PAGE_SIZE
+                || memory_end.is_none()
+            {
+                return Err(ElfError::InvalidSegment(index));
+            }
+            let start = page_down(h.p_vaddr).wrapping_add(image.load_bias);
+            let end = page_up(h.p_vaddr + h.p_memsz).wrapping_add(image.load_bias);
+            image.map(start, end, h.p_flags);
+            let file_start = h.p_offset.saturating_sub(h.p_vaddr - page_down(h.p_vaddr));
+            let file_end = match h.p_memsz > h.p_filesz {
+                true => h.p_offset.saturating_add(h.p_filesz),
+                false => page_up(h.p_offset.saturating_add(h.p_filesz)),
+            };
+            let bytes = file
+                .get(file_start as usize..(file_end as usize).min(file.len()))
+                .unwrap_or_default();
+            let mapped = end.wrapping_sub(start) as usize;
+            image.write(start, &bytes[..bytes.len().min(mapped)])?;
+        }
+        for program in &self.programs {
+            if program.header.p_type == PType::PT_GNU_RELRO {
+                let start = page_down(program.header.p_vaddr);
+                let end = page_down(
+                    program
+                        .header
+                        .p_vaddr
+                        .saturating_add(program.header.p_memsz),
+                );
+                if end > start {
+                    image.relro.push((
+                        start.wrapping_add(image.load_bias),
+                        end.wrapping_add(image.load_bias),
+                    ));
+                }
+            }
+        }
+        image.entry = self.header.e_entry.wrapping_add(image.load_bias);
+        Ok(image)
+    }
+}
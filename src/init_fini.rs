@@ -0,0 +1,274 @@
+use super::elf64::{covers, Elf64};
+use super::header::e_type::EType;
+use super::section::elf64_dyn::DynamicTag;
+use super::section::relr::RelrSection;
+use super::section::section::SectionData;
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+use super::section::symbol::{Elf64_Rela, Elf64_Sym, RelocationType, SymbolType};
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ArrayKind {
+    PreInit,
+    Init,
+    Fini,
+}
+
+impl ArrayKind {
+    fn section_type(self) -> ShType {
+        match self {
+            Self::PreInit => ShType::SHT_PREINIT_ARRAY,
+            Self::Init => ShType::SHT_INIT_ARRAY,
+            Self::Fini => ShType::SHT_FINI_ARRAY,
+        }
+    }
+    fn dynamic_tags(self) -> (DynamicTag, DynamicTag) {
+        match self {
+            Self::PreInit => (DynamicTag::DT_PREINIT_ARRAY, DynamicTag::DT_PREINIT_ARRAYSZ),
+            Self::Init => (DynamicTag::DT_INIT_ARRAY, DynamicTag::DT_INIT_ARRAYSZ),
+            Self::Fini => (DynamicTag::DT_FINI_ARRAY, DynamicTag::DT_FINI_ARRAYSZ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArrayEntry {
+    pub slot: u64,    /* Address of the slot, offset in its section for relocatable files */
+    pub address: u64, /* Value of the slot after relocation, 0 if it could not be resolved */
+    pub symbol: Option<String>,
+    pub symbol_offset: u64, /* Distance of address from the start of symbol */
+    pub resolver: bool,     /* Filled by R_X86_64_IRELATIVE, address is the ifunc resolver */
+}
+
+struct Relocated {
+    value: Option<u64>,
+    symbol: Option<String>,
+    resolver: bool,
+}
+
+impl Elf64 {
+    pub fn preinit_array(&self) -> Vec<ArrayEntry> {
+        self.address_array(ArrayKind::PreInit)
+    }
+    pub fn init_array(&self) -> Vec<ArrayEntry> {
+        self.address_array(ArrayKind::Init)
+    }
+    pub fn fini_array(&self) -> Vec<ArrayEntry> {
+        self.address_array(ArrayKind::Fini)
+    }
+    /*
+     * entries are read from the array sections, or through the DT_*_ARRAY
+     * tags when there are no section headers. slots are relocated the way
+     * the dynamic loader (or the linker for relocatable files) would do it
+     * with a load base of zero, RELR relocated slots already hold that value
+     */
+    pub fn address_array(&self, kind: ArrayKind) -> Vec<ArrayEntry> {
+        let mut out: Vec<ArrayEntry> = Vec::new();
+        let relocatable = self.header.e_type == EType::ET_REL;
+        let dynamic_relocations = if relocatable {
+            HashMap::new()
+        } else {
+            self.dynamic_relocations()
+        };
+        let mut found_section = false;
+        for (index, section) in self.sections.iter().enumerate() {
+            let entries = match (&section.data, section.header.sh_type == kind.section_type()) {
+                (SectionData::AddressArray(a), true) => &a.entries,
+                _ => continue,
+            };
+            found_section = true;
+            let relocations = if relocatable {
+                self.section_relocations(index)
+            } else {
+                HashMap::new()
+            };
+            for (i, value) in entries.iter().enumerate() {
+                let slot = section.header.sh_addr + 8 * i as u64;
+                let relocation = match relocatable {
+                    true => relocations.get(&(8 * i as u64)),
+                    false => dynamic_relocations.get(&slot),
+                };
+                out.push(self.array_entry(slot, *value, relocation, relocatable));
+            }
+        }
+        if found_section || relocatable {
+            return out;
+        }
+        let (array_tag, size_tag) = kind.dynamic_tags();
+        let dynamic = match self.dynamic() {
+            Some(d) => d,
+            None => return out,
+        };
+        let (start, size) = match (dynamic.get(array_tag), dynamic.get(size_tag)) {
+            (Some(start), Some(size)) => (start, size),
+            _ => return out,
+        };
+        let symbols = self.dynamic_table_symbols(&dynamic);
+        if let Some(raw) = self.read_at_address(start, size as usize) {
+            for (i, chunk) in raw.chunks_exact(8).enumerate() {
+                let slot = start + 8 * i as u64;
+                let value = u64::from_le_bytes(chunk.try_into().unwrap());
+                let mut entry =
+                    self.array_entry(slot, value, dynamic_relocations.get(&slot), false);
+                if entry.symbol.is_none() {
+                    if let Some(s) = symbols.iter().find(|s| covers(s, entry.address)) {
+                        entry.symbol = Some(s.name.clone());
+                        entry.symbol_offset = entry.address - s.st_value;
+                    }
+                }
+                out.push(entry);
+            }
+        }
+        out
+    }
+    fn array_entry(
+        &self,
+        slot: u64,
+        value: u64,
+        relocation: Option<&Relocated>,
+        relocatable: bool,
+    ) -> ArrayEntry {
+        let (address, mut symbol, resolver) = match relocation {
+            Some(r) => (r.value.unwrap_or(0), r.symbol.clone(), r.resolver),
+            None => (value, None, false),
+        };
+        let mut symbol_offset = 0;
+        if symbol.is_none() && !relocatable {
+            if let Some(s) = self.symbol_for_address(address) {
+                symbol = Some(s.name.clone());
+                symbol_offset = address - s.st_value;
+            }
+        }
+        ArrayEntry {
+            slot,
+            address,
+            symbol,
+            symbol_offset,
+            resolver,
+        }
+    }
+    /*
+     * relocations from the allocated (dynamic) relocation sections keyed by
+     * the address they patch. without them the tables are found through
+     * DT_RELA and DT_RELR, and the symbols through DT_SYMTAB
+     */
+    fn dynamic_relocations(&self) -> HashMap<u64, Relocated> {
+        let mut out: HashMap<u64, Relocated> = HashMap::new();
+        let mut tables: Vec<(Vec<Elf64_Rela>, Option<&Vec<Elf64_Sym>>)> = Vec::new();
+        for section in &self.sections {
+            if let SectionData::Rela(r) = &section.data {
+                if section.header.sh_flags.contains(SHFlags::SHF_ALLOC) {
+                    let symbols = self.linked_symbols(section.header.sh_link as usize);
+                    tables.push((r.rela_entries.clone(), symbols));
+                }
+            }
+        }
+        let dynamic = self.dynamic().filter(|_| tables.is_empty());
+        if let Some(dynamic) = &dynamic {
+            if let (Some(start), Some(size)) = (
+                dynamic.get(DynamicTag::DT_RELR),
+                dynamic.get(DynamicTag::DT_RELRSZ),
+            ) {
+                let raw = self
+                    .read_at_address(start, size as usize)
+                    .unwrap_or_default();
+                for addr in RelrSection::from(raw).addresses() {
+                    let value = self
+                        .read_at_address(addr, 8)
+                        .map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+                    let relocated = Relocated {
+                        value,
+                        symbol: None,
+                        resolver: false,
+                    };
+                    out.insert(addr, relocated);
+                }
+            }
+            let rela = self.dynamic_rela(dynamic, DynamicTag::DT_RELA, DynamicTag::DT_RELASZ);
+            tables.push((rela, None));
+        }
+        for (rela, symbols) in &tables {
+            for entry in rela {
+                let symbol = match (symbols, &dynamic) {
+                    (Some(s), _) => s.get(entry.symbol_index as usize).cloned(),
+                    (None, Some(d)) => self.dynamic_symbol(d, entry.symbol_index),
+                    (None, None) => None,
+                };
+                let symbol = symbol.as_ref();
+                let relocated = match entry.relocation_type {
+                    RelocationType::R_X86_64_RELATIVE | RelocationType::R_X86_64_IRELATIVE => {
+                        Relocated {
+                            value: Some(entry.r_addend),
+                            symbol: None,
+                            resolver: entry.relocation_type == RelocationType::R_X86_64_IRELATIVE,
+                        }
+                    }
+                    RelocationType::R_X86_64_64 => Relocated {
+                        value: symbol
+                            .filter(|s| s.st_shndx != 0)
+                            .map(|s| s.st_value.wrapping_add(entry.r_addend)),
+                        symbol: symbol.map(|s| s.name.clone()),
+                        resolver: false,
+                    },
+                    _ => continue,
+                };
+                out.insert(entry.r_offset, relocated);
+            }
+        }
+        out
+    }
+    /*
+     * relocations applying to one section of a relocatable file keyed by
+     * the offset they patch, section symbols are replaced by the function
+     * they point into
+     */
+    fn section_relocations(&self, target: usize) -> HashMap<u64, Relocated> {
+        let mut out: HashMap<u64, Relocated> = HashMap::new();
+        for section in &self.sections {
+            let rela = match &section.data {
+                SectionData::Rela(r) if section.header.sh_info as usize == target => r,
+                _ => continue,
+            };
+            let symbols = self.linked_symbols(section.header.sh_link as usize);
+            for entry in &rela.rela_entries {
+                if entry.relocation_type != RelocationType::R_X86_64_64 {
+                    continue;
+                }
+                let symbol = match symbols.and_then(|s| s.get(entry.symbol_index as usize)) {
+                    Some(s) => s,
+                    None => continue,
+                };
+                let value = symbol.st_value.wrapping_add(entry.r_addend);
+                let name = match symbol.symbol_type {
+                    SymbolType::STT_SECTION => symbols
+                        .unwrap()
+                        .iter()
+                        .find(|s| {
+                            s.st_shndx == symbol.st_shndx
+                                && matches!(s.symbol_type, SymbolType::STT_FUNC)
+                                && s.st_value <= value
+                                && value < s.st_value.saturating_add(s.st_size.max(1))
+                        })
+                        .map(|s| s.name.clone())
+                        .or_else(|| {
+                            self.sections
+                                .get(symbol.st_shndx as usize)
+                                .map(|s| format!("{}+{:#x}", s.name, value))
+                        }),
+                    _ => Some(symbol.name.clone()),
+                };
+                out.insert(
+                    entry.r_offset,
+                    Relocated {
+                        value: Some(value),
+                        symbol: name,
+                        resolver: false,
+                    },
+                );
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,123 @@
+use super::elf64::Elf64;
+use super::error::ElfError;
+use super::patch::Patch;
+use super::program::program_header::PFlags;
+use super::section::symbol::SymbolType;
+
+const JMP_REL32: u8 = 0xe9;
+const JMP_SIZE: u64 = 5;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InjectOptions {
+    /*
+     * use a PT_NOTE entry for a new segment when there is no PT_NULL one
+     * instead of moving the program header table. the notes stay in their
+     * sections but are not found through the program headers anymore
+     */
+    pub convert_note: bool,
+    /*
+     * e_entry points at the code, a jump to the original entry follows it
+     * so the program starts when the code runs off its end. the code has
+     * to leave %rsp and %rdx (the ld.so destructor) as they were
+     */
+    pub redirect_entry: bool,
+}
+
+/*
+ * addresses are link time addresses: in a pie they are relative to the
+ * load base, the code reaches the data and the rest of the program with
+ * rip relative addressing
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Injection {
+    pub code_address: u64,
+    pub data_address: u64, /* first page after the code, 0 without data */
+    pub original_entry: u64,
+}
+
+/*
+ * jmp rel32 from address to target
+ */
+fn jump(address: u64, target: u64) -> Result<[u8; 5], ElfError> {
+    let displacement = target.wrapping_sub(address + JMP_SIZE) as i64;
+    let displacement =
+        i32::try_from(displacement).map_err(|_| ElfError::RelocationOverflow(address))?;
+    let mut out = [JMP_REL32, 0, 0, 0, 0];
+    out[1..].copy_from_slice(&displacement.to_le_bytes());
+    Ok(out)
+}
+
+impl Elf64 {
+    /*
+     * maps code in a new R+X segment and data in a new R+W one at the
+     * first page after the code, so the distance between them is known
+     * before injecting. a new segment takes a PT_NULL (or PT_NOTE) entry
+     * of the table when there is one, otherwise the table moves to a
+     * segment at the end of the file like patchelf does
+     */
+    pub fn inject(
+        &mut self,
+        code: &[u8],
+        data: &[u8],
+        options: &InjectOptions,
+    ) -> Result<Injection, ElfError> {
+        let mut patch = Patch::new(self)?;
+        let original_entry = self.header.e_entry;
+        let count = !code.is_empty() as usize + !data.is_empty() as usize;
+        patch.reserve_programs(count, options.convert_note);
+        let (_, code_address) = patch.next_segment();
+        let mut code = code.to_vec();
+        if options.redirect_entry {
+            let at = code_address + code.len() as u64;
+            code.extend(jump(at, original_entry)?);
+        }
+        if !code.is_empty() {
+            patch.add_segment(&code, PFlags::Read | PFlags::Execute, options.convert_note);
+        }
+        let data_address = match data.is_empty() {
+            true => 0,
+            false => {
+                let flags = PFlags::Read | PFlags::Write;
+                patch.add_segment(data, flags, options.convert_note).1
+            }
+        };
+        if options.redirect_entry {
+            patch.header.e_entry = code_address;
+        }
+        *self = patch.finish()?;
+        Ok(Injection {
+            code_address,
+            data_address,
+            original_entry,
+        })
+    }
+    /*
+     * writes a jmp rel32 to target over the first bytes of a function of
+     * .symtab or .dynsym and returns the bytes it replaced, a trampoline
+     * runs them before jumping back after the hook. the jump is relative
+     * so it stays valid wherever a pie is loaded
+     */
+    pub fn hook_function(&mut self, name: &str, target: u64) -> Result<Vec<u8>, ElfError> {
+        let symbol = self
+            .lookup_symbols()
+            .into_iter()
+            .find(|s| s.name == name && s.is_defined() && s.symbol_type == SymbolType::STT_FUNC)
+            .ok_or_else(|| ElfError::UnresolvedSymbol(name.to_string()))?;
+        let (address, size) = (symbol.st_value, symbol.st_size);
+        if size != 0 && size < JMP_SIZE {
+            return Err(ElfError::SizeMismatch {
+                expected: JMP_SIZE,
+                actual: size,
+            });
+        }
+        let code = jump(address, target)?;
+        let mut patch = Patch::new(self)?;
+        let original = patch
+            .read_address(address, code.len())
+            .ok_or(ElfError::Unmapped(address))?
+            .to_vec();
+        patch.write_address(address, &code)?;
+        *self = patch.finish()?;
+        Ok(original)
+    }
+}
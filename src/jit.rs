@@ -0,0 +1,370 @@
+use super::common::PAGE_SIZE;
+use super::elf64::Elf64;
+use super::error::ElfError;
+use super::header::e_type::EType;
+use super::section::section::SectionData;
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+use super::section::symbol::{
+    Elf64_Rela, Elf64_Sym, RelocationType, SectionIndex, SymbolBinding, SymbolType,
+};
+use std::collections::HashMap;
+
+const GOT_SYMBOL: &str = "_GLOBAL_OFFSET_TABLE_";
+const STUB_SIZE: usize = 8; /* jmp *disp32(%rip) and two int3 */
+
+fn align_up(v: usize, align: usize) -> usize {
+    match align {
+        0 | 1 => v,
+        a => v.div_ceil(a) * a,
+    }
+}
+
+/*
+ * one anonymous mapping holding the text (code and plt stubs), the
+ * sections both writable and executable, read only data and writable data
+ * (with .bss, common symbols and the GOT) of a relocatable object, each
+ * part starting on its own page
+ */
+pub struct JitModule {
+    memory: *mut u8,
+    size: usize,
+    symbols: HashMap<String, u64>,
+}
+
+#[derive(Default)]
+struct Layout {
+    section_offsets: HashMap<usize, usize>,
+    common_offsets: HashMap<usize, usize>, /* Symbol index of a SHN_COMMON symbol */
+    got_offsets: HashMap<usize, usize>,    /* Symbol index */
+    stub_offsets: HashMap<usize, usize>,   /* Symbol index */
+    got_start: usize,
+    writable_text_start: usize,
+    rodata_start: usize,
+    data_start: usize,
+    size: usize,
+}
+
+fn needs_got(t: RelocationType) -> bool {
+    matches!(
+        t,
+        RelocationType::R_X86_64_GOTPCREL
+            | RelocationType::R_X86_64_GOTPCRELX
+            | RelocationType::R_X86_64_REX_GOTPCRELX
+    )
+}
+
+impl JitModule {
+    /*
+     * maps the SHF_ALLOC sections of an ET_REL file and applies its
+     * relocations. undefined symbols are asked to the resolver, calls to
+     * them go through a plt stub and a GOT entry so they can be anywhere in
+     * the address space
+     */
+    pub fn load<F>(elf: &Elf64, mut resolver: F) -> Result<Self, ElfError>
+    where
+        F: FnMut(&str) -> Option<u64>,
+    {
+        if elf.header.e_type != EType::ET_REL {
+            return Err(ElfError::WrongFileType {
+                expected: EType::ET_REL,
+                actual: elf.header.e_type,
+            });
+        }
+        let symtab = elf
+            .sections
+            .iter()
+            .position(|s| s.header.sh_type == ShType::SHT_SYMTAB)
+            .ok_or_else(|| ElfError::UnknownSection(".symtab".to_string()))?;
+        let symbols = elf
+            .linked_symbols(symtab)
+            .ok_or(ElfError::InvalidSection(symtab))?;
+        let layout = Self::layout(elf, symtab, symbols);
+        let memory = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                layout.size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if memory == libc::MAP_FAILED {
+            return Err(ElfError::Os(std::io::Error::last_os_error().to_string()));
+        }
+        let mut module = Self {
+            memory: memory as *mut u8,
+            size: layout.size,
+            symbols: HashMap::new(),
+        };
+        let base = module.memory as u64;
+        for (index, offset) in &layout.section_offsets {
+            let section = &elf.sections[*index];
+            if section.header.sh_type != ShType::SHT_NOBITS {
+                let data: Vec<u8> = (&section.data).into();
+                module.bytes_mut(*offset, data.len()).copy_from_slice(&data);
+            }
+        }
+        let mut addresses: Vec<u64> = Vec::with_capacity(symbols.len());
+        for (i, symbol) in symbols.iter().enumerate() {
+            let address = match symbol.section_index() {
+                SectionIndex::SHN_UNDEF if i == 0 => 0,
+                SectionIndex::SHN_UNDEF if symbol.name == GOT_SYMBOL => {
+                    base + layout.got_start as u64
+                }
+                SectionIndex::SHN_UNDEF => match resolver(&symbol.name) {
+                    Some(a) => a,
+                    None if symbol.symbol_binding == SymbolBinding::STB_WEAK => 0,
+                    None => return Err(ElfError::UnresolvedSymbol(symbol.name.clone())),
+                },
+                SectionIndex::SHN_ABS => symbol.st_value,
+                SectionIndex::SHN_COMMON => base + layout.common_offsets[&i] as u64,
+                SectionIndex::Section(s) => match layout.section_offsets.get(&(s as usize)) {
+                    Some(offset) => base + *offset as u64 + symbol.st_value,
+                    None => 0,
+                },
+                SectionIndex::SHN_XINDEX => return Err(ElfError::InvalidSection(i)),
+            };
+            addresses.push(address);
+            if symbol.is_defined()
+                && symbol.symbol_binding != SymbolBinding::STB_LOCAL
+                && !matches!(symbol.symbol_type, SymbolType::STT_SECTION)
+            {
+                module.symbols.insert(symbol.name.clone(), address);
+            }
+        }
+        for (symbol, offset) in &layout.got_offsets {
+            module.write(*offset, &addresses[*symbol].to_le_bytes());
+        }
+        for (symbol, offset) in &layout.stub_offsets {
+            let got = layout.got_offsets[symbol] as i64;
+            let disp = (got - (*offset as i64 + 6)) as i32;
+            let mut stub = vec![0xff, 0x25];
+            stub.extend(disp.to_le_bytes());
+            stub.extend([0xcc, 0xcc]);
+            module.write(*offset, &stub);
+        }
+        for section in &elf.sections {
+            let relocations = match &section.data {
+                SectionData::Rela(r) if section.header.sh_link as usize == symtab => r,
+                _ => continue,
+            };
+            let info = section.header.sh_info as usize;
+            let target = match layout.section_offsets.get(&info) {
+                Some(t) => (*t, elf.sections[info].header.sh_size),
+                None => continue,
+            };
+            for rela in &relocations.rela_entries {
+                module.apply(rela, target, &addresses, symbols, &layout)?;
+            }
+        }
+        let (text, writable_text) = (layout.writable_text_start, layout.rodata_start);
+        module.protect(0, text, libc::PROT_READ | libc::PROT_EXEC)?;
+        let rwx = libc::PROT_READ | libc::PROT_WRITE | libc::PROT_EXEC;
+        module.protect(text, writable_text, rwx)?;
+        module.protect(layout.rodata_start, layout.data_start, libc::PROT_READ)?;
+        Ok(module)
+    }
+    /*
+     * defined global and weak symbols of the object
+     */
+    pub fn symbol_address(&self, name: &str) -> Option<u64> {
+        self.symbols.get(name).copied()
+    }
+    pub fn symbols(&self) -> &HashMap<String, u64> {
+        &self.symbols
+    }
+    /*
+     * the symbol as a function pointer. unsafe because T must be an
+     * extern "C" fn type matching the code of the symbol, and the module
+     * must outlive the pointer
+     */
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn function<T: Copy>(&self, name: &str) -> Option<T> {
+        assert_eq!(std::mem::size_of::<T>(), std::mem::size_of::<usize>());
+        let address = self.symbol_address(name)? as usize;
+        Some(std::mem::transmute_copy(&address))
+    }
+    fn layout(elf: &Elf64, symtab: usize, symbols: &[Elf64_Sym]) -> Layout {
+        let mut layout = Layout::default();
+        let loaded: Vec<usize> = elf
+            .sections
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.header.sh_flags.contains(SHFlags::SHF_ALLOC) && s.header.sh_size > 0)
+            .map(|(i, _)| i)
+            .collect();
+        let mut got_symbols: Vec<usize> = Vec::new();
+        let mut stub_symbols: Vec<usize> = Vec::new();
+        for section in &elf.sections {
+            let relocations = match &section.data {
+                SectionData::Rela(r) if section.header.sh_link as usize == symtab => r,
+                _ => continue,
+            };
+            if !loaded.contains(&(section.header.sh_info as usize)) {
+                continue;
+            }
+            for rela in &relocations.rela_entries {
+                let index = rela.symbol_index as usize;
+                let undefined = symbols.get(index).map(|s| !s.is_defined()).unwrap_or(false);
+                let stub = undefined && rela.relocation_type == RelocationType::R_X86_64_PLT32;
+                if (stub || needs_got(rela.relocation_type)) && !got_symbols.contains(&index) {
+                    got_symbols.push(index);
+                }
+                if stub && !stub_symbols.contains(&index) {
+                    stub_symbols.push(index);
+                }
+            }
+        }
+        let mut offset = 0usize;
+        let place = |layout: &mut Layout, index: usize, offset: &mut usize| {
+            let header = &elf.sections[index].header;
+            *offset = align_up(*offset, header.sh_addralign as usize);
+            layout.section_offsets.insert(index, *offset);
+            *offset += header.sh_size as usize;
+        };
+        let flags = |i: &usize| elf.sections[*i].header.sh_flags;
+        for index in loaded.iter().filter(|i| {
+            flags(i).contains(SHFlags::SHF_EXECINSTR) && !flags(i).contains(SHFlags::SHF_WRITE)
+        }) {
+            place(&mut layout, *index, &mut offset);
+        }
+        for index in &stub_symbols {
+            offset = align_up(offset, STUB_SIZE);
+            layout.stub_offsets.insert(*index, offset);
+            offset += STUB_SIZE;
+        }
+        offset = align_up(offset, PAGE_SIZE as usize);
+        layout.writable_text_start = offset;
+        for index in loaded.iter().filter(|i| {
+            flags(i).contains(SHFlags::SHF_EXECINSTR) && flags(i).contains(SHFlags::SHF_WRITE)
+        }) {
+            place(&mut layout, *index, &mut offset);
+        }
+        offset = align_up(offset, PAGE_SIZE as usize);
+        layout.rodata_start = offset;
+        for index in loaded.iter().filter(|i| {
+            !flags(i).contains(SHFlags::SHF_EXECINSTR) && !flags(i).contains(SHFlags::SHF_WRITE)
+        }) {
+            place(&mut layout, *index, &mut offset);
+        }
+        offset = align_up(offset, PAGE_SIZE as usize);
+        layout.data_start = offset;
+        for index in loaded.iter().filter(|i| {
+            !flags(i).contains(SHFlags::SHF_EXECINSTR) && flags(i).contains(SHFlags::SHF_WRITE)
+        }) {
+            place(&mut layout, *index, &mut offset);
+        }
+        for (i, symbol) in symbols.iter().enumerate() {
+            if symbol.section_index() == SectionIndex::SHN_COMMON {
+                /* st_value of a common symbol is its alignment */
+                offset = align_up(offset, symbol.st_value as usize);
+                layout.common_offsets.insert(i, offset);
+                offset += symbol.st_size as usize;
+            }
+        }
+        offset = align_up(offset, 8);
+        layout.got_start = offset;
+        for index in got_symbols {
+            layout.got_offsets.insert(index, offset);
+            offset += 8;
+        }
+        layout.size = align_up(offset.max(1), PAGE_SIZE as usize);
+        layout
+    }
+    fn apply(
+        &mut self,
+        rela: &Elf64_Rela,
+        target: (usize, u64), /* Offset and size of the section */
+        addresses: &[u64],
+        symbols: &[Elf64_Sym],
+        layout: &Layout,
+    ) -> Result<(), ElfError> {
+        let base = self.memory as u64;
+        let index = rela.symbol_index as usize;
+        let t = rela.relocation_type;
+        if t == RelocationType::R_X86_64_NONE {
+            return Ok(());
+        }
+        let size = t
+            .field_size()
+            .ok_or(ElfError::UnsupportedRelocation(t.into()))?;
+        match rela.r_offset.checked_add(size as u64) {
+            Some(end) if end <= target.1 => (),
+            _ => return Err(ElfError::RelocationOutOfSection(rela.r_offset)),
+        }
+        let offset = target.0 + rela.r_offset as usize;
+        let place = base + offset as u64;
+        let s = *addresses
+            .get(index)
+            .ok_or(ElfError::InvalidSection(index))?;
+        let a = rela.r_addend;
+        let value = match t {
+            RelocationType::R_X86_64_PLT32 => {
+                let direct = s.wrapping_add(a).wrapping_sub(place);
+                let defined = symbols.get(index).map(|s| s.is_defined()).unwrap_or(true);
+                match (defined || t.fits(direct), layout.stub_offsets.get(&index)) {
+                    (false, Some(stub)) => {
+                        (base + *stub as u64).wrapping_add(a).wrapping_sub(place)
+                    }
+                    _ => direct,
+                }
+            }
+            t if needs_got(t) => {
+                let got = base + layout.got_offsets[&index] as u64;
+                got.wrapping_add(a).wrapping_sub(place)
+            }
+            RelocationType::R_X86_64_GOTOFF64 => s
+                .wrapping_add(a)
+                .wrapping_sub(base + layout.got_start as u64),
+            RelocationType::R_X86_64_GOTPC32 => (base + layout.got_start as u64)
+                .wrapping_add(a)
+                .wrapping_sub(place),
+            RelocationType::R_X86_64_SIZE32 | RelocationType::R_X86_64_SIZE64 => {
+                let size = symbols.get(index).map(|s| s.st_size).unwrap_or(0);
+                size.wrapping_add(a)
+            }
+            t if t.is_tls() => return Err(ElfError::UnsupportedRelocation(t.into())),
+            t => t
+                .compute(s, a, place, 0)
+                .ok_or(ElfError::UnsupportedRelocation(t.into()))?,
+        };
+        if !t.fits(value) {
+            return Err(ElfError::RelocationOverflow(place));
+        }
+        self.write(offset, &value.to_le_bytes()[..size]);
+        Ok(())
+    }
+    fn bytes_mut(&mut self, offset: usize, len: usize) -> &mut [u8] {
+        assert!(offset + len <= self.size);
+        unsafe { std::slice::from_raw_parts_mut(self.memory.add(offset), len) }
+    }
+    fn write(&mut self, offset: usize, data: &[u8]) {
+        self.bytes_mut(offset, data.len()).copy_from_slice(data);
+    }
+    fn protect(&self, start: usize, end: usize, prot: libc::c_int) -> Result<(), ElfError> {
+        if end <= start {
+            return Ok(());
+        }
+        let result = unsafe {
+            libc::mprotect(
+                self.memory.add(start) as *mut libc::c_void,
+                end - start,
+                prot,
+            )
+        };
+        match result {
+            0 => Ok(()),
+            _ => Err(ElfError::Os(std::io::Error::last_os_error().to_string())),
+        }
+    }
+}
+
+impl Drop for JitModule {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.memory as *mut libc::c_void, self.size);
+        }
+    }
+}
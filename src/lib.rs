@@ -1,8 +1,33 @@
+#![allow(clippy::from_over_into)]
+#![allow(clippy::upper_case_acronyms)]
+#![allow(clippy::module_inception)]
+
 mod basic_types;
+pub mod builder;
 pub mod common;
+pub mod coredump;
+pub mod debuginfo;
 pub mod elf64;
+pub mod error;
 pub mod header;
+pub mod image;
+pub mod init_fini;
+pub mod inject;
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+pub mod jit;
+pub mod linker;
+pub mod note;
+pub mod patch;
+pub mod plt;
 pub mod program;
+pub mod relocate;
 pub mod section;
+pub mod section_edit;
+pub mod segment_map;
+pub mod strip;
+pub mod symbol_edit;
+pub mod symbolizer;
+pub mod symbols;
+pub mod writer;
 
 pub use crate::elf64::Elf64;
@@ -0,0 +1,1539 @@
+use super::builder::{align_up, null_header, section_program, Placed};
+use super::common::{write_at, PAGE_SIZE};
+use super::elf64::Elf64;
+use super::error::ElfError;
+use super::header::e_machine::EMachine;
+use super::header::e_type::EType;
+use super::header::header::Elf64Ehdr;
+use super::program::p_type::PType;
+use super::program::program::Program;
+use super::program::program_header::{Elf64_Phdr, PFlags};
+use super::section::elf64_dyn::{DynamicSection, DynamicTag, Elf64_Dyn};
+use super::section::section::{Section, SectionData, StringSection, SymbolSection};
+use super::section::section_header::Elf64Shdr;
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+use super::section::string_table::StringTableBuilder;
+use super::section::symbol::{
+    Elf64_Rela, Elf64_Sym, RelocationType, SectionIndex, SymbolBinding, SymbolType,
+};
+use enumflags2::BitFlags;
+use std::collections::HashMap;
+
+const DF_1_PIE: u64 = 0x08000000;
+const GOT_SYMBOL: &str = "_GLOBAL_OFFSET_TABLE_";
+
+/*
+ * input section name prefixes merged into one output section, as in the
+ * default linker script of GNU ld
+ */
+const OUTPUT_SECTIONS: [&str; 10] = [
+    ".text",
+    ".rodata",
+    ".data.rel.ro",
+    ".data",
+    ".bss",
+    ".init_array",
+    ".fini_array",
+    ".preinit_array",
+    ".gcc_except_table",
+    ".tdata",
+];
+
+fn output_name(name: &str) -> &str {
+    OUTPUT_SECTIONS
+        .iter()
+        .find(|o| name == **o || name.starts_with(&format!("{}.", o)))
+        .copied()
+        .unwrap_or(name)
+}
+
+/*
+ * order of an input section in its output section like SORT_BY_INIT_PRIORITY:
+ * .init_array.NNNNN and .fini_array.NNNNN by priority, before the sections
+ * without one which keep the input order
+ */
+fn init_priority(name: &str) -> u64 {
+    [".init_array.", ".fini_array."]
+        .iter()
+        .find_map(|p| name.strip_prefix(p))
+        .and_then(|n| n.parse::<u32>().ok())
+        .map_or(u64::MAX, u64::from)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputType {
+    Executable, // ET_EXEC at base_address
+    StaticPie,  // ET_DYN at 0 with R_X86_64_RELATIVE relocations in .rela.dyn
+}
+
+#[derive(Debug, Clone)]
+pub struct LinkOptions {
+    pub output_type: OutputType,
+    pub base_address: u64, /* Address of the file header of an executable */
+    pub entry: String,
+}
+
+impl Default for LinkOptions {
+    fn default() -> Self {
+        Self {
+            output_type: OutputType::Executable,
+            base_address: 0x400000,
+            entry: "_start".to_string(),
+        }
+    }
+}
+
+pub struct LinkOutput {
+    pub elf: Elf64,
+    pub warnings: Vec<String>,
+}
+
+/*
+ * static linker for x86-64 relocatable objects. objects are linked in the
+ * order they were added, the first definition of a COMDAT group and of a
+ * weak symbol is kept
+ */
+pub struct Linker<'a> {
+    options: LinkOptions,
+    objects: Vec<(String, &'a Elf64)>,
+}
+
+/*
+ * segments of the output, in address order: headers and notes, code,
+ * read only data, then writable data followed by .bss
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Class {
+    Headers,
+    Text,
+    ReadOnly,
+    Data,
+    Bss,
+}
+
+impl Class {
+    /*
+     * .bss is at the end of the data segment
+     */
+    fn segment(&self) -> Self {
+        match self {
+            Self::Bss => Self::Data,
+            c => *c,
+        }
+    }
+    fn flags(&self) -> BitFlags<PFlags> {
+        match self {
+            Self::Headers | Self::ReadOnly => PFlags::Read.into(),
+            Self::Text => PFlags::Read | PFlags::Execute,
+            Self::Data | Self::Bss => PFlags::Read | PFlags::Write,
+        }
+    }
+}
+
+struct OutputSection {
+    name: String,
+    sh_type: ShType,
+    flags: BitFlags<SHFlags>,
+    align: u64,
+    entsize: u64,
+    data: Vec<u8>,
+    size: u64,
+    addr: u64,
+    offset: u64,
+    class: Class,
+}
+
+impl Placed for OutputSection {
+    fn placement(&self) -> (u64, u64, u64, u64) {
+        (self.offset, self.addr, self.size, self.align)
+    }
+}
+
+impl OutputSection {
+    fn new(name: &str, sh_type: ShType, flags: BitFlags<SHFlags>, align: u64) -> Self {
+        let class = match (sh_type, flags.contains(SHFlags::SHF_WRITE)) {
+            _ if flags.contains(SHFlags::SHF_EXECINSTR) => Class::Text,
+            (ShType::SHT_NOBITS, true) => Class::Bss,
+            (_, true) => Class::Data,
+            (ShType::SHT_NOTE, false) => Class::Headers,
+            _ => Class::ReadOnly,
+        };
+        Self {
+            name: name.to_string(),
+            sh_type,
+            flags,
+            align: align.max(1),
+            entsize: 0,
+            data: Vec::new(),
+            size: 0,
+            addr: 0,
+            offset: 0,
+            class,
+        }
+    }
+    /*
+     * room for an input section, returns its offset in the output section
+     */
+    fn reserve(&mut self, size: u64, align: u64) -> u64 {
+        let offset = align_up(self.size, align);
+        self.align = self.align.max(align);
+        self.size = offset + size;
+        offset
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Definition {
+    Section { object: usize, section: usize },
+    Absolute,
+    Common,
+    Output { output: usize, offset: u64 },
+    Linker(u64), /* Image relative value of a symbol defined by the linker */
+}
+
+struct GlobalSymbol {
+    name: String,
+    binding: SymbolBinding,
+    symbol_type: SymbolType,
+    value: u64, /* st_value of the definition, alignment of a common symbol */
+    size: u64,
+    definition: Option<Definition>,
+    origin: Option<(usize, usize)>, /* Object and symbol index of the definition */
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SymbolKey {
+    Global(usize),
+    Local(usize, usize), /* Object and symbol index */
+}
+
+/*
+ * address of a symbol, relative is false for values that do not move with
+ * the load address of a static pie
+ */
+#[derive(Debug, Clone, Copy)]
+struct Resolved {
+    address: u64,
+    relative: bool,
+}
+
+struct Link<'l, 'a> {
+    linker: &'l Linker<'a>,
+    kept: Vec<Vec<bool>>,
+    placements: HashMap<(usize, usize), (usize, u64)>,
+    outputs: Vec<OutputSection>,
+    globals: Vec<GlobalSymbol>,
+    global_index: HashMap<String, usize>,
+    got: Vec<SymbolKey>,
+    dynamic_relocations: Vec<Elf64_Rela>,
+    diagnostics: Vec<String>,
+    warnings: Vec<String>,
+    context: Option<(usize, String)>,
+}
+
+impl<'a> Linker<'a> {
+    pub fn new(options: LinkOptions) -> Self {
+        Self {
+            options,
+            objects: Vec::new(),
+        }
+    }
+    /*
+     * name is only used in diagnostics, like the path given to ld
+     */
+    pub fn add_object(mut self, name: &str, elf: &'a Elf64) -> Self {
+        self.objects.push((name.to_string(), elf));
+        self
+    }
+    /*
+     * errors are reported in ElfError::Link, one message per entry with the
+     * wording of GNU ld minus its "ld: " prefix
+     */
+    pub fn link(&self) -> Result<LinkOutput, ElfError> {
+        let mut link = Link {
+            linker: self,
+            kept: Vec::new(),
+            placements: HashMap::new(),
+            outputs: Vec::new(),
+            globals: Vec::new(),
+            global_index: HashMap::new(),
+            got: Vec::new(),
+            dynamic_relocations: Vec::new(),
+            diagnostics: Vec::new(),
+            warnings: Vec::new(),
+            context: None,
+        };
+        link.check_objects();
+        link.fail()?;
+        link.select_comdat_groups();
+        link.resolve_symbols();
+        link.place_sections();
+        link.scan_relocations();
+        link.fail()?;
+        link.add_synthetic_sections();
+        let order = link.layout();
+        link.define_linker_symbols();
+        link.apply_relocations();
+        link.fail()?;
+        let elf = link.build(&order)?;
+        Ok(LinkOutput {
+            elf,
+            warnings: link.warnings,
+        })
+    }
+}
+
+impl<'a> Link<'_, 'a> {
+    fn pie(&self) -> bool {
+        self.linker.options.output_type == OutputType::StaticPie
+    }
+    fn object(&self, object: usize) -> &'a Elf64 {
+        self.linker.objects[object].1
+    }
+    fn object_name(&self, object: usize) -> &str {
+        &self.linker.objects[object].0
+    }
+    fn symtab(&self, object: usize) -> Option<usize> {
+        self.object(object)
+            .sections
+            .iter()
+            .position(|s| s.header.sh_type == ShType::SHT_SYMTAB)
+    }
+    fn symbols(&self, object: usize) -> &'a [Elf64_Sym] {
+        let elf = self.object(object);
+        self.symtab(object)
+            .and_then(|i| elf.linked_symbols(i))
+            .map(|s| s.as_slice())
+            .unwrap_or_default()
+    }
+    fn fail(&mut self) -> Result<(), ElfError> {
+        match self.diagnostics.is_empty() {
+            true => Ok(()),
+            false => Err(ElfError::Link(std::mem::take(&mut self.diagnostics))),
+        }
+    }
+    /*
+     * the function is the closest preceding code label like bfd finds it.
+     * ld names it once for consecutive messages about the same function,
+     * the following locations are prefixed by the source file name
+     */
+    fn report(&mut self, object: usize, section: usize, offset: u64, message: &str) {
+        let section_name = &self.object(object).sections[section].name;
+        let function = self
+            .symbols(object)
+            .iter()
+            .filter(|s| {
+                matches!(s.symbol_type, SymbolType::STT_FUNC | SymbolType::STT_NOTYPE)
+                    && !s.name.is_empty()
+                    && s.st_shndx as usize == section
+                    && s.st_value <= offset
+            })
+            .max_by_key(|s| s.st_value)
+            .map(|s| s.name.clone());
+        let location = format!("({}+{:#x}): {}", section_name, offset, message);
+        let diagnostic = match function {
+            Some(function) => {
+                let file = self.source_file(object);
+                let context = Some((object, function.clone()));
+                match self.context == context {
+                    true => format!("{}{}", file, location),
+                    false => {
+                        self.context = context;
+                        format!(
+                            "{}: in function `{}':\n{}{}",
+                            self.object_name(object),
+                            function,
+                            file,
+                            location
+                        )
+                    }
+                }
+            }
+            None => {
+                self.context = None;
+                format!("{}:{}", self.object_name(object), location)
+            }
+        };
+        self.diagnostics.push(diagnostic);
+    }
+    /*
+     * "b.c:" from the STT_FILE symbol, empty when the object has none
+     */
+    fn source_file(&self, object: usize) -> String {
+        self.symbols(object)
+            .iter()
+            .find(|s| s.symbol_type == SymbolType::STT_FILE)
+            .map(|s| format!("{}:", s.name))
+            .unwrap_or_default()
+    }
+    /*
+     * location of a definition in the "first defined here" part
+     */
+    fn definition_site(&self, object: usize, symbol: usize) -> String {
+        let symbol = &self.symbols(object)[symbol];
+        let section = match symbol.section_index() {
+            SectionIndex::Section(s) => self.object(object).sections[s as usize].name.clone(),
+            _ => "*ABS*".to_string(),
+        };
+        let file = match symbol.symbol_type {
+            SymbolType::STT_FUNC | SymbolType::STT_NOTYPE => self.source_file(object),
+            _ => String::new(),
+        };
+        format!(
+            "{}:{}({}+{:#x})",
+            self.object_name(object),
+            file,
+            section,
+            symbol.st_value
+        )
+    }
+    fn check_objects(&mut self) {
+        for (object, (name, elf)) in self.linker.objects.iter().enumerate() {
+            if elf.header.e_type != EType::ET_REL
+                || !matches!(elf.header.e_machine, EMachine::EM_X86_64)
+            {
+                self.diagnostics
+                    .push(format!("{}: file format not recognized", name));
+                continue;
+            }
+            let tls = elf.sections.iter().find(|s| {
+                s.header.sh_flags.contains(SHFlags::SHF_TLS)
+                    && s.header.sh_flags.contains(SHFlags::SHF_ALLOC)
+            });
+            if let Some(section) = tls {
+                self.diagnostics.push(format!(
+                    "{}: TLS section `{}' is not supported",
+                    name, section.name
+                ));
+            }
+            let Some(symtab) = self.symtab(object) else {
+                self.diagnostics.push(format!("{}: no symbols", name));
+                continue;
+            };
+            let symbols = self.symbols(object);
+            for (index, symbol) in symbols.iter().enumerate().skip(1) {
+                if let SectionIndex::Section(s) = symbol.section_index() {
+                    if s as usize >= elf.sections.len() {
+                        self.diagnostics.push(format!(
+                            "{}: symbol {} has a bad section index {}",
+                            name, index, s
+                        ));
+                    }
+                }
+                if symbol.symbol_binding != SymbolBinding::STB_LOCAL && symbol.name.is_empty() {
+                    self.diagnostics
+                        .push(format!("{}: global symbol {} has no name", name, index));
+                }
+            }
+            for section in elf.sections.iter() {
+                let SectionData::Rela(r) = &section.data else {
+                    continue;
+                };
+                if section.header.sh_link as usize != symtab {
+                    continue;
+                }
+                if let Some(rela) = r
+                    .rela_entries
+                    .iter()
+                    .find(|r| r.symbol_index as usize >= symbols.len())
+                {
+                    self.diagnostics.push(format!(
+                        "{}: bad symbol index {} in `{}'",
+                        name, rela.symbol_index, section.name
+                    ));
+                }
+            }
+        }
+    }
+    /*
+     * the first group with a signature is kept, the members of the later
+     * ones are discarded together with the symbols they define
+     */
+    fn select_comdat_groups(&mut self) {
+        let mut signatures: HashMap<String, usize> = HashMap::new();
+        for object in 0..self.linker.objects.len() {
+            let elf = self.object(object);
+            let mut kept = vec![true; elf.sections.len()];
+            for (index, group) in elf.groups() {
+                kept[index] = false;
+                if !group.is_comdat() {
+                    continue;
+                }
+                let signature = match elf.group_signature_name(index) {
+                    Some(s) => s,
+                    None => continue,
+                };
+                match signatures.get(&signature) {
+                    Some(first) if *first != object => {
+                        for member in &group.members {
+                            if let Some(k) = kept.get_mut(*member as usize) {
+                                *k = false;
+                            }
+                        }
+                    }
+                    _ => {
+                        signatures.insert(signature, object);
+                    }
+                }
+            }
+            self.kept.push(kept);
+        }
+    }
+    fn global(&mut self, symbol: &Elf64_Sym) -> usize {
+        if let Some(index) = self.global_index.get(&symbol.name) {
+            return *index;
+        }
+        self.globals.push(GlobalSymbol {
+            name: symbol.name.clone(),
+            binding: symbol.symbol_binding,
+            symbol_type: symbol.symbol_type,
+            value: 0,
+            size: 0,
+            definition: None,
+            origin: None,
+        });
+        self.global_index
+            .insert(symbol.name.clone(), self.globals.len() - 1);
+        self.globals.len() - 1
+    }
+    /*
+     * a global definition replaces weak and common ones, two global
+     * definitions are an error. common symbols are merged to the largest
+     * size and alignment and replace weak definitions
+     */
+    fn resolve_symbols(&mut self) {
+        for object in 0..self.linker.objects.len() {
+            let symbols = self.symbols(object).to_vec();
+            for (index, symbol) in symbols.iter().enumerate() {
+                if symbol.symbol_binding == SymbolBinding::STB_LOCAL || symbol.name.is_empty() {
+                    continue;
+                }
+                let g = self.global(symbol);
+                let definition = match symbol.section_index() {
+                    SectionIndex::SHN_UNDEF | SectionIndex::SHN_XINDEX => {
+                        if self.globals[g].definition.is_none()
+                            && symbol.symbol_binding != SymbolBinding::STB_WEAK
+                        {
+                            self.globals[g].binding = symbol.symbol_binding;
+                        }
+                        continue;
+                    }
+                    SectionIndex::Section(s) if !self.kept[object][s as usize] => continue,
+                    SectionIndex::Section(s) => Definition::Section {
+                        object,
+                        section: s as usize,
+                    },
+                    SectionIndex::SHN_ABS => Definition::Absolute,
+                    SectionIndex::SHN_COMMON => Definition::Common,
+                };
+                let weak = symbol.symbol_binding == SymbolBinding::STB_WEAK;
+                let existing = &self.globals[g];
+                let replace = match (existing.definition, definition) {
+                    (None, _) => true,
+                    (Some(Definition::Common), Definition::Common) => {
+                        let existing = &mut self.globals[g];
+                        existing.size = existing.size.max(symbol.st_size);
+                        existing.value = existing.value.max(symbol.st_value);
+                        false
+                    }
+                    (Some(Definition::Common), _) => !weak,
+                    (Some(_), Definition::Common) => existing.binding == SymbolBinding::STB_WEAK,
+                    (Some(_), _) if weak => false,
+                    (Some(_), _) if existing.binding == SymbolBinding::STB_WEAK => true,
+                    (Some(_), _) => {
+                        let (first_object, first_symbol) = existing.origin.unwrap();
+                        let first = self.definition_site(first_object, first_symbol);
+                        let message = format!(
+                            "multiple definition of `{}'; {}: first defined here",
+                            symbol.name, first
+                        );
+                        match definition {
+                            Definition::Section { section, .. } => {
+                                self.report(object, section, symbol.st_value, &message)
+                            }
+                            _ => self
+                                .diagnostics
+                                .push(format!("{}: {}", self.linker.objects[object].0, message)),
+                        }
+                        false
+                    }
+                };
+                if replace {
+                    let existing = &mut self.globals[g];
+                    existing.definition = Some(definition);
+                    existing.origin = Some((object, index));
+                    existing.binding = match symbol.symbol_binding {
+                        SymbolBinding::STB_GNU_UNIQUE => SymbolBinding::STB_GLOBAL,
+                        b => b,
+                    };
+                    existing.symbol_type = match symbol.symbol_type {
+                        SymbolType::STT_COMMON => SymbolType::STT_OBJECT,
+                        t => t,
+                    };
+                    existing.value = symbol.st_value;
+                    existing.size = symbol.st_size;
+                }
+            }
+        }
+    }
+    /*
+     * every kept SHF_ALLOC section goes to the output section of its name
+     * in input order, but for the init and fini arrays with a priority.
+     * common symbols are allocated at the end of .bss
+     */
+    fn place_sections(&mut self) {
+        let mut by_key: HashMap<(String, u32, u64), usize> = HashMap::new();
+        let merged = SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE | SHFlags::SHF_EXECINSTR;
+        let mut inputs: Vec<(usize, usize)> = Vec::new();
+        for object in 0..self.linker.objects.len() {
+            for (index, section) in self.object(object).sections.iter().enumerate() {
+                let h = &section.header;
+                if self.kept[object][index]
+                    && h.sh_flags.contains(SHFlags::SHF_ALLOC)
+                    && !matches!(
+                        h.sh_type,
+                        ShType::SHT_GROUP | ShType::SHT_RELA | ShType::SHT_REL | ShType::SHT_SYMTAB
+                    )
+                {
+                    inputs.push((object, index));
+                }
+            }
+        }
+        /* output sections are still created in the order of their first input */
+        let mut first: HashMap<&str, usize> = HashMap::new();
+        for (n, (object, index)) in inputs.iter().enumerate() {
+            let name = output_name(&self.object(*object).sections[*index].name);
+            first.entry(name).or_insert(n);
+        }
+        inputs.sort_by_key(|(object, index)| {
+            let name = &self.object(*object).sections[*index].name;
+            (first[output_name(name)], init_priority(name))
+        });
+        for (object, index) in inputs {
+            let section = &self.object(object).sections[index];
+            let h = &section.header;
+            let flags = h.sh_flags & merged;
+            let name = output_name(&section.name).to_string();
+            let key = (name.clone(), h.sh_type.into(), flags.bits());
+            let output = *by_key.entry(key).or_insert_with(|| {
+                self.outputs
+                    .push(OutputSection::new(&name, h.sh_type, flags, h.sh_addralign));
+                self.outputs.len() - 1
+            });
+            let offset = self.outputs[output].reserve(h.sh_size, h.sh_addralign);
+            if h.sh_type != ShType::SHT_NOBITS {
+                let data: Vec<u8> = (&section.data).into();
+                let out = &mut self.outputs[output];
+                write_at(&mut out.data, offset as usize, &data);
+                out.data.resize(out.size as usize, 0);
+            }
+            self.placements.insert((object, index), (output, offset));
+        }
+        let commons: Vec<usize> = (0..self.globals.len())
+            .filter(|g| matches!(self.globals[*g].definition, Some(Definition::Common)))
+            .collect();
+        if commons.is_empty() {
+            return;
+        }
+        let bss = self.output_section(
+            ".bss",
+            ShType::SHT_NOBITS,
+            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+        );
+        for g in commons {
+            let (size, align) = (self.globals[g].size, self.globals[g].value);
+            let offset = self.outputs[bss].reserve(size, align);
+            self.globals[g].definition = Some(Definition::Output {
+                output: bss,
+                offset,
+            });
+            self.globals[g].value = 0;
+        }
+    }
+    fn output_section(&mut self, name: &str, sh_type: ShType, flags: BitFlags<SHFlags>) -> usize {
+        match self
+            .outputs
+            .iter()
+            .position(|o| o.name == name && o.sh_type == sh_type && o.flags == flags)
+        {
+            Some(i) => i,
+            None => {
+                self.outputs
+                    .push(OutputSection::new(name, sh_type, flags, 8));
+                self.outputs.len() - 1
+            }
+        }
+    }
+    fn relocations(&self, object: usize) -> Vec<(usize, Vec<Elf64_Rela>)> {
+        let symtab = self.symtab(object);
+        self.object(object)
+            .sections
+            .iter()
+            .filter(|s| Some(s.header.sh_link as usize) == symtab)
+            .filter(|s| {
+                self.placements
+                    .contains_key(&(object, s.header.sh_info as usize))
+            })
+            .filter_map(|s| match &s.data {
+                SectionData::Rela(r) => Some((s.header.sh_info as usize, r.rela_entries.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+    fn key(&self, object: usize, symbol: usize) -> SymbolKey {
+        let s = &self.symbols(object)[symbol];
+        match s.symbol_binding {
+            SymbolBinding::STB_LOCAL => SymbolKey::Local(object, symbol),
+            _ => SymbolKey::Global(self.global_index[&s.name]),
+        }
+    }
+    /*
+     * values that follow the load address, everything but absolute
+     * symbols and undefined weak references
+     */
+    fn is_relative(&self, key: SymbolKey) -> bool {
+        match key {
+            SymbolKey::Global(g) => !matches!(
+                self.globals[g].definition,
+                None | Some(Definition::Absolute)
+            ),
+            SymbolKey::Local(object, symbol) => {
+                self.symbols(object)[symbol].section_index() != SectionIndex::SHN_ABS
+            }
+        }
+    }
+    /*
+     * undefined references, GOT entries and the number of dynamic
+     * relocations of a static pie
+     */
+    fn scan_relocations(&mut self) {
+        for object in 0..self.linker.objects.len() {
+            for (section, relocations) in self.relocations(object) {
+                for rela in relocations {
+                    let index = rela.symbol_index as usize;
+                    let symbol = match self.symbols(object).get(index) {
+                        Some(s) => s.clone(),
+                        None => {
+                            let message = format!("bad symbol index {}", index);
+                            self.report(object, section, rela.r_offset, &message);
+                            continue;
+                        }
+                    };
+                    if symbol.name == GOT_SYMBOL && !symbol.is_defined() {
+                        self.output_section(
+                            ".got",
+                            ShType::SHT_PROGBITS,
+                            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+                        );
+                    }
+                    let key = self.key(object, index);
+                    if let SymbolKey::Global(g) = key {
+                        if self.globals[g].definition.is_none()
+                            && symbol.symbol_binding != SymbolBinding::STB_WEAK
+                            && !is_linker_symbol(&symbol.name)
+                        {
+                            let message = format!("undefined reference to `{}'", symbol.name);
+                            self.report(object, section, rela.r_offset, &message);
+                            continue;
+                        }
+                    }
+                    let t = rela.relocation_type;
+                    match t {
+                        RelocationType::R_X86_64_GOTPCREL
+                        | RelocationType::R_X86_64_GOTPCRELX
+                        | RelocationType::R_X86_64_REX_GOTPCRELX
+                            if !self.got.contains(&key) =>
+                        {
+                            self.got.push(key);
+                        }
+                        RelocationType::R_X86_64_GOTPC32 | RelocationType::R_X86_64_GOTOFF64 => {
+                            self.output_section(
+                                ".got",
+                                ShType::SHT_PROGBITS,
+                                SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+                            );
+                        }
+                        RelocationType::R_X86_64_32
+                        | RelocationType::R_X86_64_32S
+                        | RelocationType::R_X86_64_16
+                        | RelocationType::R_X86_64_8
+                            if self.pie() && self.is_relative(key) =>
+                        {
+                            let name = match symbol.symbol_type {
+                                SymbolType::STT_SECTION => format!(
+                                    "`{}'",
+                                    self.object(object).sections[symbol.st_shndx as usize].name
+                                ),
+                                _ => format!("symbol `{}'", symbol.name),
+                            };
+                            self.diagnostics.push(format!(
+                                "{}: relocation {:?} against {} can not be used when making a PIE object; recompile with -fPIE",
+                                self.object_name(object),
+                                t,
+                                name
+                            ));
+                        }
+                        RelocationType::R_X86_64_64 if self.pie() && self.is_relative(key) => {
+                            let (output, _) = self.placements[&(object, section)];
+                            if !self.outputs[output].flags.contains(SHFlags::SHF_WRITE) {
+                                let name = self.object(object).sections[section].name.clone();
+                                self.warnings.push(format!(
+                                    "{}: warning: relocation against `{}' in read-only section `{}'",
+                                    self.object_name(object),
+                                    symbol.name,
+                                    name
+                                ));
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+    fn add_synthetic_sections(&mut self) {
+        if !self.got.is_empty() {
+            let got = self.output_section(
+                ".got",
+                ShType::SHT_PROGBITS,
+                SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+            );
+            self.outputs[got].size = 8 * self.got.len() as u64;
+            self.outputs[got].entsize = 8;
+        }
+        if let Some(got) = self.outputs.iter_mut().find(|o| o.name == ".got") {
+            got.data.resize(got.size as usize, 0);
+        }
+        if !self.pie() {
+            return;
+        }
+        /*
+         * one RELATIVE relocation per R_X86_64_64 and GOT entry of an
+         * address in the image, the contents are written with the
+         * relocations
+         */
+        let mut count = self.got.iter().filter(|k| self.is_relative(**k)).count();
+        for object in 0..self.linker.objects.len() {
+            for (_, relocations) in self.relocations(object) {
+                count += relocations
+                    .iter()
+                    .filter(|r| r.relocation_type == RelocationType::R_X86_64_64)
+                    .filter(|r| self.is_relative(self.key(object, r.symbol_index as usize)))
+                    .count();
+            }
+        }
+        let mut rela =
+            OutputSection::new(".rela.dyn", ShType::SHT_RELA, SHFlags::SHF_ALLOC.into(), 8);
+        rela.size = 24 * count as u64;
+        rela.entsize = 24;
+        self.outputs.push(rela);
+        /* empty, only there to be the sh_link of .dynamic */
+        let mut dynstr =
+            OutputSection::new(".dynstr", ShType::SHT_STRTAB, SHFlags::SHF_ALLOC.into(), 1);
+        dynstr.data = StringTableBuilder::new().data().to_vec();
+        dynstr.size = 1;
+        self.outputs.push(dynstr);
+        let mut dynamic = OutputSection::new(
+            ".dynamic",
+            ShType::SHT_DYNAMIC,
+            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+            8,
+        );
+        dynamic.size = 16 * self.dynamic_entries(0, 0).len() as u64;
+        dynamic.entsize = 16;
+        self.outputs.push(dynamic);
+    }
+    fn dynamic_entries(&self, rela: u64, textrel: u64) -> Vec<Elf64_Dyn> {
+        let mut entries = vec![
+            (DynamicTag::DT_RELA, rela),
+            (
+                DynamicTag::DT_RELASZ,
+                24 * self.dynamic_relocations.len() as u64,
+            ),
+            (DynamicTag::DT_RELAENT, 24),
+            (DynamicTag::DT_Flags1, DF_1_PIE),
+        ];
+        if textrel != 0 {
+            entries.push((DynamicTag::DT_TEXTREL, 0));
+        }
+        entries.push((DynamicTag::DT_NULL, 0));
+        entries
+            .into_iter()
+            .map(|(d_tag, val_ptr)| Elf64_Dyn { d_tag, val_ptr })
+            .collect()
+    }
+    /*
+     * every segment starts on a new page both in the file and in memory so
+     * file offsets are the addresses minus the base
+     */
+    fn layout(&mut self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.outputs.len()).collect();
+        order.sort_by_key(|i| self.outputs[*i].class);
+        let base = match self.pie() {
+            true => 0,
+            false => self.linker.options.base_address,
+        };
+        let phnum = self.program_count(&order) as u64;
+        let mut offset = 64 + 56 * phnum;
+        let mut class = Class::Headers;
+        for i in &order {
+            let output = &mut self.outputs[*i];
+            if output.class.segment() != class.segment() {
+                offset = align_up(offset, PAGE_SIZE);
+            }
+            class = output.class;
+            offset = align_up(offset, output.align);
+            output.offset = offset;
+            output.addr = base + offset;
+            offset += output.size;
+        }
+        order
+    }
+    fn program_count(&self, order: &[usize]) -> usize {
+        let mut classes: Vec<Class> = vec![Class::Headers];
+        for i in order {
+            let class = self.outputs[*i].class.segment();
+            if !classes.contains(&class) {
+                classes.push(class);
+            }
+        }
+        let notes = self.outputs.iter().any(|o| o.sh_type == ShType::SHT_NOTE);
+        classes.len() + 1 + notes as usize + self.pie() as usize
+    }
+    fn define_linker_symbols(&mut self) {
+        let base = match self.pie() {
+            true => 0,
+            false => self.linker.options.base_address,
+        };
+        let end_of = |classes: &[Class], outputs: &[OutputSection]| {
+            outputs
+                .iter()
+                .filter(|o| classes.contains(&o.class))
+                .map(|o| o.addr + o.size)
+                .max()
+        };
+        let text_end = end_of(&[Class::Text], &self.outputs).unwrap_or(base);
+        let data_end = self
+            .outputs
+            .iter()
+            .filter(|o| o.sh_type != ShType::SHT_NOBITS)
+            .map(|o| o.addr + o.size)
+            .max()
+            .unwrap_or(base);
+        let end = end_of(&[Class::Data, Class::Bss], &self.outputs)
+            .unwrap_or(data_end)
+            .max(data_end);
+        let bss_start = self
+            .outputs
+            .iter()
+            .find(|o| o.class == Class::Bss)
+            .map(|o| o.addr)
+            .unwrap_or(data_end);
+        let section = |name: &str, outputs: &[OutputSection]| {
+            outputs
+                .iter()
+                .find(|o| o.name == name)
+                .map(|o| (o.addr, o.addr + o.size))
+                .unwrap_or((data_end, data_end))
+        };
+        let (init_start, init_end) = section(".init_array", &self.outputs);
+        let (fini_start, fini_end) = section(".fini_array", &self.outputs);
+        let (preinit_start, preinit_end) = section(".preinit_array", &self.outputs);
+        let (got, _) = section(".got", &self.outputs);
+        let (dynamic, _) = section(".dynamic", &self.outputs);
+        let values = [
+            ("__ehdr_start", base),
+            ("__executable_start", base),
+            ("etext", text_end),
+            ("_etext", text_end),
+            ("__etext", text_end),
+            ("edata", data_end),
+            ("_edata", data_end),
+            ("__bss_start", bss_start),
+            ("end", end),
+            ("_end", end),
+            ("__init_array_start", init_start),
+            ("__init_array_end", init_end),
+            ("__fini_array_start", fini_start),
+            ("__fini_array_end", fini_end),
+            ("__preinit_array_start", preinit_start),
+            ("__preinit_array_end", preinit_end),
+            ("__rela_iplt_start", base),
+            ("__rela_iplt_end", base),
+            (GOT_SYMBOL, got),
+            ("_DYNAMIC", dynamic),
+        ];
+        for (name, value) in values {
+            if let Some(g) = self.global_index.get(name) {
+                let symbol = &mut self.globals[*g];
+                if symbol.definition.is_none() {
+                    symbol.definition = Some(Definition::Linker(value));
+                    symbol.binding = SymbolBinding::STB_GLOBAL;
+                }
+            }
+        }
+    }
+    fn resolve(&self, key: SymbolKey) -> Resolved {
+        let relative = self.is_relative(key);
+        let address = match key {
+            SymbolKey::Global(g) => {
+                let symbol = &self.globals[g];
+                match symbol.definition {
+                    None => 0,
+                    Some(Definition::Absolute) => symbol.value,
+                    Some(Definition::Linker(v)) => v,
+                    Some(Definition::Output { output, offset }) => {
+                        self.outputs[output].addr + offset
+                    }
+                    Some(Definition::Section { object, section }) => {
+                        self.section_address(object, section) + symbol.value
+                    }
+                    Some(Definition::Common) => 0,
+                }
+            }
+            SymbolKey::Local(object, symbol) => {
+                let s = &self.symbols(object)[symbol];
+                match s.section_index() {
+                    SectionIndex::Section(section) => {
+                        self.section_address(object, section as usize) + s.st_value
+                    }
+                    SectionIndex::SHN_ABS => s.st_value,
+                    _ => 0,
+                }
+            }
+        };
+        Resolved { address, relative }
+    }
+    /*
+     * discarded sections are at 0, references to them only remain in
+     * .eh_frame and debug sections
+     */
+    fn section_address(&self, object: usize, section: usize) -> u64 {
+        match self.placements.get(&(object, section)) {
+            Some((output, offset)) => self.outputs[*output].addr + offset,
+            None => 0,
+        }
+    }
+    fn got_address(&self) -> u64 {
+        self.outputs
+            .iter()
+            .find(|o| o.name == ".got")
+            .map(|o| o.addr)
+            .unwrap_or(0)
+    }
+    fn apply_relocations(&mut self) {
+        let got = self.got_address();
+        for (slot, key) in self.got.clone().iter().enumerate() {
+            let resolved = self.resolve(*key);
+            let place = got + 8 * slot as u64;
+            self.write(".got", place, &resolved.address.to_le_bytes());
+            if self.pie() && resolved.relative {
+                self.dynamic_relocations.push(Elf64_Rela::new(
+                    place,
+                    RelocationType::R_X86_64_RELATIVE,
+                    0,
+                    resolved.address,
+                ));
+            }
+        }
+        let mut textrel = false;
+        for object in 0..self.linker.objects.len() {
+            for (section, relocations) in self.relocations(object) {
+                let (output, offset) = self.placements[&(object, section)];
+                if self.outputs[output].sh_type == ShType::SHT_NOBITS {
+                    continue;
+                }
+                for rela in relocations {
+                    let key = self.key(object, rela.symbol_index as usize);
+                    let resolved = self.resolve(key);
+                    let place = self.outputs[output].addr + offset + rela.r_offset;
+                    let (s, a) = (resolved.address, rela.r_addend);
+                    let t = rela.relocation_type;
+                    let value = match t {
+                        RelocationType::R_X86_64_NONE => continue,
+                        RelocationType::R_X86_64_64 => {
+                            if self.pie() && resolved.relative {
+                                self.dynamic_relocations.push(Elf64_Rela::new(
+                                    place,
+                                    RelocationType::R_X86_64_RELATIVE,
+                                    0,
+                                    s.wrapping_add(a),
+                                ));
+                                textrel |= !self.outputs[output].flags.contains(SHFlags::SHF_WRITE);
+                            }
+                            s.wrapping_add(a)
+                        }
+                        RelocationType::R_X86_64_PLT32 => s.wrapping_add(a).wrapping_sub(place),
+                        RelocationType::R_X86_64_GOTPCREL
+                        | RelocationType::R_X86_64_GOTPCRELX
+                        | RelocationType::R_X86_64_REX_GOTPCRELX => {
+                            let slot = self.got.iter().position(|k| *k == key).unwrap();
+                            (got + 8 * slot as u64).wrapping_add(a).wrapping_sub(place)
+                        }
+                        RelocationType::R_X86_64_GOTPC32 => got.wrapping_add(a).wrapping_sub(place),
+                        RelocationType::R_X86_64_GOTOFF64 => s.wrapping_add(a).wrapping_sub(got),
+                        RelocationType::R_X86_64_SIZE32 | RelocationType::R_X86_64_SIZE64 => {
+                            self.symbol_size(key).wrapping_add(a)
+                        }
+                        t if t.is_tls() => {
+                            self.report(
+                                object,
+                                section,
+                                rela.r_offset,
+                                &format!("unsupported relocation {:?}", t),
+                            );
+                            continue;
+                        }
+                        t => match t.compute(s, a, place, 0) {
+                            Some(v) => v,
+                            None => {
+                                self.report(
+                                    object,
+                                    section,
+                                    rela.r_offset,
+                                    &format!("unsupported relocation {:?}", t),
+                                );
+                                continue;
+                            }
+                        },
+                    };
+                    if !t.fits(value) {
+                        let target = self.describe(object, rela.symbol_index as usize, key);
+                        let message =
+                            format!("relocation truncated to fit: {:?} against {}", t, target);
+                        self.report(object, section, rela.r_offset, &message);
+                        continue;
+                    }
+                    let size = t.field_size().unwrap_or(8);
+                    let name = self.outputs[output].name.clone();
+                    self.write(&name, place, &value.to_le_bytes()[..size]);
+                }
+            }
+        }
+        if textrel {
+            self.warnings
+                .push("warning: creating DT_TEXTREL in a PIE".to_string());
+        }
+        if self.pie() {
+            let rela = self
+                .outputs
+                .iter()
+                .position(|o| o.name == ".rela.dyn")
+                .unwrap();
+            self.outputs[rela].data = self
+                .dynamic_relocations
+                .iter()
+                .flat_map::<Vec<u8>, _>(|r| r.into())
+                .collect();
+            let entries = self.dynamic_entries(self.outputs[rela].addr, textrel as u64);
+            let dynamic = self
+                .outputs
+                .iter()
+                .position(|o| o.name == ".dynamic")
+                .unwrap();
+            self.outputs[dynamic].size = 16 * entries.len() as u64;
+            self.outputs[dynamic].data = (&DynamicSection { entries }).into();
+        }
+    }
+    fn symbol_size(&self, key: SymbolKey) -> u64 {
+        match key {
+            SymbolKey::Global(g) => self.globals[g].size,
+            SymbolKey::Local(object, symbol) => self.symbols(object)[symbol].st_size,
+        }
+    }
+    /*
+     * "symbol `x' defined in .bss section in t.o" or "`.rodata'" for
+     * section symbols, as in the overflow messages of ld
+     */
+    fn describe(&self, object: usize, symbol: usize, key: SymbolKey) -> String {
+        let s = &self.symbols(object)[symbol];
+        if s.symbol_type == SymbolType::STT_SECTION {
+            return format!(
+                "`{}'",
+                self.object(object).sections[s.st_shndx as usize].name
+            );
+        }
+        let origin = match key {
+            SymbolKey::Global(g) => match self.globals[g].definition {
+                None => return format!("undefined symbol `{}'", s.name),
+                Some(Definition::Common) | Some(Definition::Output { .. }) => {
+                    return format!("symbol `{}' defined in COMMON section", s.name)
+                }
+                Some(Definition::Linker(_)) => {
+                    return format!("symbol `{}'", s.name);
+                }
+                _ => self.globals[g].origin.unwrap(),
+            },
+            SymbolKey::Local(o, i) => (o, i),
+        };
+        let definition = &self.symbols(origin.0)[origin.1];
+        let section = match definition.section_index() {
+            SectionIndex::Section(i) => self.object(origin.0).sections[i as usize].name.clone(),
+            _ => "*ABS*".to_string(),
+        };
+        format!(
+            "symbol `{}' defined in {} section in {}",
+            s.name,
+            section,
+            self.object_name(origin.0)
+        )
+    }
+    fn write(&mut self, output: &str, address: u64, data: &[u8]) {
+        if let Some(o) = self.outputs.iter_mut().find(|o| {
+            o.name == output && o.addr <= address && address + data.len() as u64 <= o.addr + o.size
+        }) {
+            let start = (address - o.addr) as usize;
+            write_at(&mut o.data, start, data);
+        }
+    }
+    fn output_symbols(&self, section_index: &HashMap<usize, u16>) -> (Vec<Elf64_Sym>, u32) {
+        let mut symbols = vec![Elf64_Sym::new(
+            "",
+            SymbolBinding::STB_LOCAL,
+            SymbolType::STT_NOTYPE,
+            0,
+            0,
+            0,
+        )];
+        let shndx = |output: usize| section_index[&output];
+        for object in 0..self.linker.objects.len() {
+            for (index, s) in self.symbols(object).iter().enumerate() {
+                if s.symbol_binding != SymbolBinding::STB_LOCAL
+                    || s.name.is_empty()
+                    || s.symbol_type == SymbolType::STT_SECTION
+                {
+                    continue;
+                }
+                let st_shndx = match s.section_index() {
+                    SectionIndex::Section(i) => match self.placements.get(&(object, i as usize)) {
+                        Some((output, _)) => shndx(*output),
+                        None => continue,
+                    },
+                    SectionIndex::SHN_ABS => SectionIndex::SHN_ABS.into(),
+                    _ => continue,
+                };
+                let value = self.resolve(SymbolKey::Local(object, index)).address;
+                symbols.push(Elf64_Sym::new(
+                    &s.name,
+                    s.symbol_binding,
+                    s.symbol_type,
+                    st_shndx,
+                    value,
+                    s.st_size,
+                ));
+            }
+        }
+        let first_global = symbols.len() as u32;
+        for (g, symbol) in self.globals.iter().enumerate() {
+            let st_shndx = match symbol.definition {
+                None => {
+                    if symbol.binding != SymbolBinding::STB_WEAK {
+                        continue;
+                    }
+                    0
+                }
+                Some(Definition::Section { object, section }) => {
+                    shndx(self.placements[&(object, section)].0)
+                }
+                Some(Definition::Output { output, .. }) => shndx(output),
+                Some(_) => SectionIndex::SHN_ABS.into(),
+            };
+            symbols.push(Elf64_Sym::new(
+                &symbol.name,
+                symbol.binding,
+                symbol.symbol_type,
+                st_shndx,
+                self.resolve(SymbolKey::Global(g)).address,
+                symbol.size,
+            ));
+        }
+        (symbols, first_global)
+    }
+    fn build(&mut self, order: &[usize]) -> Result<Elf64, ElfError> {
+        let base = match self.pie() {
+            true => 0,
+            false => self.linker.options.base_address,
+        };
+        let entry = self
+            .global_index
+            .get(&self.linker.options.entry)
+            .filter(|g| self.globals[**g].definition.is_some())
+            .map(|g| self.resolve(SymbolKey::Global(*g)).address);
+        let entry = match entry {
+            Some(e) => e,
+            None => {
+                let default = order
+                    .iter()
+                    .map(|i| &self.outputs[*i])
+                    .find(|o| o.class == Class::Text)
+                    .map(|o| o.addr)
+                    .unwrap_or(base);
+                self.warnings.push(format!(
+                    "warning: cannot find entry symbol {}; defaulting to {:016x}",
+                    self.linker.options.entry, default
+                ));
+                default
+            }
+        };
+        /*
+         * segments
+         */
+        let mut programs: Vec<Elf64_Phdr> = Vec::new();
+        let phnum = self.program_count(order) as u64;
+        let headers_end = 64 + 56 * phnum;
+        let mut load = Elf64_Phdr {
+            p_type: PType::PT_LOAD,
+            p_flags: Class::Headers.flags(),
+            p_offset: 0,
+            p_vaddr: base,
+            p_paddr: base,
+            p_filesz: headers_end,
+            p_memsz: headers_end,
+            p_align: PAGE_SIZE,
+        };
+        let mut class = Class::Headers;
+        for i in order {
+            let o = &self.outputs[*i];
+            if o.class.segment() != class.segment() {
+                programs.push(load.clone());
+                load = Elf64_Phdr {
+                    p_type: PType::PT_LOAD,
+                    p_flags: o.class.flags(),
+                    p_offset: o.offset,
+                    p_vaddr: o.addr,
+                    p_paddr: o.addr,
+                    p_filesz: 0,
+                    p_memsz: 0,
+                    p_align: PAGE_SIZE,
+                };
+            }
+            class = o.class;
+            if o.sh_type != ShType::SHT_NOBITS {
+                load.p_filesz = o.offset + o.size - load.p_offset;
+            }
+            load.p_memsz = o.addr + o.size - load.p_vaddr;
+        }
+        programs.push(load);
+        if let Some(dynamic) = self
+            .outputs
+            .iter()
+            .find(|o| o.sh_type == ShType::SHT_DYNAMIC)
+        {
+            programs.push(section_program(
+                PType::PT_DYNAMIC,
+                dynamic,
+                PFlags::Read | PFlags::Write,
+            ));
+        }
+        let notes: Vec<&OutputSection> = order
+            .iter()
+            .map(|i| &self.outputs[*i])
+            .filter(|o| o.sh_type == ShType::SHT_NOTE)
+            .collect();
+        if let (Some(first), Some(last)) = (notes.first(), notes.last()) {
+            let mut note = section_program(PType::PT_NOTE, *first, PFlags::Read.into());
+            note.p_filesz = last.offset + last.size - first.offset;
+            note.p_memsz = note.p_filesz;
+            programs.push(note);
+        }
+        programs.push(Elf64_Phdr {
+            p_type: PType::PT_GNU_STACK,
+            p_flags: PFlags::Read | PFlags::Write,
+            p_offset: 0,
+            p_vaddr: 0,
+            p_paddr: 0,
+            p_filesz: 0,
+            p_memsz: 0,
+            p_align: 16,
+        });
+        /*
+         * sections, the non allocated ones follow the last segment
+         */
+        let mut section_index: HashMap<usize, u16> = HashMap::new();
+        for (n, i) in order.iter().enumerate() {
+            section_index.insert(*i, n as u16 + 1);
+        }
+        let (symbols, first_global) = self.output_symbols(&section_index);
+        let mut strtab = StringTableBuilder::new();
+        symbols.iter().for_each(|s| strtab.add(&s.name));
+        strtab.finalize();
+        let symbols: Vec<Elf64_Sym> = symbols
+            .into_iter()
+            .map(|mut s| {
+                s.st_name = strtab.offset(&s.name)?;
+                Ok(s)
+            })
+            .collect::<Result<_, ElfError>>()?;
+        let mut shstrtab = StringTableBuilder::new();
+        for name in order.iter().map(|i| self.outputs[*i].name.as_str()) {
+            shstrtab.add(name);
+        }
+        for name in [".symtab", ".strtab", ".shstrtab"] {
+            shstrtab.add(name);
+        }
+        shstrtab.finalize();
+        let mut headers: Vec<(Elf64Shdr, String, SectionData)> = Vec::new();
+        headers.push((
+            null_header(),
+            String::new(),
+            SectionData::Unknown(Vec::new().into()),
+        ));
+        let dynstr = order
+            .iter()
+            .position(|i| self.outputs[*i].name == ".dynstr")
+            .map(|n| n as u32 + 1)
+            .unwrap_or(0);
+        for i in order {
+            let o = &self.outputs[*i];
+            let header = Elf64Shdr {
+                sh_name: shstrtab.offset(&o.name)?,
+                sh_type: o.sh_type,
+                sh_flags: o.flags,
+                sh_addr: o.addr,
+                sh_offset: o.offset,
+                sh_size: o.size,
+                sh_link: match o.sh_type {
+                    ShType::SHT_DYNAMIC => dynstr,
+                    _ => 0,
+                },
+                sh_info: 0,
+                sh_addralign: o.align,
+                sh_entsize: o.entsize,
+            };
+            let data = match o.sh_type {
+                ShType::SHT_RELA => SectionData::Rela(o.data.as_slice().into()),
+                ShType::SHT_DYNAMIC => SectionData::Dynamic(o.data.as_slice().into()),
+                ShType::SHT_STRTAB => SectionData::String(o.data.as_slice().into()),
+                ShType::SHT_NOBITS => SectionData::Unknown(Vec::new().into()),
+                _ => SectionData::Unknown(o.data.as_slice().into()),
+            };
+            headers.push((header, o.name.clone(), data));
+        }
+        let mut offset = order
+            .iter()
+            .map(|i| &self.outputs[*i])
+            .filter(|o| o.sh_type != ShType::SHT_NOBITS)
+            .map(|o| o.offset + o.size)
+            .max()
+            .unwrap_or(headers_end)
+            .max(headers_end);
+        offset = align_up(offset, 8);
+        let symtab = Elf64Shdr {
+            sh_name: shstrtab.offset(".symtab")?,
+            sh_type: ShType::SHT_SYMTAB,
+            sh_offset: offset,
+            sh_size: 24 * symbols.len() as u64,
+            sh_link: headers.len() as u32 + 1,
+            sh_info: first_global,
+            sh_addralign: 8,
+            sh_entsize: 24,
+            ..null_header()
+        };
+        offset += symtab.sh_size;
+        headers.push((
+            symtab,
+            ".symtab".to_string(),
+            SectionData::Symbol(SymbolSection { symbols }),
+        ));
+        let strtab_header = Elf64Shdr {
+            sh_name: shstrtab.offset(".strtab")?,
+            sh_type: ShType::SHT_STRTAB,
+            sh_offset: offset,
+            sh_size: strtab.len() as u64,
+            sh_addralign: 1,
+            ..null_header()
+        };
+        offset += strtab_header.sh_size;
+        headers.push((
+            strtab_header,
+            ".strtab".to_string(),
+            SectionData::String(StringSection::from(strtab.data())),
+        ));
+        let sh_name = shstrtab.offset(".shstrtab")?;
+        let shstrtab_header = Elf64Shdr {
+            sh_name,
+            sh_type: ShType::SHT_STRTAB,
+            sh_offset: offset,
+            sh_size: shstrtab.len() as u64,
+            sh_addralign: 1,
+            ..null_header()
+        };
+        offset += shstrtab_header.sh_size;
+        headers.push((
+            shstrtab_header,
+            ".shstrtab".to_string(),
+            SectionData::String(StringSection::from(shstrtab.data())),
+        ));
+        let mut header = Elf64Ehdr::new(match self.pie() {
+            true => EType::ET_DYN,
+            false => EType::ET_EXEC,
+        });
+        header.e_entry = entry;
+        header.e_phoff = 64;
+        header.e_phnum = programs.len() as u16;
+        header.e_shoff = align_up(offset, 8);
+        header.e_shnum = headers.len() as u16;
+        header.e_shstrndx = headers.len() as u16 - 1;
+        /*
+         * segment contents are read back from the file image
+         */
+        let mut file: Vec<u8> = (&header).into();
+        for (n, program) in programs.iter().enumerate() {
+            write_at(&mut file, 64 + 56 * n, &Into::<Vec<u8>>::into(program));
+        }
+        for i in order {
+            let o = &self.outputs[*i];
+            if o.sh_type != ShType::SHT_NOBITS {
+                write_at(&mut file, o.offset as usize, &o.data);
+            }
+        }
+        let programs = programs
+            .into_iter()
+            .map(|p| Program {
+                data: file
+                    .get(p.p_offset as usize..(p.p_offset + p.p_filesz) as usize)
+                    .unwrap_or_default()
+                    .into(),
+                header: p,
+            })
+            .collect();
+        let sections = headers
+            .into_iter()
+            .map(|(header, name, data)| Section { header, name, data })
+            .collect();
+        Ok(Elf64 {
+            header,
+            sections,
+            programs,
+        })
+    }
+}
+
+/*
+ * symbols ld defines when they are referenced and not defined
+ */
+fn is_linker_symbol(name: &str) -> bool {
+    matches!(
+        name,
+        "__ehdr_start"
+            | "__executable_start"
+            | "etext"
+            | "_etext"
+            | "__etext"
+            | "edata"
+            | "_edata"
+            | "__bss_start"
+            | "end"
+            | "_end"
+            | "__init_array_start"
+            | "__init_array_end"
+            | "__fini_array_start"
+            | "__fini_array_end"
+            | "__preinit_array_start"
+            | "__preinit_array_end"
+            | "__rela_iplt_start"
+            | "__rela_iplt_end"
+            | "_DYNAMIC"
+    ) || name == GOT_SYMBOL
+}
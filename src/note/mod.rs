@@ -0,0 +1,2 @@
+pub mod n_type;
+pub mod note;
@@ -0,0 +1,90 @@
+#![allow(non_camel_case_types)]
+
+use serde::Serialize;
+
+/*
+ * note types are only meaningful together with the owner name of the note,
+ * the same value means different things for "CORE" and "GNU" notes
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[repr(u32)]
+pub enum CoreNoteType {
+    NT_PRSTATUS = 1,         // Thread status and registers (Elf64_Prstatus)
+    NT_PRFPREG = 2,          // Floating point registers (fxsave area)
+    NT_PRPSINFO = 3,         // Process information (Elf64_Prpsinfo)
+    NT_TASKSTRUCT = 4,       // Copy of the task struct
+    NT_AUXV = 6,             // Auxiliary vector
+    NT_X86_XSTATE = 0x202,   // Extended x86 state, owner name is "LINUX"
+    NT_SIGINFO = 0x53494749, // siginfo_t of the signal that killed the process
+    NT_FILE = 0x46494c45,    // Mapped files
+    UNSPECIFIED(u32),
+}
+
+impl From<u32> for CoreNoteType {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::NT_PRSTATUS,
+            2 => Self::NT_PRFPREG,
+            3 => Self::NT_PRPSINFO,
+            4 => Self::NT_TASKSTRUCT,
+            6 => Self::NT_AUXV,
+            0x202 => Self::NT_X86_XSTATE,
+            0x53494749 => Self::NT_SIGINFO,
+            0x46494c45 => Self::NT_FILE,
+            _ => Self::UNSPECIFIED(value),
+        }
+    }
+}
+
+impl Into<u32> for CoreNoteType {
+    fn into(self) -> u32 {
+        match self {
+            Self::NT_PRSTATUS => 1,
+            Self::NT_PRFPREG => 2,
+            Self::NT_PRPSINFO => 3,
+            Self::NT_TASKSTRUCT => 4,
+            Self::NT_AUXV => 6,
+            Self::NT_X86_XSTATE => 0x202,
+            Self::NT_SIGINFO => 0x53494749,
+            Self::NT_FILE => 0x46494c45,
+            Self::UNSPECIFIED(v) => v,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[repr(u32)]
+pub enum GnuNoteType {
+    NT_GNU_ABI_TAG = 1,         // Minimum kernel version
+    NT_GNU_HWCAP = 2,           // Hardware capabilities
+    NT_GNU_BUILD_ID = 3,        // Unique build id of the file
+    NT_GNU_GOLD_VERSION = 4,    // Version of gold that linked the file
+    NT_GNU_PROPERTY_TYPE_0 = 5, // Program properties (ibt, shstk, isa level ...)
+    UNSPECIFIED(u32),
+}
+
+impl From<u32> for GnuNoteType {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::NT_GNU_ABI_TAG,
+            2 => Self::NT_GNU_HWCAP,
+            3 => Self::NT_GNU_BUILD_ID,
+            4 => Self::NT_GNU_GOLD_VERSION,
+            5 => Self::NT_GNU_PROPERTY_TYPE_0,
+            _ => Self::UNSPECIFIED(value),
+        }
+    }
+}
+
+impl Into<u32> for GnuNoteType {
+    fn into(self) -> u32 {
+        match self {
+            Self::NT_GNU_ABI_TAG => 1,
+            Self::NT_GNU_HWCAP => 2,
+            Self::NT_GNU_BUILD_ID => 3,
+            Self::NT_GNU_GOLD_VERSION => 4,
+            Self::NT_GNU_PROPERTY_TYPE_0 => 5,
+            Self::UNSPECIFIED(v) => v,
+        }
+    }
+}
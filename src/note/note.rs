@@ -0,0 +1,144 @@
+#![allow(non_camel_case_types)]
+
+use super::super::common::RawBinaryData;
+use nom::bytes::complete::take;
+use nom::number::complete::le_u32;
+use nom::sequence;
+use nom::IResult;
+use serde::Serialize;
+use std::ffi::CStr;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Elf64_Nhdr {
+    pub n_namesz: u32, /* Length of the note's name (including the null byte) */
+    pub n_descsz: u32, /* Length of the note's descriptor */
+    pub n_type: u32,   /* Type of the note, meaning depends on the name */
+}
+
+impl Elf64_Nhdr {
+    pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
+        let (r, (n_namesz, n_descsz, n_type)) = sequence::tuple((le_u32, le_u32, le_u32))(raw)?;
+        Ok((
+            r,
+            Self {
+                n_namesz,
+                n_descsz,
+                n_type,
+            },
+        ))
+    }
+}
+
+impl Into<Vec<u8>> for &Elf64_Nhdr {
+    fn into(self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        out.extend(self.n_namesz.to_le_bytes());
+        out.extend(self.n_descsz.to_le_bytes());
+        out.extend(self.n_type.to_le_bytes());
+        out
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Note {
+    pub header: Elf64_Nhdr,
+    pub name: String,
+    pub desc: RawBinaryData,
+}
+
+fn padding(len: usize, align: usize) -> usize {
+    (align - len % align) % align
+}
+
+impl Note {
+    pub fn new(name: &str, n_type: u32, desc: Vec<u8>) -> Self {
+        Self {
+            header: Elf64_Nhdr {
+                n_namesz: name.len() as u32 + 1,
+                n_descsz: desc.len() as u32,
+                n_type,
+            },
+            name: name.to_string(),
+            desc: desc.into(),
+        }
+    }
+    /*
+     * name and descriptor are padded to 4 bytes in almost every note,
+     * gnu property notes (and their segments) use 8 byte alignment. the
+     * descriptor is aligned from the start of the note, after the 12 byte
+     * header
+     */
+    pub fn parse(raw: &[u8], align: usize) -> IResult<&[u8], Self> {
+        let (remaining, header) = Elf64_Nhdr::parse(raw)?;
+        let (remaining, name) = take(header.n_namesz as usize)(remaining)?;
+        let (remaining, _) = take(padding(12 + header.n_namesz as usize, align))(remaining)?;
+        let (remaining, desc) = take(header.n_descsz as usize)(remaining)?;
+        let pad = padding(header.n_descsz as usize, align).min(remaining.len());
+        let (remaining, _) = take(pad)(remaining)?;
+        let name = match CStr::from_bytes_until_nul(name) {
+            Ok(s) => s.to_string_lossy().into_owned(),
+            Err(_) => String::from_utf8_lossy(name).into_owned(),
+        };
+        Ok((
+            remaining,
+            Self {
+                header,
+                name,
+                desc: desc.into(),
+            },
+        ))
+    }
+    pub fn to_bytes(&self, align: usize) -> Vec<u8> {
+        let mut out: Vec<u8> = (&self.header).into();
+        let mut name = Vec::from(self.name.as_bytes());
+        name.resize(self.header.n_namesz as usize, 0);
+        out.extend(&name);
+        out.resize(out.len() + padding(out.len(), align), 0);
+        out.extend(&self.desc.inner);
+        out.resize(out.len() + padding(self.desc.len(), align), 0);
+        out
+    }
+}
+
+impl Into<Vec<u8>> for &Note {
+    fn into(self) -> Vec<u8> {
+        self.to_bytes(4)
+    }
+}
+
+#[derive(Default, Debug, Serialize)]
+pub struct NoteSection {
+    pub align: usize,
+    pub notes: Vec<Note>,
+}
+
+impl NoteSection {
+    pub fn parse(raw: &[u8], align: usize) -> Self {
+        let align = if align == 8 { 8 } else { 4 };
+        let mut notes: Vec<Note> = Vec::new();
+        let mut remaining = raw;
+        while !remaining.is_empty() {
+            match Note::parse(remaining, align) {
+                Ok((r, note)) => {
+                    notes.push(note);
+                    remaining = r;
+                }
+                Err(_) => break,
+            }
+        }
+        Self { align, notes }
+    }
+}
+
+impl Into<Vec<u8>> for &NoteSection {
+    fn into(self) -> Vec<u8> {
+        let align = if self.align == 8 { 8 } else { 4 };
+        self.notes.iter().flat_map(|n| n.to_bytes(align)).collect()
+    }
+}
+
+impl Into<Vec<u8>> for NoteSection {
+    fn into(self) -> Vec<u8> {
+        (&self).into()
+    }
+}
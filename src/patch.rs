@@ -0,0 +1,711 @@
+use super::common::{write_at, PAGE_SIZE};
+use super::elf64::Elf64;
+use super::error::ElfError;
+use super::header::header::Elf64Ehdr;
+use super::program::p_type::PType;
+use super::program::program_header::{Elf64_Phdr, PFlags};
+use super::section::elf64_dyn::{DynamicSection, DynamicTag, Elf64_Dyn};
+use super::section::section_header::Elf64Shdr;
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+use super::section::string_table::StringTableBuilder;
+use super::writer::WriteOptions;
+use enumflags2::BitFlags;
+use std::ffi::CStr;
+
+/*
+ * segment added at the end of the file for the data that does not fit at
+ * its old place, it starts with the program header table that has room
+ * for one more entry: the PT_LOAD of the segment
+ */
+struct Extension {
+    offset: u64,
+    vaddr: u64,
+    data: Vec<u8>,
+    flags: BitFlags<PFlags>,
+}
+
+/*
+ * the file being edited as raw bytes with copies of its headers, the
+ * headers are written back by finish. everything is edited the way
+ * patchelf does: in place when it fits, otherwise the data moves to a new
+ * PT_LOAD and the old copy is left unused
+ */
+pub(crate) struct Patch {
+    raw: Vec<u8>,
+    pub(crate) header: Elf64Ehdr,
+    pub(crate) programs: Vec<Elf64_Phdr>,
+    pub(crate) sections: Vec<Elf64Shdr>,
+    extension: Option<Extension>,
+}
+
+impl Patch {
+    pub(crate) fn new(elf: &Elf64) -> Result<Self, ElfError> {
+        let raw = elf.write(&WriteOptions::default())?;
+        let (_, written) = Elf64::parse(&raw).map_err(|_| ElfError::Truncated("written file"))?;
+        let mut patch = Self {
+            header: written.header.clone(),
+            programs: written.programs.iter().map(|p| p.header.clone()).collect(),
+            sections: written.sections.iter().map(|s| s.header.clone()).collect(),
+            raw,
+            extension: None,
+        };
+        patch.reuse_extension();
+        Ok(patch)
+    }
+    /*
+     * the last PT_LOAD starting with the program header table is the
+     * segment of an earlier edit, it grows instead of adding another one
+     */
+    fn reuse_extension(&mut self) {
+        let last = self
+            .programs
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.p_type == PType::PT_LOAD)
+            .max_by_key(|(_, p)| p.p_vaddr)
+            .map(|(i, p)| (i, p.clone()));
+        let (index, load) = match last {
+            Some((i, p)) if p.p_offset == self.header.e_phoff && p.p_filesz == p.p_memsz => (i, p),
+            _ => return,
+        };
+        let data = match self
+            .raw
+            .get(load.p_offset as usize..(load.p_offset + load.p_filesz) as usize)
+        {
+            Some(d) => d.to_vec(),
+            None => return,
+        };
+        self.programs.remove(index);
+        self.extension = Some(Extension {
+            offset: load.p_offset,
+            vaddr: load.p_vaddr,
+            data,
+            flags: load.p_flags,
+        });
+    }
+    fn offset_of(&self, addr: u64) -> Option<u64> {
+        self.programs
+            .iter()
+            .filter(|p| p.p_type == PType::PT_LOAD)
+            .find(|p| addr >= p.p_vaddr && addr < p.p_vaddr + p.p_filesz)
+            .map(|p| addr - p.p_vaddr + p.p_offset)
+    }
+    /*
+     * offset of addr in the data of the segment being added
+     */
+    fn extension_offset(&self, addr: u64) -> Option<usize> {
+        let extension = self.extension.as_ref()?;
+        match addr >= extension.vaddr && addr < extension.vaddr + extension.data.len() as u64 {
+            true => Some((addr - extension.vaddr) as usize),
+            false => None,
+        }
+    }
+    pub(crate) fn read_address(&self, addr: u64, len: usize) -> Option<&[u8]> {
+        if let Some(at) = self.extension_offset(addr) {
+            return self.extension.as_ref()?.data.get(at..at + len);
+        }
+        let offset = self.offset_of(addr)? as usize;
+        self.raw.get(offset..offset + len)
+    }
+    pub(crate) fn write_address(&mut self, addr: u64, data: &[u8]) -> Result<(), ElfError> {
+        if let Some(at) = self.extension_offset(addr) {
+            write_at(&mut self.extension.as_mut().unwrap().data, at, data);
+            return Ok(());
+        }
+        let offset = self.offset_of(addr).ok_or(ElfError::Unmapped(addr))?;
+        write_at(&mut self.raw, offset as usize, data);
+        Ok(())
+    }
+    /*
+     * the new segment keeps the difference between address and offset of
+     * the first PT_LOAD: kernels before 5.18 compute AT_PHDR from it and
+     * e_phoff instead of looking for the segment holding the table
+     */
+    fn extension(&mut self) -> &mut Extension {
+        if self.extension.is_none() {
+            let loads: Vec<&Elf64_Phdr> = self
+                .programs
+                .iter()
+                .filter(|p| p.p_type == PType::PT_LOAD)
+                .collect();
+            let delta = loads
+                .iter()
+                .min_by_key(|p| p.p_vaddr)
+                .map(|p| p.p_vaddr.wrapping_sub(p.p_offset))
+                .unwrap_or(0);
+            let end = loads
+                .iter()
+                .map(|p| p.p_vaddr + p.p_memsz)
+                .max()
+                .unwrap_or(0);
+            let offset = (self.raw.len() as u64)
+                .max(end.saturating_sub(delta))
+                .next_multiple_of(PAGE_SIZE);
+            self.extension = Some(Extension {
+                offset,
+                vaddr: offset.wrapping_add(delta),
+                data: vec![0; 56 * (self.programs.len() + 1)],
+                flags: PFlags::Read.into(),
+            });
+        }
+        self.extension.as_mut().unwrap()
+    }
+    /*
+     * adds data to the new segment and returns its offset and address
+     */
+    pub(crate) fn append(
+        &mut self,
+        data: &[u8],
+        align: u64,
+        flags: BitFlags<PFlags>,
+    ) -> (u64, u64) {
+        let extension = self.extension();
+        let at = extension.data.len().next_multiple_of(align.max(1) as usize);
+        extension.data.resize(at, 0);
+        extension.data.extend_from_slice(data);
+        extension.flags |= flags;
+        (extension.offset + at as u64, extension.vaddr + at as u64)
+    }
+    /*
+     * turns the segment of an earlier edit back into a plain PT_LOAD, its
+     * copy of the program header table is left unused. only done before
+     * anything was appended to it
+     */
+    fn close_extension(&mut self) {
+        if let Some(extension) = self.extension.take() {
+            write_at(&mut self.raw, extension.offset as usize, &extension.data);
+            let size = extension.data.len() as u64;
+            insert_load(
+                &mut self.programs,
+                Elf64_Phdr {
+                    p_type: PType::PT_LOAD,
+                    p_flags: extension.flags,
+                    p_offset: extension.offset,
+                    p_vaddr: extension.vaddr,
+                    p_paddr: extension.vaddr,
+                    p_filesz: size,
+                    p_memsz: size,
+                    p_align: PAGE_SIZE,
+                },
+            );
+        }
+    }
+    /*
+     * a PT_NULL entry, or with convert_note a PT_NOTE one, that can
+     * describe a new segment without growing the program header table
+     */
+    fn free_program(&self, convert_note: bool) -> Option<usize> {
+        self.programs
+            .iter()
+            .position(|p| p.p_type == PType::PT_NULL)
+            .or_else(|| match convert_note {
+                true => self
+                    .programs
+                    .iter()
+                    .rposition(|p| p.p_type == PType::PT_NOTE),
+                false => None,
+            })
+    }
+    /*
+     * makes sure count segments can be added. the table moves to a new
+     * segment with PT_NULL entries for them when there are not enough
+     * free entries
+     */
+    pub(crate) fn reserve_programs(&mut self, count: usize, convert_note: bool) {
+        let mut free = self
+            .programs
+            .iter()
+            .filter(|p| p.p_type == PType::PT_NULL)
+            .count();
+        if convert_note {
+            free += self
+                .programs
+                .iter()
+                .filter(|p| p.p_type == PType::PT_NOTE)
+                .count();
+        }
+        if free >= count {
+            return;
+        }
+        self.close_extension();
+        self.programs.extend((free..count).map(|_| Elf64_Phdr {
+            p_type: PType::PT_NULL,
+            p_flags: BitFlags::empty(),
+            p_offset: 0,
+            p_vaddr: 0,
+            p_paddr: 0,
+            p_filesz: 0,
+            p_memsz: 0,
+            p_align: 0,
+        }));
+        self.extension();
+    }
+    /*
+     * offset and address of the next segment added by add_segment: the
+     * first page after everything in the file and in memory
+     */
+    pub(crate) fn next_segment(&self) -> (u64, u64) {
+        let mut file_end = self.raw.len() as u64;
+        let mut memory_end = self
+            .programs
+            .iter()
+            .filter(|p| p.p_type == PType::PT_LOAD)
+            .map(|p| p.p_vaddr + p.p_memsz)
+            .max()
+            .unwrap_or(0);
+        if let Some(extension) = &self.extension {
+            file_end = file_end.max(extension.offset + extension.data.len() as u64);
+            memory_end = memory_end.max(extension.vaddr + extension.data.len() as u64);
+        }
+        (
+            file_end.next_multiple_of(PAGE_SIZE),
+            memory_end.next_multiple_of(PAGE_SIZE),
+        )
+    }
+    /*
+     * maps data in a PT_LOAD of its own at next_segment, taking over a
+     * free entry of the table. the entry moves after the last PT_LOAD
+     * since the kernel sizes the mapping from the first and the last one.
+     * nothing can be appended to the new segment of the table afterwards
+     */
+    pub(crate) fn add_segment(
+        &mut self,
+        data: &[u8],
+        flags: BitFlags<PFlags>,
+        convert_note: bool,
+    ) -> (u64, u64) {
+        self.reserve_programs(1, convert_note);
+        let (offset, vaddr) = self.next_segment();
+        let index = self.free_program(convert_note).unwrap();
+        self.programs.remove(index);
+        insert_load(
+            &mut self.programs,
+            Elf64_Phdr {
+                p_type: PType::PT_LOAD,
+                p_flags: flags,
+                p_offset: offset,
+                p_vaddr: vaddr,
+                p_paddr: vaddr,
+                p_filesz: data.len() as u64,
+                p_memsz: data.len() as u64,
+                p_align: PAGE_SIZE,
+            },
+        );
+        write_at(&mut self.raw, offset as usize, data);
+        (offset, vaddr)
+    }
+    pub(crate) fn move_section(&mut self, index: usize, offset: u64, addr: u64, size: u64) {
+        let section = &mut self.sections[index];
+        section.sh_offset = offset;
+        section.sh_addr = addr;
+        section.sh_size = size;
+    }
+    pub(crate) fn move_program(&mut self, index: usize, offset: u64, addr: u64, size: u64) {
+        let program = &mut self.programs[index];
+        program.p_offset = offset;
+        program.p_vaddr = addr;
+        program.p_paddr = addr;
+        program.p_filesz = size;
+        program.p_memsz = size;
+    }
+    pub(crate) fn finish(mut self) -> Result<Elf64, ElfError> {
+        if let Some(extension) = self.extension.take() {
+            /* sections that are not loaded go after the segment */
+            let tail: Vec<(usize, Vec<u8>)> = self
+                .sections
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| {
+                    !s.sh_flags.contains(SHFlags::SHF_ALLOC)
+                        && s.sh_type != ShType::SHT_NOBITS
+                        && s.sh_offset >= extension.offset
+                })
+                .filter_map(|(i, s)| {
+                    let range = s.sh_offset as usize..(s.sh_offset + s.sh_size) as usize;
+                    Some((i, self.raw.get(range)?.to_vec()))
+                })
+                .collect();
+            /* segments added after it keep their place */
+            let added: Vec<(u64, Vec<u8>)> = self
+                .programs
+                .iter()
+                .filter(|p| p.p_type == PType::PT_LOAD && p.p_offset >= extension.offset)
+                .filter_map(|p| {
+                    let range = p.p_offset as usize..(p.p_offset + p.p_filesz) as usize;
+                    Some((p.p_offset, self.raw.get(range)?.to_vec()))
+                })
+                .collect();
+            self.raw.truncate(extension.offset as usize);
+            let size = extension.data.len() as u64;
+            insert_load(
+                &mut self.programs,
+                Elf64_Phdr {
+                    p_type: PType::PT_LOAD,
+                    p_flags: extension.flags,
+                    p_offset: extension.offset,
+                    p_vaddr: extension.vaddr,
+                    p_paddr: extension.vaddr,
+                    p_filesz: size,
+                    p_memsz: size,
+                    p_align: PAGE_SIZE,
+                },
+            );
+            let table = 56 * self.programs.len() as u64;
+            if let Some(phdr) = self
+                .programs
+                .iter()
+                .position(|p| p.p_type == PType::PT_PHDR)
+            {
+                self.move_program(phdr, extension.offset, extension.vaddr, table);
+            }
+            self.header.e_phoff = extension.offset;
+            self.header.e_phentsize = 56;
+            self.header.e_phnum = self.programs.len() as u16;
+            write_at(&mut self.raw, extension.offset as usize, &extension.data);
+            let mut end = extension.offset + size;
+            for (offset, data) in added {
+                write_at(&mut self.raw, offset as usize, &data);
+                end = end.max(offset + data.len() as u64);
+            }
+            for (i, data) in tail {
+                let section = &mut self.sections[i];
+                section.sh_offset = end.next_multiple_of(section.sh_addralign.max(1));
+                write_at(&mut self.raw, section.sh_offset as usize, &data);
+                end = section.sh_offset + data.len() as u64;
+            }
+            if self.header.e_shoff >= extension.offset {
+                self.header.e_shoff = end.next_multiple_of(8);
+            }
+        }
+        for (n, program) in self.programs.iter().enumerate() {
+            let offset = self.header.e_phoff as usize + self.header.e_phentsize as usize * n;
+            write_at(&mut self.raw, offset, &Into::<Vec<u8>>::into(program));
+        }
+        for (n, section) in self.sections.iter().enumerate() {
+            let offset = self.header.e_shoff as usize + self.header.e_shentsize as usize * n;
+            write_at(&mut self.raw, offset, &Into::<Vec<u8>>::into(section));
+        }
+        write_at(&mut self.raw, 0, &Into::<Vec<u8>>::into(&self.header));
+        Elf64::parse(&self.raw)
+            .map(|(_, elf)| elf)
+            .map_err(|_| ElfError::Truncated("edited file"))
+    }
+}
+
+fn string_at(strings: &[u8], offset: u64) -> Option<String> {
+    let s = CStr::from_bytes_until_nul(strings.get(offset as usize..)?).ok()?;
+    Some(s.to_string_lossy().into_owned())
+}
+
+/*
+ * PT_LOAD entries are sorted by address
+ */
+fn insert_load(programs: &mut Vec<Elf64_Phdr>, load: Elf64_Phdr) {
+    let at = programs
+        .iter()
+        .position(|p| p.p_type == PType::PT_LOAD && p.p_vaddr > load.p_vaddr)
+        .or_else(|| {
+            programs
+                .iter()
+                .rposition(|p| p.p_type == PType::PT_LOAD)
+                .map(|i| i + 1)
+        })
+        .unwrap_or(programs.len());
+    programs.insert(at, load);
+}
+
+/*
+ * ld writes DT_NEEDED first, then DT_SONAME and the search path
+ */
+fn insert_entry(entries: &mut Vec<Elf64_Dyn>, entry: Elf64_Dyn) {
+    let at = entries
+        .iter()
+        .rposition(|e| {
+            matches!(
+                e.d_tag,
+                DynamicTag::DT_NEEDED
+                    | DynamicTag::DT_SONAME
+                    | DynamicTag::DT_RPATH
+                    | DynamicTag::DT_RUN_PATH
+            )
+        })
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    entries.insert(at, entry);
+}
+
+impl Elf64 {
+    /*
+     * path of the program interpreter from PT_INTERP
+     */
+    pub fn interpreter(&self) -> Option<String> {
+        let program = self
+            .programs
+            .iter()
+            .find(|p| p.header.p_type == PType::PT_INTERP)?;
+        string_at(&program.data.inner, 0)
+    }
+    fn dynamic_strings(&self, tag: DynamicTag) -> Vec<String> {
+        let dynamic = match self.dynamic() {
+            Some(d) => d,
+            None => return Vec::new(),
+        };
+        let strings = match (
+            dynamic.get(DynamicTag::DT_STRTAB),
+            dynamic.get(DynamicTag::DT_STRSZ),
+        ) {
+            (Some(strtab), Some(strsz)) => self.read_at_address(strtab, strsz as usize),
+            _ => None,
+        };
+        dynamic
+            .entries
+            .iter()
+            .take_while(|e| e.d_tag != DynamicTag::DT_NULL)
+            .filter(|e| e.d_tag == tag)
+            .filter_map(|e| string_at(strings?, e.val_ptr))
+            .collect()
+    }
+    pub fn needed(&self) -> Vec<String> {
+        self.dynamic_strings(DynamicTag::DT_NEEDED)
+    }
+    pub fn rpath(&self) -> Option<String> {
+        self.dynamic_strings(DynamicTag::DT_RPATH)
+            .into_iter()
+            .next()
+    }
+    pub fn runpath(&self) -> Option<String> {
+        self.dynamic_strings(DynamicTag::DT_RUN_PATH)
+            .into_iter()
+            .next()
+    }
+    /*
+     * a shorter path is written over the old one, a longer one goes to a
+     * new segment
+     */
+    pub fn set_interpreter(&mut self, path: &str) -> Result<(), ElfError> {
+        let mut patch = Patch::new(self)?;
+        let program = patch
+            .programs
+            .iter()
+            .position(|p| p.p_type == PType::PT_INTERP)
+            .ok_or_else(|| ElfError::UnknownSection(".interp".to_string()))?;
+        let old = patch.programs[program].clone();
+        let section = patch
+            .sections
+            .iter()
+            .position(|s| s.sh_type == ShType::SHT_PROGBITS && s.sh_offset == old.p_offset);
+        let mut data = path.as_bytes().to_vec();
+        data.push(0);
+        let size = data.len() as u64;
+        let (offset, addr) = match size <= old.p_filesz {
+            true => {
+                data.resize(old.p_filesz as usize, 0);
+                patch.write_address(old.p_vaddr, &data)?;
+                (old.p_offset, old.p_vaddr)
+            }
+            false => patch.append(&data, 1, PFlags::Read.into()),
+        };
+        patch.move_program(program, offset, addr, size);
+        if let Some(section) = section {
+            patch.move_section(section, offset, addr, size);
+        }
+        *self = patch.finish()?;
+        Ok(())
+    }
+    /*
+     * runs edit on the entries of the dynamic table before DT_NULL and the
+     * contents of .dynstr, then writes them back. .dynstr only grows so
+     * the names of .dynsym and of the version sections stay valid
+     */
+    fn edit_dynamic<F>(&mut self, edit: F) -> Result<(), ElfError>
+    where
+        F: FnOnce(&mut Vec<Elf64_Dyn>, &mut StringTableBuilder) -> Result<(), ElfError>,
+    {
+        let mut patch = Patch::new(self)?;
+        let program = patch
+            .programs
+            .iter()
+            .position(|p| p.p_type == PType::PT_DYNAMIC)
+            .ok_or_else(|| ElfError::UnknownSection(".dynamic".to_string()))?;
+        let (address, capacity) = (
+            patch.programs[program].p_vaddr,
+            patch.programs[program].p_filesz,
+        );
+        let raw = patch
+            .read_address(address, capacity as usize)
+            .ok_or(ElfError::Unmapped(address))?;
+        let dynamic = DynamicSection::from(raw);
+        let (strtab, strsz) = match (
+            dynamic.get(DynamicTag::DT_STRTAB),
+            dynamic.get(DynamicTag::DT_STRSZ),
+        ) {
+            (Some(strtab), Some(strsz)) => (strtab, strsz),
+            _ => return Err(ElfError::UnknownSection(".dynstr".to_string())),
+        };
+        let mut strings = StringTableBuilder::from_table(
+            patch
+                .read_address(strtab, strsz as usize)
+                .ok_or(ElfError::Unmapped(strtab))?,
+        );
+        let mut entries: Vec<Elf64_Dyn> = dynamic
+            .entries
+            .into_iter()
+            .take_while(|e| e.d_tag != DynamicTag::DT_NULL)
+            .collect();
+        edit(&mut entries, &mut strings)?;
+        if strings.len() as u64 > strsz {
+            let size = strings.len() as u64;
+            let (offset, addr) = patch.append(strings.data(), 1, PFlags::Read.into());
+            for entry in entries.iter_mut() {
+                match entry.d_tag {
+                    DynamicTag::DT_STRTAB => entry.val_ptr = addr,
+                    DynamicTag::DT_STRSZ => entry.val_ptr = size,
+                    _ => (),
+                }
+            }
+            if let Some(section) = patch
+                .sections
+                .iter()
+                .position(|s| s.sh_type == ShType::SHT_STRTAB && s.sh_addr == strtab)
+            {
+                patch.move_section(section, offset, addr, size);
+            }
+        }
+        entries.push(Elf64_Dyn {
+            d_tag: DynamicTag::DT_NULL,
+            val_ptr: 0,
+        });
+        let mut data: Vec<u8> = DynamicSection { entries }.into();
+        if data.len() as u64 <= capacity {
+            /* the rest is filled with DT_NULL entries */
+            data.resize(capacity as usize, 0);
+            patch.write_address(address, &data)?;
+        } else {
+            /* ld.so writes DT_DEBUG, the table has to stay writable */
+            let size = data.len() as u64;
+            let (offset, addr) = patch.append(&data, 8, PFlags::Read | PFlags::Write);
+            patch.move_program(program, offset, addr, size);
+            if let Some(section) = patch
+                .sections
+                .iter()
+                .position(|s| s.sh_type == ShType::SHT_DYNAMIC)
+            {
+                patch.move_section(section, offset, addr, size);
+            }
+        }
+        *self = patch.finish()?;
+        Ok(())
+    }
+    /*
+     * the library is loaded after the ones already needed, adding a name
+     * that is already needed changes nothing
+     */
+    pub fn add_needed(&mut self, name: &str) -> Result<(), ElfError> {
+        if self.needed().iter().any(|n| n == name) {
+            return Ok(());
+        }
+        self.edit_dynamic(|entries, strings| {
+            let val_ptr = strings.insert(name) as u64;
+            let at = entries
+                .iter()
+                .rposition(|e| e.d_tag == DynamicTag::DT_NEEDED)
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            entries.insert(
+                at,
+                Elf64_Dyn {
+                    d_tag: DynamicTag::DT_NEEDED,
+                    val_ptr,
+                },
+            );
+            Ok(())
+        })
+    }
+    pub fn remove_needed(&mut self, name: &str) -> Result<(), ElfError> {
+        self.edit_dynamic(|entries, strings| {
+            let before = entries.len();
+            entries.retain(|e| {
+                e.d_tag != DynamicTag::DT_NEEDED
+                    || string_at(strings.data(), e.val_ptr).as_deref() != Some(name)
+            });
+            match entries.len() == before {
+                true => Err(ElfError::UnknownNeeded(name.to_string())),
+                false => Ok(()),
+            }
+        })
+    }
+    pub fn replace_needed(&mut self, old: &str, new: &str) -> Result<(), ElfError> {
+        self.edit_dynamic(|entries, strings| {
+            let val_ptr = strings.insert(new) as u64;
+            let mut found = false;
+            for entry in entries.iter_mut() {
+                if entry.d_tag == DynamicTag::DT_NEEDED
+                    && string_at(strings.data(), entry.val_ptr).as_deref() == Some(old)
+                {
+                    entry.val_ptr = val_ptr;
+                    found = true;
+                }
+            }
+            match found {
+                true => Ok(()),
+                false => Err(ElfError::UnknownNeeded(old.to_string())),
+            }
+        })
+    }
+    fn set_dynamic_string(
+        &mut self,
+        tag: DynamicTag,
+        value: &str,
+        replaces: Option<DynamicTag>,
+    ) -> Result<(), ElfError> {
+        self.edit_dynamic(|entries, strings| {
+            let val_ptr = strings.insert(value) as u64;
+            if let Some(other) = replaces {
+                for entry in entries.iter_mut().filter(|e| e.d_tag == other) {
+                    entry.d_tag = tag;
+                }
+            }
+            let mut found = false;
+            entries.retain_mut(|e| {
+                if e.d_tag != tag {
+                    return true;
+                }
+                e.val_ptr = val_ptr;
+                !std::mem::replace(&mut found, true)
+            });
+            if !found {
+                insert_entry(
+                    entries,
+                    Elf64_Dyn {
+                        d_tag: tag,
+                        val_ptr,
+                    },
+                );
+            }
+            Ok(())
+        })
+    }
+    pub fn set_soname(&mut self, soname: &str) -> Result<(), ElfError> {
+        self.set_dynamic_string(DynamicTag::DT_SONAME, soname, None)
+    }
+    /*
+     * ld.so ignores DT_RPATH when there is a DT_RUNPATH, setting one of
+     * them replaces the other
+     */
+    pub fn set_rpath(&mut self, path: &str) -> Result<(), ElfError> {
+        self.set_dynamic_string(DynamicTag::DT_RPATH, path, Some(DynamicTag::DT_RUN_PATH))
+    }
+    pub fn set_runpath(&mut self, path: &str) -> Result<(), ElfError> {
+        self.set_dynamic_string(DynamicTag::DT_RUN_PATH, path, Some(DynamicTag::DT_RPATH))
+    }
+    /*
+     * removes both DT_RPATH and DT_RUNPATH
+     */
+    pub fn remove_rpath(&mut self) -> Result<(), ElfError> {
+        self.edit_dynamic(|entries, _| {
+            entries.retain(|e| !matches!(e.d_tag, DynamicTag::DT_RPATH | DynamicTag::DT_RUN_PATH));
+            Ok(())
+        })
+    }
+}
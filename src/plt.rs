@@ -0,0 +1,266 @@
+use super::elf64::Elf64;
+use super::section::elf64_dyn::{DynamicSection, DynamicTag};
+use super::section::section::SectionData;
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+use super::section::symbol::{Elf64_Rela, RelocationType};
+use serde::Serialize;
+use std::collections::HashMap;
+
+const ENDBR64: [u8; 4] = [0xf3, 0x0f, 0x1e, 0xfa];
+const BND_PREFIX: u8 = 0xf2;
+const JMP_RIP_RELATIVE: [u8; 2] = [0xff, 0x25];
+const PUSH_RIP_RELATIVE: [u8; 2] = [0xff, 0x35];
+const PUSH_IMM32: u8 = 0x68;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PltKind {
+    Plt,    // .plt, lazy binding stubs
+    PltSec, // .plt.sec, the jumps of an IBT enabled plt
+    PltGot, // .plt.got, stubs for functions that also have a GLOB_DAT slot
+}
+
+impl PltKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            ".plt" => Some(Self::Plt),
+            ".plt.sec" => Some(Self::PltSec),
+            ".plt.got" => Some(Self::PltGot),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PltStub {
+    pub address: u64,
+    pub size: u64,
+    pub kind: PltKind,
+    pub got_slot: Option<u64>, /* GOT entry the stub jumps through */
+    pub symbol: Option<String>,
+}
+
+impl PltStub {
+    /*
+     * name in the form used by objdump and perf, foo@plt
+     */
+    pub fn name(&self) -> Option<String> {
+        self.symbol.as_ref().map(|s| format!("{}@plt", s))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GotSlot {
+    pub address: u64,
+    pub relocation_type: RelocationType,
+    pub addend: u64,
+    pub symbol: Option<String>,
+}
+
+/*
+ * dynamic relocations split the way the loader sees them, the push index
+ * of a lazy plt stub is an index into jmprel
+ */
+#[derive(Default)]
+struct DynamicRelocations {
+    jmprel: Vec<GotSlot>,
+    other: Vec<GotSlot>,
+}
+
+impl Elf64 {
+    /*
+     * GOT slots patched by the dynamic loader for imported symbols, sorted
+     * by address
+     */
+    pub fn got_slots(&self) -> Vec<GotSlot> {
+        let relocations = self.plt_relocations();
+        let mut out: Vec<GotSlot> = relocations
+            .jmprel
+            .into_iter()
+            .chain(relocations.other)
+            .filter(|r| {
+                matches!(
+                    r.relocation_type,
+                    RelocationType::R_X86_64_JUMP_SLOT
+                        | RelocationType::R_X86_64_GLOB_DAT
+                        | RelocationType::R_X86_64_IRELATIVE
+                )
+            })
+            .collect();
+        out.sort_by_key(|r| r.address);
+        out
+    }
+    /*
+     * stubs of .plt, .plt.sec and .plt.got. the header of the lazy .plt
+     * (push GOT+8, jmp *GOT+16) is not a stub and is skipped
+     */
+    pub fn plt_stubs(&self) -> Vec<PltStub> {
+        let relocations = self.plt_relocations();
+        let by_slot: HashMap<u64, &GotSlot> = relocations
+            .jmprel
+            .iter()
+            .chain(&relocations.other)
+            .map(|r| (r.address, r))
+            .collect();
+        let mut ranges: Vec<(PltKind, u64, u64, u64)> = self
+            .sections
+            .iter()
+            .filter(|s| s.header.sh_type == ShType::SHT_PROGBITS)
+            .filter_map(|s| {
+                let kind = PltKind::from_name(&s.name)?;
+                let entsize = match s.header.sh_entsize {
+                    0 => 16,
+                    e => e,
+                };
+                Some((kind, s.header.sh_addr, s.header.sh_size, entsize))
+            })
+            .collect();
+        if ranges.is_empty() {
+            if let Some(dynamic) = self.dynamic() {
+                if let (Some(addr), Some(size)) = (
+                    dynamic.get(DynamicTag::DT_X86_64_PLT),
+                    dynamic.get(DynamicTag::DT_X86_64_PLTSZ),
+                ) {
+                    let entsize = match dynamic.get(DynamicTag::DT_X86_64_PLTENT) {
+                        None | Some(0) => 16,
+                        Some(e) => e,
+                    };
+                    ranges.push((PltKind::Plt, addr, size, entsize));
+                }
+            }
+        }
+        let mut out: Vec<PltStub> = Vec::new();
+        for (kind, start, size, entsize) in ranges {
+            let code = match self.read_at_address(start, size as usize) {
+                Some(c) => c,
+                None => continue,
+            };
+            for (i, entry) in code.chunks(entsize as usize).enumerate() {
+                let address = start + i as u64 * entsize;
+                let insn = skip_prefixes(entry);
+                if insn.starts_with(&PUSH_RIP_RELATIVE) {
+                    continue;
+                }
+                let got_slot = rip_relative_jump(entry)
+                    .map(|(end, disp)| address.wrapping_add(end as u64).wrapping_add(disp as u64));
+                let mut symbol = got_slot
+                    .and_then(|slot| by_slot.get(&slot))
+                    .and_then(|r| r.symbol.clone());
+                let mut slot = got_slot;
+                if got_slot.is_none() && insn.first() == Some(&PUSH_IMM32) {
+                    /*
+                     * lazy stub of an IBT plt, only the push index tells
+                     * which JUMP_SLOT it belongs to
+                     */
+                    let index = insn
+                        .get(1..5)
+                        .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize);
+                    if let Some(r) = index.and_then(|i| relocations.jmprel.get(i)) {
+                        symbol = r.symbol.clone();
+                        slot = Some(r.address);
+                    }
+                }
+                if slot.is_none() && symbol.is_none() {
+                    continue;
+                }
+                out.push(PltStub {
+                    address,
+                    size: entsize,
+                    kind,
+                    got_slot: slot,
+                    symbol,
+                });
+            }
+        }
+        out
+    }
+    /*
+     * foo@plt name of the stub containing addr
+     */
+    pub fn plt_symbol_for_address(&self, addr: u64) -> Option<String> {
+        self.plt_stubs()
+            .into_iter()
+            .find(|s| s.address <= addr && addr < s.address + s.size)
+            .and_then(|s| s.name())
+    }
+    /*
+     * relocations are taken from the allocated SHT_RELA sections, or from
+     * DT_JMPREL and DT_RELA when the section headers are stripped
+     */
+    fn plt_relocations(&self) -> DynamicRelocations {
+        let mut out = DynamicRelocations::default();
+        let dynamic = self.dynamic();
+        let jmprel = dynamic.as_ref().and_then(|d| d.get(DynamicTag::DT_JMPREL));
+        let mut found_section = false;
+        for section in &self.sections {
+            let rela = match &section.data {
+                SectionData::Rela(r) if section.header.sh_flags.contains(SHFlags::SHF_ALLOC) => r,
+                _ => continue,
+            };
+            found_section = true;
+            let symbols = self.linked_symbols(section.header.sh_link as usize);
+            let slots = rela.rela_entries.iter().map(|r| {
+                got_slot(
+                    r,
+                    symbols
+                        .and_then(|s| s.get(r.symbol_index as usize))
+                        .map(|s| s.name.clone()),
+                )
+            });
+            match Some(section.header.sh_addr) == jmprel || section.name == ".rela.plt" {
+                true => out.jmprel.extend(slots),
+                false => out.other.extend(slots),
+            }
+        }
+        if found_section {
+            return out;
+        }
+        if let Some(dynamic) = dynamic {
+            out.jmprel =
+                self.tag_relocations(&dynamic, DynamicTag::DT_JMPREL, DynamicTag::DT_PLTRELSZ);
+            out.other = self.tag_relocations(&dynamic, DynamicTag::DT_RELA, DynamicTag::DT_RELASZ);
+        }
+        out
+    }
+    fn tag_relocations(
+        &self,
+        dynamic: &DynamicSection,
+        start_tag: DynamicTag,
+        size_tag: DynamicTag,
+    ) -> Vec<GotSlot> {
+        self.dynamic_rela(dynamic, start_tag, size_tag)
+            .iter()
+            .map(|r| {
+                let symbol = self.dynamic_symbol(dynamic, r.symbol_index);
+                got_slot(r, symbol.map(|s| s.name))
+            })
+            .collect()
+    }
+}
+
+fn got_slot(rela: &Elf64_Rela, symbol: Option<String>) -> GotSlot {
+    GotSlot {
+        address: rela.r_offset,
+        relocation_type: rela.relocation_type,
+        addend: rela.r_addend,
+        symbol: symbol.filter(|s| !s.is_empty()),
+    }
+}
+
+fn skip_prefixes(entry: &[u8]) -> &[u8] {
+    let entry = entry.strip_prefix(&ENDBR64).unwrap_or(entry);
+    entry.strip_prefix(&[BND_PREFIX]).unwrap_or(entry)
+}
+
+/*
+ * end offset in the entry and displacement of a jmp *disp32(%rip)
+ */
+fn rip_relative_jump(entry: &[u8]) -> Option<(usize, i32)> {
+    let insn = skip_prefixes(entry);
+    if !insn.starts_with(&JMP_RIP_RELATIVE) {
+        return None;
+    }
+    let start = entry.len() - insn.len();
+    let disp = i32::from_le_bytes(insn.get(2..6)?.try_into().unwrap());
+    Some((start + 6, disp))
+}
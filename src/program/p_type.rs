@@ -4,7 +4,7 @@ use nom::number::complete::le_u32;
 use nom::IResult;
 use serde::Serialize;
 
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[repr(u32)]
 pub enum PType {
     /**
@@ -1,6 +1,8 @@
 #![allow(non_camel_case_types)]
 
 use super::super::common::{ParserIn, ParserOut, RawBinaryData};
+use super::super::note::note::NoteSection;
+use super::p_type::PType;
 use super::program_header::Elf64_Phdr;
 use serde::Serialize;
 
@@ -16,11 +18,22 @@ impl Program {
         Ok((
             (input.whole_file, r).into(),
             Self {
-                data: input.whole_file
-                    [header.p_offset as usize..(header.p_offset + header.p_filesz) as usize]
+                data: input
+                    .whole_file
+                    .get(
+                        header.p_offset as usize
+                            ..header.p_offset.saturating_add(header.p_filesz) as usize,
+                    )
+                    .unwrap_or_default()
                     .into(),
                 header,
             },
         ))
     }
+    pub fn notes(&self) -> NoteSection {
+        match self.header.p_type {
+            PType::PT_NOTE => NoteSection::parse(&self.data.inner, self.header.p_align as usize),
+            _ => NoteSection::default(),
+        }
+    }
 }
@@ -15,7 +15,7 @@ pub enum PFlags {
     Read = 0x4,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Elf64_Phdr {
     pub p_type: PType,             /* Type of segment */
     pub p_flags: BitFlags<PFlags>, /* Segment attributes */
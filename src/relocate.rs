@@ -0,0 +1,287 @@
+use super::elf64::Elf64;
+use super::error::ElfError;
+use super::image::MemoryImage;
+use super::program::p_type::PType;
+use super::program::program_header::PFlags;
+use super::section::elf64_dyn::DynamicTag;
+use super::section::relr::RelrSection;
+use super::section::sh_type::ShType;
+use super::section::symbol::{
+    Elf64_Rela, Elf64_Sym, RelocationType, SectionIndex, SymbolBinding, SymbolType,
+};
+use super::section::version::SymbolVersion;
+use serde::Serialize;
+
+/*
+ * entries of a writable .dynamic rebased in place by ld.so
+ * (elf_get_dynamic_info)
+ */
+const REBASED_DYNAMIC_TAGS: [DynamicTag; 10] = [
+    DynamicTag::DT_PLTGOT,
+    DynamicTag::DT_HASH,
+    DynamicTag::DT_STRTAB,
+    DynamicTag::DT_SYMTAB,
+    DynamicTag::DT_RELA,
+    DynamicTag::DT_REL,
+    DynamicTag::DT_JMPREL,
+    DynamicTag::DT_RELR,
+    DynamicTag::DT_GNU_HASH,
+    DynamicTag::DT_VERSYM,
+];
+
+/*
+ * what the resolver callback is asked for
+ */
+#[derive(Debug)]
+pub enum ResolveRequest<'a> {
+    /*
+     * address of a symbol. for TPOFF64 the thread pointer relative offset
+     * of the symbol and for DTPOFF64 its offset in the TLS block of the
+     * module defining it
+     */
+    Symbol {
+        name: &'a str,
+        version: Option<&'a SymbolVersion>,
+        weak: bool,
+        relocation_type: RelocationType,
+    },
+    TlsBlockOffset, // thread pointer relative offset of the TLS block of this file
+}
+
+/*
+ * relocations that need the running process and were left untouched
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingRelocation {
+    pub place: u64,
+    pub relocation_type: RelocationType,
+    pub symbol: Option<String>,
+    pub value: u64, /* Resolver function for IRELATIVE and IFUNC, size of the copy for COPY */
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct RelocationReport {
+    pub applied: usize,
+    pub pending: Vec<PendingRelocation>,
+}
+
+impl Elf64 {
+    /*
+     * applies .rela.dyn, .rela.plt and RELR the way ld.so does with
+     * immediate binding, base is the load bias of the image. symbols are
+     * given to the resolver first, a definition in this file is used when
+     * it returns None and unresolved weak references become 0. a writable
+     * .dynamic gets its address entries rebased like ld.so does. IRELATIVE,
+     * COPY, DTPMOD64 and references to an STT_GNU_IFUNC defined here depend
+     * on the running process and are reported in pending
+     */
+    pub fn apply_dynamic_relocations<F>(
+        &self,
+        image: &mut MemoryImage,
+        base: u64,
+        mut resolver: F,
+    ) -> Result<RelocationReport, ElfError>
+    where
+        F: FnMut(&ResolveRequest) -> Option<u64>,
+    {
+        let mut report = RelocationReport::default();
+        let dynamic = match self.dynamic() {
+            Some(d) => d,
+            None => return Ok(report),
+        };
+        if dynamic.get(DynamicTag::DT_RELSZ).unwrap_or(0) != 0 {
+            return Err(ElfError::UnsupportedRelocationTable("DT_REL"));
+        }
+        let versions = self
+            .sections
+            .iter()
+            .position(|s| s.header.sh_type == ShType::SHT_DYNSYM)
+            .map(|i| self.symbol_versions(i))
+            .unwrap_or_default();
+        let mut relocations =
+            self.dynamic_rela(&dynamic, DynamicTag::DT_RELA, DynamicTag::DT_RELASZ);
+        match dynamic.get(DynamicTag::DT_PLTREL).map(DynamicTag::from) {
+            Some(DynamicTag::DT_RELA) | None => relocations.extend(self.dynamic_rela(
+                &dynamic,
+                DynamicTag::DT_JMPREL,
+                DynamicTag::DT_PLTRELSZ,
+            )),
+            Some(_) => return Err(ElfError::UnsupportedRelocationTable("DT_REL")),
+        }
+        if let Some(program) = self
+            .programs
+            .iter()
+            .find(|p| p.header.p_type == PType::PT_DYNAMIC)
+            .filter(|p| p.header.p_flags.contains(PFlags::Write) && base != 0)
+        {
+            for (i, entry) in dynamic.entries.iter().enumerate() {
+                if entry.d_tag == DynamicTag::DT_NULL {
+                    break;
+                }
+                if REBASED_DYNAMIC_TAGS.contains(&entry.d_tag) {
+                    let place = base.wrapping_add(program.header.p_vaddr + 16 * i as u64 + 8);
+                    image.write_u64(place, entry.val_ptr.wrapping_add(base))?;
+                }
+            }
+        }
+        if let (Some(start), Some(size)) = (
+            dynamic.get(DynamicTag::DT_RELR),
+            dynamic.get(DynamicTag::DT_RELRSZ),
+        ) {
+            let raw = self
+                .read_at_address(start, size as usize)
+                .ok_or(ElfError::Truncated("DT_RELR"))?;
+            for addr in RelrSection::from(raw).addresses() {
+                let place = base.wrapping_add(addr);
+                let value = image.read_u64(place).ok_or(ElfError::Unmapped(place))?;
+                image.write_u64(place, value.wrapping_add(base))?;
+                report.applied += 1;
+            }
+        }
+        for rela in &relocations {
+            let symbol = self.dynamic_symbol(&dynamic, rela.symbol_index);
+            let version = versions
+                .get(rela.symbol_index as usize)
+                .and_then(|v| v.as_ref());
+            let place = base.wrapping_add(rela.r_offset);
+            let pending = |value: u64| PendingRelocation {
+                place,
+                relocation_type: rela.relocation_type,
+                symbol: symbol.as_ref().map(|s| s.name.clone()),
+                value,
+            };
+            let value = match rela.relocation_type {
+                RelocationType::R_X86_64_NONE => continue,
+                RelocationType::R_X86_64_IRELATIVE => {
+                    report
+                        .pending
+                        .push(pending(base.wrapping_add(rela.r_addend)));
+                    continue;
+                }
+                RelocationType::R_X86_64_COPY => {
+                    report
+                        .pending
+                        .push(pending(symbol.as_ref().map(|s| s.st_size).unwrap_or(0)));
+                    continue;
+                }
+                RelocationType::R_X86_64_DTPMOD64 => {
+                    report.pending.push(pending(0));
+                    continue;
+                }
+                RelocationType::R_X86_64_DTPOFF64 => {
+                    let s = resolve(&mut resolver, rela, symbol.as_ref(), version, 0, true)?;
+                    s.wrapping_add(rela.r_addend)
+                }
+                RelocationType::R_X86_64_TPOFF64 => {
+                    let defined = symbol.as_ref().map(|s| s.is_defined()).unwrap_or(true);
+                    let offset = match defined {
+                        true => {
+                            let block = resolver(&ResolveRequest::TlsBlockOffset)
+                                .ok_or(ElfError::UnresolvedSymbol("TLS block offset".into()))?;
+                            block.wrapping_add(symbol.as_ref().map(|s| s.st_value).unwrap_or(0))
+                        }
+                        false => resolve(&mut resolver, rela, symbol.as_ref(), version, 0, true)?,
+                    };
+                    offset.wrapping_add(rela.r_addend)
+                }
+                t => {
+                    let s = match (rela.symbol_index, symbol.as_ref()) {
+                        (0, _) => 0,
+                        (_, Some(s)) if calls_resolver(t, s) => {
+                            match lookup(&mut resolver, rela, s, version) {
+                                Some(v) => v,
+                                None => {
+                                    report.pending.push(pending(base.wrapping_add(s.st_value)));
+                                    continue;
+                                }
+                            }
+                        }
+                        _ => resolve(&mut resolver, rela, symbol.as_ref(), version, base, false)?,
+                    };
+                    t.compute(s, rela.r_addend, place, base)
+                        .ok_or(ElfError::UnsupportedRelocation(t.into()))?
+                }
+            };
+            let size = rela
+                .relocation_type
+                .field_size()
+                .ok_or(ElfError::UnsupportedRelocation(rela.relocation_type.into()))?;
+            image.write(place, &value.to_le_bytes()[..size])?;
+            report.applied += 1;
+        }
+        Ok(report)
+    }
+}
+
+/*
+ * a defined STT_GNU_IFUNC symbol is the address of its resolver, the value
+ * it returns is only known in the running process like IRELATIVE
+ */
+fn calls_resolver(relocation_type: RelocationType, symbol: &Elf64_Sym) -> bool {
+    matches!(
+        relocation_type,
+        RelocationType::R_X86_64_64
+            | RelocationType::R_X86_64_GLOB_DAT
+            | RelocationType::R_X86_64_JUMP_SLOT
+    ) && symbol.symbol_type == SymbolType::STT_GNU_IFUNC
+        && symbol.is_defined()
+}
+
+fn lookup<F>(
+    resolver: &mut F,
+    rela: &Elf64_Rela,
+    symbol: &Elf64_Sym,
+    version: Option<&SymbolVersion>,
+) -> Option<u64>
+where
+    F: FnMut(&ResolveRequest) -> Option<u64>,
+{
+    resolver(&ResolveRequest::Symbol {
+        name: &symbol.name,
+        version,
+        weak: symbol.symbol_binding == SymbolBinding::STB_WEAK,
+        relocation_type: rela.relocation_type,
+    })
+}
+
+/*
+ * S of a relocation. tls symbols defined here resolve to their offset in
+ * the TLS block, other definitions are relocated by base
+ */
+fn resolve<F>(
+    resolver: &mut F,
+    rela: &Elf64_Rela,
+    symbol: Option<&Elf64_Sym>,
+    version: Option<&SymbolVersion>,
+    base: u64,
+    tls: bool,
+) -> Result<u64, ElfError>
+where
+    F: FnMut(&ResolveRequest) -> Option<u64>,
+{
+    let symbol = match symbol {
+        Some(s) => s,
+        None => {
+            return Err(ElfError::UnresolvedSymbol(format!(
+                "#{}",
+                rela.symbol_index
+            )))
+        }
+    };
+    let weak = symbol.symbol_binding == SymbolBinding::STB_WEAK;
+    if let Some(v) = lookup(resolver, rela, symbol, version) {
+        return Ok(v);
+    }
+    if symbol.is_defined() {
+        return Ok(
+            match tls || symbol.section_index() == SectionIndex::SHN_ABS {
+                true => symbol.st_value,
+                false => base.wrapping_add(symbol.st_value),
+            },
+        );
+    }
+    match weak {
+        true => Ok(0),
+        false => Err(ElfError::UnresolvedSymbol(symbol.name.clone())),
+    }
+}
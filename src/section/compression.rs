@@ -0,0 +1,157 @@
+#![allow(non_camel_case_types)]
+
+use super::super::error::ElfError;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use nom::bytes::complete::tag;
+use nom::number::complete::{be_u64, le_u32, le_u64};
+use nom::sequence;
+use nom::IResult;
+use serde::Serialize;
+use std::io::{Read, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[repr(u32)]
+pub enum ChType {
+    ELFCOMPRESS_ZLIB = 1, // zlib stream (rfc 1950)
+    ELFCOMPRESS_ZSTD = 2, // zstd frame
+    UNSPECIFIED(u32),
+}
+
+impl From<u32> for ChType {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::ELFCOMPRESS_ZLIB,
+            2 => Self::ELFCOMPRESS_ZSTD,
+            _ => Self::UNSPECIFIED(value),
+        }
+    }
+}
+
+impl Into<u32> for ChType {
+    fn into(self) -> u32 {
+        match self {
+            Self::ELFCOMPRESS_ZLIB => 1,
+            Self::ELFCOMPRESS_ZSTD => 2,
+            Self::UNSPECIFIED(v) => v,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Elf64_Chdr {
+    pub ch_type: ChType,   /* Compression algorithm */
+    pub ch_reserved: u32,  /* Reserved */
+    pub ch_size: u64,      /* Size of the uncompressed data */
+    pub ch_addralign: u64, /* Alignment of the uncompressed data */
+}
+
+pub const CHDR_SIZE: usize = 24;
+
+impl Elf64_Chdr {
+    pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
+        let (r, (ch_type, ch_reserved, ch_size, ch_addralign)) =
+            sequence::tuple((le_u32, le_u32, le_u64, le_u64))(raw)?;
+        Ok((
+            r,
+            Self {
+                ch_type: ch_type.into(),
+                ch_reserved,
+                ch_size,
+                ch_addralign,
+            },
+        ))
+    }
+}
+
+impl Into<Vec<u8>> for &Elf64_Chdr {
+    fn into(self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        out.extend(Into::<u32>::into(self.ch_type).to_le_bytes());
+        out.extend(self.ch_reserved.to_le_bytes());
+        out.extend(self.ch_size.to_le_bytes());
+        out.extend(self.ch_addralign.to_le_bytes());
+        out
+    }
+}
+
+/*
+ * expected comes from the file: the output is read up to one byte past it
+ * so a stream that is longer is noticed without inflating all of it
+ */
+fn inflate(compressed: &[u8], ch_type: ChType, expected: u64) -> Result<Vec<u8>, ElfError> {
+    let capacity = expected.min(compressed.len() as u64 * 4);
+    let mut out: Vec<u8> = Vec::with_capacity(capacity as usize);
+    let limit = expected.saturating_add(1);
+    match ch_type {
+        ChType::ELFCOMPRESS_ZLIB => {
+            ZlibDecoder::new(compressed)
+                .take(limit)
+                .read_to_end(&mut out)
+                .map_err(|e| ElfError::Compression(e.to_string()))?;
+        }
+        ChType::ELFCOMPRESS_ZSTD => {
+            ruzstd::decoding::StreamingDecoder::new(compressed)
+                .map_err(|e| ElfError::Compression(e.to_string()))?
+                .take(limit)
+                .read_to_end(&mut out)
+                .map_err(|e| ElfError::Compression(e.to_string()))?;
+        }
+        ChType::UNSPECIFIED(v) => return Err(ElfError::UnsupportedCompression(v)),
+    }
+    if out.len() as u64 != expected {
+        return Err(ElfError::SizeMismatch {
+            expected,
+            actual: out.len() as u64,
+        });
+    }
+    Ok(out)
+}
+
+/*
+ * contents of a SHF_COMPRESSED section: Elf64_Chdr followed by the stream
+ */
+pub fn decompress(raw: &[u8]) -> Result<Vec<u8>, ElfError> {
+    let (compressed, header) =
+        Elf64_Chdr::parse(raw).map_err(|_| ElfError::Truncated("Elf64_Chdr"))?;
+    inflate(compressed, header.ch_type, header.ch_size)
+}
+
+/*
+ * contents of a legacy .zdebug_* section: "ZLIB", big endian size, zlib stream
+ */
+pub fn decompress_zdebug(raw: &[u8]) -> Result<Vec<u8>, ElfError> {
+    let (compressed, (_, size)) = sequence::tuple((tag("ZLIB"), be_u64))(raw)
+        .map_err(|_: nom::Err<nom::error::Error<&[u8]>>| ElfError::Truncated("zdebug header"))?;
+    inflate(compressed, ChType::ELFCOMPRESS_ZLIB, size)
+}
+
+pub fn compress(data: &[u8], ch_type: ChType, addralign: u64) -> Result<Vec<u8>, ElfError> {
+    let header = Elf64_Chdr {
+        ch_type,
+        ch_reserved: 0,
+        ch_size: data.len() as u64,
+        ch_addralign: addralign,
+    };
+    let mut out: Vec<u8> = (&header).into();
+    match ch_type {
+        ChType::ELFCOMPRESS_ZLIB => {
+            let mut encoder = ZlibEncoder::new(out, Compression::default());
+            encoder
+                .write_all(data)
+                .map_err(|e| ElfError::Compression(e.to_string()))?;
+            out = encoder
+                .finish()
+                .map_err(|e| ElfError::Compression(e.to_string()))?;
+        }
+        ChType::ELFCOMPRESS_ZSTD => {
+            out.extend(ruzstd::encoding::compress_to_vec(
+                data,
+                ruzstd::encoding::CompressionLevel::Fastest,
+            ));
+        }
+        ChType::UNSPECIFIED(v) => return Err(ElfError::UnsupportedCompression(v)),
+    }
+    Ok(out)
+}
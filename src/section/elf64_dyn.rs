@@ -1,9 +1,11 @@
 #![allow(non_camel_case_types)]
 
+use nom::multi;
 use nom::number;
 use nom::IResult;
+use serde::Serialize;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[repr(u64)]
 pub enum DynamicTag {
     DT_NULL = 0,
@@ -36,9 +38,20 @@ pub enum DynamicTag {
     DT_INIT_ARRAYSZ = 27,
     DT_FINI_ARRAYSZ = 28,
     DT_RUN_PATH = 0x1d,
+    DT_FLAGS = 30,
+    DT_PREINIT_ARRAY = 32,
+    DT_PREINIT_ARRAYSZ = 33,
+    DT_RELRSZ = 35,
+    DT_RELR = 36,
+    DT_RELRENT = 37,
     DT_GNU_HASH = 0x6ffffef5,
     DT_Flags1 = 0x6ffffffb,
     DT_RELA_COUNT = 0x6ffffff9,
+    DT_VERSYM = 0x6ffffff0,
+    DT_VERDEF = 0x6ffffffc,
+    DT_VERDEFNUM = 0x6ffffffd,
+    DT_VERNEED = 0x6ffffffe,
+    DT_VERNEEDNUM = 0x6fffffff,
     DT_X86_64_PLT = 0x70000000,
     DT_X86_64_PLTSZ = 0x70000001,
     DT_X86_64_PLTENT = 0x70000003,
@@ -78,9 +91,20 @@ impl Into<u64> for DynamicTag {
             Self::DT_INIT_ARRAYSZ => 27,
             Self::DT_FINI_ARRAYSZ => 28,
             Self::DT_RUN_PATH => 0x1d,
+            Self::DT_FLAGS => 30,
+            Self::DT_PREINIT_ARRAY => 32,
+            Self::DT_PREINIT_ARRAYSZ => 33,
+            Self::DT_RELRSZ => 35,
+            Self::DT_RELR => 36,
+            Self::DT_RELRENT => 37,
             Self::DT_GNU_HASH => 0x6ffffef5,
             Self::DT_Flags1 => 0x6ffffffb,
             Self::DT_RELA_COUNT => 0x6ffffff9,
+            Self::DT_VERSYM => 0x6ffffff0,
+            Self::DT_VERDEF => 0x6ffffffc,
+            Self::DT_VERDEFNUM => 0x6ffffffd,
+            Self::DT_VERNEED => 0x6ffffffe,
+            Self::DT_VERNEEDNUM => 0x6fffffff,
             Self::DT_X86_64_PLT => 0x70000000,
             Self::DT_X86_64_PLTSZ => 0x70000001,
             Self::DT_X86_64_PLTENT => 0x70000003,
@@ -122,9 +146,20 @@ impl From<u64> for DynamicTag {
             27 => Self::DT_INIT_ARRAYSZ,
             28 => Self::DT_FINI_ARRAYSZ,
             0x1d => Self::DT_RUN_PATH,
+            30 => Self::DT_FLAGS,
+            32 => Self::DT_PREINIT_ARRAY,
+            33 => Self::DT_PREINIT_ARRAYSZ,
+            35 => Self::DT_RELRSZ,
+            36 => Self::DT_RELR,
+            37 => Self::DT_RELRENT,
             0x6ffffef5 => Self::DT_GNU_HASH,
             0x6ffffffb => Self::DT_Flags1,
             0x6ffffff9 => Self::DT_RELA_COUNT,
+            0x6ffffff0 => Self::DT_VERSYM,
+            0x6ffffffc => Self::DT_VERDEF,
+            0x6ffffffd => Self::DT_VERDEFNUM,
+            0x6ffffffe => Self::DT_VERNEED,
+            0x6fffffff => Self::DT_VERNEEDNUM,
             0x70000000 => Self::DT_X86_64_PLT,
             0x70000001 => Self::DT_X86_64_PLTSZ,
             0x70000003 => Self::DT_X86_64_PLTENT,
@@ -133,6 +168,7 @@ impl From<u64> for DynamicTag {
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct Elf64_Dyn {
     pub d_tag: DynamicTag,
     pub val_ptr: u64,
@@ -151,3 +187,53 @@ impl Elf64_Dyn {
         ))
     }
 }
+
+impl Into<Vec<u8>> for &Elf64_Dyn {
+    fn into(self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        out.extend(Into::<u64>::into(self.d_tag).to_le_bytes());
+        out.extend(self.val_ptr.to_le_bytes());
+        out
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize)]
+pub struct DynamicSection {
+    pub entries: Vec<Elf64_Dyn>,
+}
+
+impl DynamicSection {
+    /*
+     * first entry with the tag, entries after DT_NULL are ignored
+     */
+    pub fn get(&self, tag: DynamicTag) -> Option<u64> {
+        self.entries
+            .iter()
+            .take_while(|e| e.d_tag != DynamicTag::DT_NULL)
+            .find(|e| e.d_tag == tag)
+            .map(|e| e.val_ptr)
+    }
+}
+
+impl<T: AsRef<[u8]>> From<T> for DynamicSection {
+    fn from(value: T) -> Self {
+        let v = value.as_ref();
+        let (_, s) = multi::many0(Elf64_Dyn::parse)(v).unwrap();
+        Self { entries: s }
+    }
+}
+
+impl Into<Vec<u8>> for &DynamicSection {
+    fn into(self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .flat_map::<Vec<u8>, _>(|e| e.into())
+            .collect()
+    }
+}
+
+impl Into<Vec<u8>> for DynamicSection {
+    fn into(self) -> Vec<u8> {
+        (&self).into()
+    }
+}
@@ -0,0 +1,63 @@
+#![allow(non_camel_case_types)]
+
+use enumflags2::{bitflags, BitFlags};
+use nom::multi;
+use nom::number::complete::le_u32;
+use serde::Serialize;
+
+#[bitflags]
+#[derive(Clone, Copy, Debug, Serialize)]
+#[repr(u32)]
+pub enum GroupFlags {
+    GRP_COMDAT = 0x1, // Only one copy of the group is kept by the linker
+}
+
+/*
+ * contents of a SHT_GROUP section: flags word followed by the indexes of
+ * the member sections. sh_link of the group is the symbol table and sh_info
+ * the index of the signature symbol in it
+ */
+#[derive(Default, Debug, Serialize)]
+pub struct GroupSection {
+    pub flags: BitFlags<GroupFlags>,
+    pub raw_flags: u32, /* Flag word as read, with the GRP_MASKOS and GRP_MASKPROC bits */
+    pub members: Vec<u32>,
+}
+
+impl GroupSection {
+    pub fn is_comdat(&self) -> bool {
+        self.flags.contains(GroupFlags::GRP_COMDAT)
+    }
+}
+
+impl<T: AsRef<[u8]>> From<T> for GroupSection {
+    fn from(value: T) -> Self {
+        let v = value.as_ref();
+        let (_, words) = multi::many0(le_u32::<_, nom::error::Error<&[u8]>>)(v).unwrap();
+        match words.split_first() {
+            Some((flags, members)) => Self {
+                flags: BitFlags::<GroupFlags>::from_bits_truncate(*flags),
+                raw_flags: *flags,
+                members: members.to_vec(),
+            },
+            None => Self::default(),
+        }
+    }
+}
+
+impl Into<Vec<u8>> for &GroupSection {
+    fn into(self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        /* flags wins for the bits it knows, raw_flags keeps the others */
+        let flags = (self.raw_flags & !BitFlags::<GroupFlags>::all().bits()) | self.flags.bits();
+        out.extend(flags.to_le_bytes());
+        out.extend(self.members.iter().flat_map(|m| m.to_le_bytes()));
+        out
+    }
+}
+
+impl Into<Vec<u8>> for GroupSection {
+    fn into(self) -> Vec<u8> {
+        (&self).into()
+    }
+}
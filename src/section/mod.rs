@@ -1,7 +1,12 @@
+pub mod compression;
 pub mod elf64_dyn;
 pub mod elf64_sym;
+pub mod group;
+pub mod relr;
 pub mod section;
 pub mod section_header;
 pub mod sh_flags;
 pub mod sh_type;
+pub mod string_table;
 pub mod symbol;
+pub mod version;
@@ -0,0 +1,51 @@
+use serde::Serialize;
+
+/*
+ * SHT_RELR: relative relocations packed as an address followed by bitmaps.
+ * an even entry is the address of the next slot to relocate, an odd entry
+ * is a bitmap where bit n (n > 0) relocates the slot n - 1 words after the
+ * current address, which then moves forward by 63 words
+ */
+#[derive(Default, Debug, Clone, Serialize)]
+pub struct RelrSection {
+    pub entries: Vec<u64>,
+}
+
+impl RelrSection {
+    pub fn addresses(&self) -> Vec<u64> {
+        let mut out: Vec<u64> = Vec::new();
+        let mut next = 0u64;
+        for entry in &self.entries {
+            if entry & 1 == 0 {
+                out.push(*entry);
+                next = entry.wrapping_add(8);
+                continue;
+            }
+            for bit in 1..64 {
+                if (entry >> bit) & 1 == 1 {
+                    out.push(next.wrapping_add((bit - 1) * 8));
+                }
+            }
+            next = next.wrapping_add(63 * 8);
+        }
+        out
+    }
+}
+
+impl<T: AsRef<[u8]>> From<T> for RelrSection {
+    fn from(value: T) -> Self {
+        Self {
+            entries: value
+                .as_ref()
+                .chunks_exact(8)
+                .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+                .collect(),
+        }
+    }
+}
+
+impl Into<Vec<u8>> for &RelrSection {
+    fn into(self) -> Vec<u8> {
+        self.entries.iter().flat_map(|e| e.to_le_bytes()).collect()
+    }
+}
@@ -1,5 +1,12 @@
 use super::super::common::{ParserIn, ParserOut, RawBinaryData};
+use super::super::elf64::Elf64;
+use super::super::error::ElfError;
+use super::super::note::note::NoteSection;
+use super::compression;
+use super::elf64_dyn::DynamicSection;
+use super::group::GroupSection;
 use super::section_header::Elf64Shdr;
+use super::sh_flags::SHFlags;
 use super::sh_type::ShType;
 use super::symbol::{Elf64_Rel, Elf64_Rela, Elf64_Sym};
 use nom::multi;
@@ -7,6 +14,7 @@ use serde::ser::SerializeSeq;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
+use std::io::BufReader;
 
 #[derive(Debug, Serialize)]
 pub struct Section {
@@ -16,128 +24,40 @@ pub struct Section {
 }
 
 impl Section {
-    pub fn parse(input: ParserIn<'_>) -> ParserOut<Self> {
+    /*
+     * contents of the section with SHF_COMPRESSED or the legacy .zdebug_*
+     * compression undone, other sections are returned as they are
+     */
+    pub fn decompressed_data(&self) -> Result<Vec<u8>, ElfError> {
+        let raw: Vec<u8> = (&self.data).into();
+        if self.header.sh_flags.contains(SHFlags::SHF_COMPRESSED) {
+            return compression::decompress(&raw);
+        }
+        if self.name.starts_with(".zdebug") && raw.starts_with(b"ZLIB") {
+            return compression::decompress_zdebug(&raw);
+        }
+        Ok(raw)
+    }
+    pub fn parse(input: ParserIn<'_>) -> ParserOut<'_, Self> {
         let (r, header) = Elf64Shdr::parse(input.remaining)?;
         let mut raw_data: Option<&[u8]> = None;
         if header.sh_offset != 0 && header.sh_size != 0 {
-            raw_data = Some(
-                (&input.whole_file
-                    [header.sh_offset as usize..(header.sh_offset + header.sh_size) as usize])
-                    .into(),
+            /*
+             * files made with objcopy --only-keep-debug keep the size of the
+             * stripped sections, their contents are not in the file
+             */
+            raw_data = input.whole_file.get(
+                header.sh_offset as usize..header.sh_offset.saturating_add(header.sh_size) as usize,
             );
         }
-        match header.sh_type {
-            ShType::SHT_NOBITS => {
-                return Ok((
-                    (input.whole_file, r).into(),
-                    Self {
-                        name: String::new(),
-                        header,
-                        data: SectionData::Unknown(Vec::new().into()),
-                    },
-                ))
-            }
-            ShType::SHT_DYNSYM | ShType::SHT_SYMTAB => {
-                if let Some(raw) = raw_data {
-                    return Ok((
-                        (input.whole_file, r).into(),
-                        Self {
-                            name: String::new(),
-                            header,
-                            data: SectionData::Symbol(raw.into()),
-                        },
-                    ));
-                }
-                return Ok((
-                    (input.whole_file, r).into(),
-                    Self {
-                        name: String::new(),
-                        header,
-                        data: SectionData::Symbol(SymbolSection::default()),
-                    },
-                ));
-            }
-            ShType::SHT_STRTAB => {
-                if let Some(raw) = raw_data {
-                    return Ok((
-                        (input.whole_file, r).into(),
-                        Self {
-                            name: String::new(),
-                            header,
-                            data: SectionData::String(raw.into()),
-                        },
-                    ));
-                }
-                return Ok((
-                    (input.whole_file, r).into(),
-                    Self {
-                        name: String::new(),
-                        header,
-                        data: SectionData::String(StringSection::default()),
-                    },
-                ));
-            }
-            ShType::SHT_REL => {
-                if let Some(raw) = raw_data {
-                    return Ok((
-                        (input.whole_file, r).into(),
-                        Self {
-                            name: String::new(),
-                            header,
-                            data: SectionData::Rel(raw.into()),
-                        },
-                    ));
-                }
-                return Ok((
-                    (input.whole_file, r).into(),
-                    Self {
-                        name: String::new(),
-                        header,
-                        data: SectionData::Rel(RelSection::default()),
-                    },
-                ));
-            }
-            ShType::SHT_RELA => {
-                if let Some(raw) = raw_data {
-                    return Ok((
-                        (input.whole_file, r).into(),
-                        Self {
-                            name: String::new(),
-                            header,
-                            data: SectionData::Rela(raw.into()),
-                        },
-                    ));
-                }
-                return Ok((
-                    (input.whole_file, r).into(),
-                    Self {
-                        name: String::new(),
-                        header,
-                        data: SectionData::Rela(RelaSection::default()),
-                    },
-                ));
-            }
-            _ => {
-                if let Some(raw) = raw_data {
-                    return Ok((
-                        (input.whole_file, r).into(),
-                        Self {
-                            name: String::new(),
-                            header,
-                            data: SectionData::Unknown(raw.into()),
-                        },
-                    ));
-                }
-                return Ok((
-                    (input.whole_file, r).into(),
-                    Self {
-                        name: String::new(),
-                        header,
-                        data: SectionData::Unknown(UnImplementedSection::default()),
-                    },
-                ));
-            }
-        }
+        Ok((
+            (input.whole_file, r).into(),
+            Self {
+                name: String::new(),
+                data: SectionData::parse(&header, raw_data),
+                header,
+            },
+        ))
     }
 }
 
@@ -147,9 +67,53 @@ pub enum SectionData {
     Rela(RelaSection),
     Rel(RelSection),
     Symbol(SymbolSection),
+    Note(NoteSection),
+    Group(GroupSection),
+    Dynamic(DynamicSection),
+    AddressArray(AddressArraySection),
+    MiniDebugInfo(MiniDebugInfoSection),
     Unknown(UnImplementedSection),
 }
 
+impl SectionData {
+    /*
+     * contents of a section with this header, raw is None for sections
+     * whose contents are not in the file
+     */
+    pub fn parse(header: &Elf64Shdr, raw: Option<&[u8]>) -> Self {
+        match (header.sh_type, raw) {
+            (ShType::SHT_NOBITS, _) => Self::Unknown(Vec::new().into()),
+            (ShType::SHT_DYNSYM | ShType::SHT_SYMTAB, Some(raw)) => Self::Symbol(raw.into()),
+            (ShType::SHT_DYNSYM | ShType::SHT_SYMTAB, None) => {
+                Self::Symbol(SymbolSection::default())
+            }
+            (ShType::SHT_STRTAB, Some(raw)) => Self::String(raw.into()),
+            (ShType::SHT_STRTAB, None) => Self::String(StringSection::default()),
+            (ShType::SHT_REL, Some(raw)) => Self::Rel(raw.into()),
+            (ShType::SHT_REL, None) => Self::Rel(RelSection::default()),
+            (ShType::SHT_RELA, Some(raw)) => Self::Rela(raw.into()),
+            (ShType::SHT_RELA, None) => Self::Rela(RelaSection::default()),
+            (ShType::SHT_DYNAMIC, Some(raw)) => Self::Dynamic(raw.into()),
+            (ShType::SHT_DYNAMIC, None) => Self::Dynamic(DynamicSection::default()),
+            (
+                ShType::SHT_INIT_ARRAY | ShType::SHT_FINI_ARRAY | ShType::SHT_PREINIT_ARRAY,
+                Some(raw),
+            ) => Self::AddressArray(raw.into()),
+            (ShType::SHT_INIT_ARRAY | ShType::SHT_FINI_ARRAY | ShType::SHT_PREINIT_ARRAY, None) => {
+                Self::AddressArray(AddressArraySection::default())
+            }
+            (ShType::SHT_GROUP, Some(raw)) => Self::Group(raw.into()),
+            (ShType::SHT_GROUP, None) => Self::Group(GroupSection::default()),
+            (ShType::SHT_NOTE, Some(raw)) => {
+                Self::Note(NoteSection::parse(raw, header.sh_addralign as usize))
+            }
+            (ShType::SHT_NOTE, None) => Self::Note(NoteSection::default()),
+            (_, Some(raw)) => Self::Unknown(raw.into()),
+            (_, None) => Self::Unknown(UnImplementedSection::default()),
+        }
+    }
+}
+
 impl Into<Vec<u8>> for SectionData {
     fn into(self) -> Vec<u8> {
         match self {
@@ -157,6 +121,11 @@ impl Into<Vec<u8>> for SectionData {
             Self::Rel(s) => s.into(),
             Self::Rela(s) => s.into(),
             Self::Symbol(s) => s.into(),
+            Self::Note(s) => s.into(),
+            Self::Group(s) => s.into(),
+            Self::Dynamic(s) => s.into(),
+            Self::AddressArray(s) => s.into(),
+            Self::MiniDebugInfo(s) => s.data.into(),
             Self::Unknown(s) => s.data.into(),
         }
     }
@@ -169,6 +138,11 @@ impl Into<Vec<u8>> for &SectionData {
             SectionData::Rel(s) => s.into(),
             SectionData::Rela(s) => s.into(),
             SectionData::Symbol(s) => s.into(),
+            SectionData::Note(s) => s.into(),
+            SectionData::Group(s) => s.into(),
+            SectionData::Dynamic(s) => s.into(),
+            SectionData::AddressArray(s) => s.into(),
+            SectionData::MiniDebugInfo(s) => (&s.data).into(),
             SectionData::Unknown(s) => s.into(),
         }
     }
@@ -197,6 +171,33 @@ impl<T: AsRef<[u8]>> From<T> for UnImplementedSection {
     }
 }
 
+/*
+ * .gnu_debugdata holds an xz compressed elf with a reduced .symtab,
+ * data keeps the compressed bytes so the section is written back unchanged
+ */
+#[derive(Debug, Serialize)]
+pub struct MiniDebugInfoSection {
+    pub data: RawBinaryData,
+    pub elf: Option<Box<Elf64>>,
+}
+
+impl<T: AsRef<[u8]>> From<T> for MiniDebugInfoSection {
+    fn from(value: T) -> Self {
+        let compressed = value.as_ref();
+        let mut decompressed: Vec<u8> = Vec::new();
+        let mut elf: Option<Box<Elf64>> = None;
+        if lzma_rs::xz_decompress(&mut BufReader::new(compressed), &mut decompressed).is_ok() {
+            if let Ok((_, e)) = Elf64::parse(&decompressed) {
+                elf = Some(Box::new(e));
+            }
+        }
+        Self {
+            data: compressed.into(),
+            elf,
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct StringSection {
     pub strings: HashMap<usize, String>,
@@ -208,26 +209,76 @@ impl Serialize for StringSection {
         S: serde::Serializer,
     {
         let mut seq = serializer.serialize_seq(Some(self.strings.len()))?;
-        for e in self.strings.values() {
+        for (_, e) in self.sorted() {
             seq.serialize_element(e)?;
         }
         seq.end()
     }
 }
 
+impl StringSection {
+    fn sorted(&self) -> Vec<(&usize, &String)> {
+        let mut strings = self.strings.iter().collect::<Vec<(&usize, &String)>>();
+        strings.sort_by(|a, b| a.0.cmp(b.0));
+        strings
+    }
+    /*
+     * offsets may point into the middle of a string when the linker merged
+     * a name with the tail of a longer one, the string holding it is the
+     * one starting last before the offset
+     */
+    pub fn get(&self, offset: usize) -> Option<String> {
+        if let Some(s) = self.strings.get(&offset) {
+            return Some(s.clone());
+        }
+        self.tail(*self.strings.keys().filter(|s| **s < offset).max()?, offset)
+    }
+    /*
+     * sorts the string offsets once for many calls to get
+     */
+    pub fn lookup(&self) -> StringLookup<'_> {
+        let mut starts: Vec<usize> = self.strings.keys().copied().collect();
+        starts.sort_unstable();
+        StringLookup {
+            section: self,
+            starts,
+        }
+    }
+    fn tail(&self, start: usize, offset: usize) -> Option<String> {
+        let s = self.strings.get(&start)?;
+        match offset < start + s.len() {
+            true => s.get(offset - start..).map(|t| t.to_string()),
+            false => None,
+        }
+    }
+}
+
+pub struct StringLookup<'a> {
+    section: &'a StringSection,
+    starts: Vec<usize>,
+}
+
+impl StringLookup<'_> {
+    pub fn get(&self, offset: usize) -> Option<String> {
+        if let Some(s) = self.section.strings.get(&offset) {
+            return Some(s.clone());
+        }
+        match self.starts.partition_point(|s| *s <= offset) {
+            0 => None,
+            i => self.section.tail(self.starts[i - 1], offset),
+        }
+    }
+}
+
 impl<T: AsRef<[u8]>> From<T> for StringSection {
     fn from(value: T) -> Self {
         let v = value.as_ref();
         let mut x: HashMap<usize, String> = HashMap::new();
         let mut end_of_last_str: usize = 0;
-        loop {
-            if let Ok(g) = CStr::from_bytes_until_nul(&v[end_of_last_str..]) {
-                let string_from_cstr = g.to_string_lossy().into_owned();
-                x.insert(end_of_last_str, string_from_cstr);
-                end_of_last_str += g.to_bytes().len() + 1;
-            } else {
-                break;
-            }
+        while let Ok(g) = CStr::from_bytes_until_nul(&v[end_of_last_str..]) {
+            let string_from_cstr = g.to_string_lossy().into_owned();
+            x.insert(end_of_last_str, string_from_cstr);
+            end_of_last_str += g.to_bytes().len() + 1;
         }
         Self { strings: x }
     }
@@ -235,29 +286,15 @@ impl<T: AsRef<[u8]>> From<T> for StringSection {
 
 impl Into<Vec<u8>> for StringSection {
     fn into(self) -> Vec<u8> {
-        let mut out: Vec<u8> = Vec::new();
-        let mut strings = self.strings.iter().collect::<Vec<(&usize, &String)>>();
-        strings.sort_by(|a, b| a.0.cmp(b.0));
-        println!("owned:: strings are {:?}", strings);
-        out.extend(
-            strings
-                .iter()
-                .map(|a| a.1)
-                .map(|s| CString::new(s.as_str()).unwrap())
-                .flat_map(|a| Vec::from(a.as_bytes_with_nul())),
-        );
-        out
+        (&self).into()
     }
 }
 
 impl Into<Vec<u8>> for &StringSection {
     fn into(self) -> Vec<u8> {
         let mut out: Vec<u8> = Vec::new();
-        let mut strings = self.strings.iter().collect::<Vec<(&usize, &String)>>();
-        strings.sort_by(|a, b| a.0.cmp(b.0));
-        println!("borrowed:: strings are {:?}", strings);
         out.extend(
-            strings
+            self.sorted()
                 .iter()
                 .map(|a| a.1)
                 .map(|s| CString::new(s.as_str()).unwrap())
@@ -267,6 +304,44 @@ impl Into<Vec<u8>> for &StringSection {
     }
 }
 
+/*
+ * init, fini and preinit arrays: one pointer per entry, in position
+ * independent files the real values come from relocations
+ */
+#[derive(Default, Debug, Serialize)]
+pub struct AddressArraySection {
+    pub entries: Vec<u64>,
+    pub trailing: Vec<u8>, /* Bytes after the last entry when sh_size is not a multiple of 8 */
+}
+
+impl<T: AsRef<[u8]>> From<T> for AddressArraySection {
+    fn from(value: T) -> Self {
+        let chunks = value.as_ref().chunks_exact(8);
+        Self {
+            trailing: chunks.remainder().to_vec(),
+            entries: chunks
+                .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+                .collect(),
+        }
+    }
+}
+
+impl Into<Vec<u8>> for AddressArraySection {
+    fn into(self) -> Vec<u8> {
+        (&self).into()
+    }
+}
+
+impl Into<Vec<u8>> for &AddressArraySection {
+    fn into(self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .flat_map(|e| e.to_le_bytes())
+            .chain(self.trailing.iter().copied())
+            .collect()
+    }
+}
+
 #[derive(Default, Debug, Serialize)]
 pub struct RelaSection {
     pub rela_entries: Vec<Elf64_Rela>,
@@ -286,8 +361,7 @@ impl Into<Vec<u8>> for RelaSection {
         out.extend(
             self.rela_entries
                 .iter()
-                .map::<Vec<u8>, _>(|a| a.into())
-                .flat_map(|f| f),
+                .flat_map::<Vec<u8>, _>(|a| a.into()),
         );
         out
     }
@@ -299,8 +373,7 @@ impl Into<Vec<u8>> for &RelaSection {
         out.extend(
             self.rela_entries
                 .iter()
-                .map::<Vec<u8>, _>(|a| a.into())
-                .flat_map(|f| f),
+                .flat_map::<Vec<u8>, _>(|a| a.into()),
         );
         out
     }
@@ -322,12 +395,7 @@ impl<T: AsRef<[u8]>> From<T> for RelSection {
 impl Into<Vec<u8>> for RelSection {
     fn into(self) -> Vec<u8> {
         let mut out: Vec<u8> = Vec::new();
-        out.extend(
-            self.rel_entries
-                .iter()
-                .map::<Vec<u8>, _>(|a| a.into())
-                .flat_map(|f| f),
-        );
+        out.extend(self.rel_entries.iter().flat_map::<Vec<u8>, _>(|a| a.into()));
         out
     }
 }
@@ -335,12 +403,7 @@ impl Into<Vec<u8>> for RelSection {
 impl Into<Vec<u8>> for &RelSection {
     fn into(self) -> Vec<u8> {
         let mut out: Vec<u8> = Vec::new();
-        out.extend(
-            self.rel_entries
-                .iter()
-                .map::<Vec<u8>, _>(|a| a.into())
-                .flat_map(|f| f),
-        );
+        out.extend(self.rel_entries.iter().flat_map::<Vec<u8>, _>(|a| a.into()));
         out
     }
 }
@@ -361,12 +424,7 @@ impl<T: AsRef<[u8]>> From<T> for SymbolSection {
 impl Into<Vec<u8>> for SymbolSection {
     fn into(self) -> Vec<u8> {
         let mut out: Vec<u8> = Vec::new();
-        out.extend(
-            self.symbols
-                .iter()
-                .map::<Vec<u8>, _>(|a| a.into())
-                .flat_map(|f| f),
-        );
+        out.extend(self.symbols.iter().flat_map::<Vec<u8>, _>(|a| a.into()));
         out
     }
 }
@@ -374,12 +432,7 @@ impl Into<Vec<u8>> for SymbolSection {
 impl Into<Vec<u8>> for &SymbolSection {
     fn into(self) -> Vec<u8> {
         let mut out: Vec<u8> = Vec::new();
-        out.extend(
-            self.symbols
-                .iter()
-                .map::<Vec<u8>, _>(|a| a.into())
-                .flat_map(|f| f),
-        );
+        out.extend(self.symbols.iter().flat_map::<Vec<u8>, _>(|a| a.into()));
         out
     }
 }
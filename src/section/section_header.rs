@@ -10,7 +10,7 @@ use nom::IResult;
 use serde::Serialize;
 use std::fmt::Debug;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Elf64Shdr {
     pub sh_name: u32,                /* Section name */
     pub sh_type: ShType,             /* Section type */
@@ -19,5 +19,9 @@ pub enum SHFlags {
     SHF_GROUP = 0x200,
     SHF_TLS = 0x400,
     SHF_COMPRESSED = 0x800,
+    SHF_GNU_RETAIN = 0x200000,
+    SHF_GNU_MBIND = 0x01000000,
     SHF_X86_64_LASHFlagsRGE = 0x10000000,
+    SHF_ORDERED = 0x40000000,
+    SHF_EXCLUDE = 0x80000000,
 }
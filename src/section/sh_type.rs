@@ -6,7 +6,7 @@ use serde::Serialize;
 
 use std::fmt::Debug;
 
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 #[repr(u32)]
 pub enum ShType {
     /**
@@ -28,6 +28,8 @@ pub enum ShType {
     SHT_INIT_ARRAY = 0xe,
     SHT_FINI_ARRAY = 0xf,
     SHT_PREINIT_ARRAY = 0x10,
+    SHT_GROUP = 0x11, // Section group (COMDAT)
+    SHT_RELR = 0x13,  // Relative relocations in the compact RELR format
     SHT_GNU_liblist = 0x6ffffff5,
     SHT_GNU_hash = 0x6ffffff6,
     SHT_GNU_attributes = 0x6ffffff7,
@@ -70,6 +72,8 @@ impl From<u32> for ShType {
             0xe => Self::SHT_INIT_ARRAY,
             0xf => Self::SHT_FINI_ARRAY,
             0x10 => Self::SHT_PREINIT_ARRAY,
+            0x13 => Self::SHT_RELR,
+            0x11 => Self::SHT_GROUP,
             0x6ffffff5 => Self::SHT_GNU_liblist,
             0x6ffffff6 => Self::SHT_GNU_hash,
             0x6ffffff7 => Self::SHT_GNU_attributes,
@@ -102,6 +106,8 @@ impl Into<u32> for ShType {
             Self::SHT_INIT_ARRAY => 0xe,
             Self::SHT_GNU_verdef => 0x6ffffffd,
             Self::SHT_PREINIT_ARRAY => 0x10,
+            Self::SHT_GROUP => 0x11,
+            Self::SHT_RELR => 0x13,
             Self::SHT_GNU_liblist => 0x6ffffff5,
             Self::SHT_GNU_hash => 0x6ffffff6,
             Self::SHT_GNU_attributes => 0x6ffffff7,
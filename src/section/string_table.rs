@@ -0,0 +1,120 @@
+use super::super::error::ElfError;
+use std::collections::{HashMap, HashSet};
+
+/*
+ * builds the contents of a string table. strings are added first and get
+ * their offset when the table is finalized: equal strings are stored once
+ * and a string that is the tail of another one points into it, like lld
+ * does. offsets never change once given so a table can be finalized again
+ * after more strings were added
+ */
+#[derive(Debug, Clone)]
+pub struct StringTableBuilder {
+    data: Vec<u8>,
+    existing: usize, /* bytes of the table the builder was created from */
+    offsets: HashMap<String, u32>,
+    pending: Vec<String>,
+    added: HashSet<String>,
+}
+
+impl Default for StringTableBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StringTableBuilder {
+    /*
+     * a table that only holds the empty string at offset 0
+     */
+    pub fn new() -> Self {
+        Self {
+            data: vec![0],
+            existing: 0,
+            offsets: HashMap::from([(String::new(), 0)]),
+            pending: Vec::new(),
+            added: HashSet::new(),
+        }
+    }
+    /*
+     * keeps the contents of an existing table, strings it already holds
+     * keep their offset and new ones are appended
+     */
+    pub fn from_table(table: &[u8]) -> Self {
+        let mut builder = Self::new();
+        if !table.is_empty() {
+            builder.data = table.to_vec();
+            if builder.data.last() != Some(&0) {
+                builder.data.push(0);
+            }
+            builder.existing = builder.data.len();
+        }
+        builder
+    }
+    pub fn add(&mut self, s: &str) {
+        if !self.offsets.contains_key(s) && self.added.insert(s.to_string()) {
+            self.pending.push(s.to_string());
+        }
+    }
+    /*
+     * places the strings added since the last call. sorting them by their
+     * reversed bytes puts every string right after the longest one it is
+     * the tail of
+     */
+    pub fn finalize(&mut self) {
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.sort_unstable_by(|a, b| b.bytes().rev().cmp(a.bytes().rev()));
+        let mut previous: Option<(&str, u32)> = None;
+        for s in &pending {
+            let offset = match previous {
+                Some((p, offset)) if p.ends_with(s.as_str()) => offset + (p.len() - s.len()) as u32,
+                _ => {
+                    let offset = self.find_existing(s).unwrap_or_else(|| {
+                        let offset = self.data.len() as u32;
+                        self.data.extend_from_slice(s.as_bytes());
+                        self.data.push(0);
+                        offset
+                    });
+                    previous = Some((s, offset));
+                    offset
+                }
+            };
+            self.offsets.insert(s.clone(), offset);
+        }
+        self.added.clear();
+    }
+    fn find_existing(&self, s: &str) -> Option<u32> {
+        let mut needle = s.as_bytes().to_vec();
+        needle.push(0);
+        self.data[..self.existing]
+            .windows(needle.len())
+            .position(|w| w == needle.as_slice())
+            .map(|p| p as u32)
+    }
+    /*
+     * adds a single string and places it at once
+     */
+    pub fn insert(&mut self, s: &str) -> u32 {
+        self.add(s);
+        self.finalize();
+        self.offsets.get(s).copied().unwrap_or_default()
+    }
+    /*
+     * offset of a string that was added and finalized
+     */
+    pub fn offset(&self, s: &str) -> Result<u32, ElfError> {
+        self.offsets
+            .get(s)
+            .copied()
+            .ok_or_else(|| ElfError::MissingString(s.to_string()))
+    }
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
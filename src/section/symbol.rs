@@ -4,11 +4,12 @@ use nom::number;
 use nom::IResult;
 use serde::Serialize;
 
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum SymbolBinding {
-    STB_LOCAL,  // Not visible outside the object file
-    STB_GLOBAL, // Global symbol, visible to all object files
-    STB_WEAK,   // Global scope, but with lower precedence than global symbols
+    STB_LOCAL,      // Not visible outside the object file
+    STB_GLOBAL,     // Global symbol, visible to all object files
+    STB_WEAK,       // Global scope, but with lower precedence than global symbols
+    STB_GNU_UNIQUE, // Global symbol unique in the whole process
     UNSPECIFIED(u8),
 }
 
@@ -18,6 +19,7 @@ impl Into<u8> for SymbolBinding {
             Self::STB_LOCAL => 0,
             Self::STB_GLOBAL => 1,
             Self::STB_WEAK => 2,
+            Self::STB_GNU_UNIQUE => 10,
             Self::UNSPECIFIED(v) => v,
         }
     }
@@ -29,17 +31,21 @@ impl From<u8> for SymbolBinding {
             0 => Self::STB_LOCAL,
             1 => Self::STB_GLOBAL,
             2 => Self::STB_WEAK,
+            10 => Self::STB_GNU_UNIQUE,
             _ => Self::UNSPECIFIED(value),
         }
     }
 }
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum SymbolType {
-    STT_NOTYPE,  // No type specified (e.g., an absolute symbol)
-    STT_OBJECT,  // Data object
-    STT_FUNC,    // Function entry point
-    STT_SECTION, // Symbol is associated with a section
-    STT_FILE,    // Source file associated with the object file
+    STT_NOTYPE,    // No type specified (e.g., an absolute symbol)
+    STT_OBJECT,    // Data object
+    STT_FUNC,      // Function entry point
+    STT_SECTION,   // Symbol is associated with a section
+    STT_FILE,      // Source file associated with the object file
+    STT_COMMON,    // Uninitialized common block
+    STT_TLS,       // Thread local data, the value is an offset in the TLS block
+    STT_GNU_IFUNC, // Function whose address is returned by a resolver
     UNSPECIFIED(u8),
 }
 
@@ -51,6 +57,9 @@ impl Into<u8> for SymbolType {
             Self::STT_FUNC => 2,
             Self::STT_SECTION => 3,
             Self::STT_FILE => 4,
+            Self::STT_COMMON => 5,
+            Self::STT_TLS => 6,
+            Self::STT_GNU_IFUNC => 10,
             Self::UNSPECIFIED(v) => v,
         }
     }
@@ -63,11 +72,77 @@ impl From<u8> for SymbolType {
             2 => Self::STT_FUNC,
             3 => Self::STT_SECTION,
             4 => Self::STT_FILE,
+            5 => Self::STT_COMMON,
+            6 => Self::STT_TLS,
+            10 => Self::STT_GNU_IFUNC,
             _ => Self::UNSPECIFIED(value),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SymbolVisibility {
+    STV_DEFAULT,   // Visibility given by the binding
+    STV_INTERNAL,  // Processor specific, treated as hidden
+    STV_HIDDEN,    // Not visible to other components
+    STV_PROTECTED, // Visible to other components but not preemptable
+}
+
+impl From<u8> for SymbolVisibility {
+    fn from(value: u8) -> Self {
+        match value & 0x3 {
+            0 => Self::STV_DEFAULT,
+            1 => Self::STV_INTERNAL,
+            2 => Self::STV_HIDDEN,
+            _ => Self::STV_PROTECTED,
+        }
+    }
+}
+
+impl Into<u8> for SymbolVisibility {
+    fn into(self) -> u8 {
+        match self {
+            Self::STV_DEFAULT => 0,
+            Self::STV_INTERNAL => 1,
+            Self::STV_HIDDEN => 2,
+            Self::STV_PROTECTED => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SectionIndex {
+    SHN_UNDEF,  // Undefined, resolved from another object
+    SHN_ABS,    // Absolute value, not relocated
+    SHN_COMMON, // Common block not yet allocated
+    SHN_XINDEX, // Real index is in SHT_SYMTAB_SHNDX
+    Section(u16),
+}
+
+impl From<u16> for SectionIndex {
+    fn from(value: u16) -> Self {
+        match value {
+            0 => Self::SHN_UNDEF,
+            0xfff1 => Self::SHN_ABS,
+            0xfff2 => Self::SHN_COMMON,
+            0xffff => Self::SHN_XINDEX,
+            _ => Self::Section(value),
+        }
+    }
+}
+
+impl Into<u16> for SectionIndex {
+    fn into(self) -> u16 {
+        match self {
+            Self::SHN_UNDEF => 0,
+            Self::SHN_ABS => 0xfff1,
+            Self::SHN_COMMON => 0xfff2,
+            Self::SHN_XINDEX => 0xffff,
+            Self::Section(v) => v,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Elf64_Sym {
     pub st_name: u32,  /* Symbol name  if 0 it has no name */
@@ -78,6 +153,7 @@ pub struct Elf64_Sym {
     pub st_size: u64,  /* Size of object (e.g., common) */
     pub symbol_type: SymbolType,
     pub symbol_binding: SymbolBinding,
+    pub(crate) name: String, /* resolved from the string table in sh_link of the symbol table */
 }
 
 impl Into<Vec<u8>> for Elf64_Sym {
@@ -107,6 +183,60 @@ impl Into<Vec<u8>> for &Elf64_Sym {
 }
 
 impl Elf64_Sym {
+    /*
+     * resolved from the string table in sh_link of the symbol table
+     */
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /*
+     * st_name is left to 0, it is set when the string table is written
+     */
+    pub fn new(
+        name: &str,
+        symbol_binding: SymbolBinding,
+        symbol_type: SymbolType,
+        st_shndx: u16,
+        st_value: u64,
+        st_size: u64,
+    ) -> Self {
+        let binding: u8 = symbol_binding.into();
+        let kind: u8 = symbol_type.into();
+        Self {
+            st_name: 0,
+            st_info: (binding << 4) | (kind & 0xf),
+            st_other: 0,
+            st_shndx,
+            st_value,
+            st_size,
+            symbol_type,
+            symbol_binding,
+            name: name.to_string(),
+        }
+    }
+    /*
+     * changes the binding in st_info as well
+     */
+    pub fn set_binding(&mut self, symbol_binding: SymbolBinding) {
+        let binding: u8 = symbol_binding.into();
+        self.st_info = (binding << 4) | (self.st_info & 0xf);
+        self.symbol_binding = symbol_binding;
+    }
+    pub fn visibility(&self) -> SymbolVisibility {
+        self.st_other.into()
+    }
+    pub fn section_index(&self) -> SectionIndex {
+        self.st_shndx.into()
+    }
+    pub fn is_defined(&self) -> bool {
+        self.section_index() != SectionIndex::SHN_UNDEF
+    }
+    /*
+     * the raw name when demangling is disabled or the name is not mangled
+     */
+    pub fn demangled_name(&self) -> String {
+        demangle(&self.name).unwrap_or_else(|| self.name.clone())
+    }
     pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
         let (remaining, st_name) = number::complete::le_u32(raw)?;
         let (remaining, st_info) = number::complete::le_u8(remaining)?;
@@ -123,13 +253,34 @@ impl Elf64_Sym {
                 st_other,
                 st_shndx,
                 st_value,
-                symbol_type: SymbolType::from(st_info << 4),
+                symbol_type: SymbolType::from(st_info & 0xf),
                 symbol_binding: SymbolBinding::from(st_info >> 4),
+                name: String::new(),
             },
         ))
     }
 }
 
+/*
+ * rust legacy and v0 manglings are tried first, legacy rust names are also
+ * valid itanium names but carry a hash the c++ demangler would print
+ */
+#[cfg(feature = "demangle")]
+pub fn demangle(name: &str) -> Option<String> {
+    if let Ok(d) = rustc_demangle::try_demangle(name) {
+        return Some(format!("{:#}", d));
+    }
+    if !name.starts_with("_Z") {
+        return None;
+    }
+    cpp_demangle::Symbol::new(name).ok()?.demangle().ok()
+}
+
+#[cfg(not(feature = "demangle"))]
+pub fn demangle(_name: &str) -> Option<String> {
+    None
+}
+
 #[derive(Debug, Clone, Copy, Serialize)]
 pub struct Elf64_Rel {
     pub r_offset: u64, /* Address of reference */
@@ -162,7 +313,7 @@ impl Elf64_Rel {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
 #[repr(u32)]
 pub enum RelocationType {
     R_X86_64_NONE = 0,             // none none
@@ -215,6 +366,59 @@ pub enum RelocationType {
     UNSPECIFIED(u32),
 }
 
+impl Into<u32> for RelocationType {
+    fn into(self) -> u32 {
+        match self {
+            Self::R_X86_64_NONE => 0,
+            Self::R_X86_64_64 => 1,
+            Self::R_X86_64_PC32 => 2,
+            Self::R_X86_64_GOT32 => 3,
+            Self::R_X86_64_PLT32 => 4,
+            Self::R_X86_64_COPY => 5,
+            Self::R_X86_64_GLOB_DAT => 6,
+            Self::R_X86_64_JUMP_SLOT => 7,
+            Self::R_X86_64_RELATIVE => 8,
+            Self::R_X86_64_GOTPCREL => 9,
+            Self::R_X86_64_32 => 10,
+            Self::R_X86_64_32S => 11,
+            Self::R_X86_64_16 => 12,
+            Self::R_X86_64_PC16 => 13,
+            Self::R_X86_64_8 => 14,
+            Self::R_X86_64_PC8 => 15,
+            Self::R_X86_64_DTPMOD64 => 16,
+            Self::R_X86_64_DTPOFF64 => 17,
+            Self::R_X86_64_TPOFF64 => 18,
+            Self::R_X86_64_TLSGD => 19,
+            Self::R_X86_64_TLSLD => 20,
+            Self::R_X86_64_DTPOFF32 => 21,
+            Self::R_X86_64_GOTTPOFF => 22,
+            Self::R_X86_64_TPOFF32 => 23,
+            Self::R_X86_64_PC64 => 24,
+            Self::R_X86_64_GOTOFF64 => 25,
+            Self::R_X86_64_GOTPC32 => 26,
+            Self::R_X86_64_SIZE32 => 32,
+            Self::R_X86_64_SIZE64 => 33,
+            Self::R_X86_64_GOTPC32_TLSDESC => 34,
+            Self::R_X86_64_TLSDESC_CALL => 35,
+            Self::R_X86_64_TLSDESC => 36,
+            Self::R_X86_64_IRELATIVE => 37,
+            Self::R_X86_64_RELATIVE64 => 38,
+            Self::R_X86_64_GOTPCRELX => 41,
+            Self::R_X86_64_REX_GOTPCRELX => 42,
+            Self::R_X86_64_CODE_4_GOTPCRELX => 43,
+            Self::R_X86_64_CODE_4_GOTTPOFF => 44,
+            Self::R_X86_64_CODE_4_GOTPC32_TLSDESC => 45,
+            Self::R_X86_64_CODE_5_GOTPCRELX => 46,
+            Self::R_X86_64_CODE_5_GOTTPOFF => 47,
+            Self::R_X86_64_CODE_5_GOTPC32_TLSDESC => 48,
+            Self::R_X86_64_CODE_6_GOTPCRELX => 49,
+            Self::R_X86_64_CODE_6_GOTTPOFF => 50,
+            Self::R_X86_64_CODE_6_GOTPC32_TLSDESC => 51,
+            Self::UNSPECIFIED(v) => v,
+        }
+    }
+}
+
 impl From<u32> for RelocationType {
     fn from(value: u32) -> Self {
         match value {
@@ -268,7 +472,103 @@ impl From<u32> for RelocationType {
     }
 }
 
-#[derive(Debug, Serialize)]
+impl RelocationType {
+    /*
+     * bytes written at the place, None for types that do not patch memory
+     * or are not handled
+     */
+    pub fn field_size(&self) -> Option<usize> {
+        match self {
+            Self::R_X86_64_64
+            | Self::R_X86_64_GLOB_DAT
+            | Self::R_X86_64_JUMP_SLOT
+            | Self::R_X86_64_RELATIVE
+            | Self::R_X86_64_IRELATIVE
+            | Self::R_X86_64_RELATIVE64
+            | Self::R_X86_64_PC64
+            | Self::R_X86_64_GOTOFF64
+            | Self::R_X86_64_SIZE64
+            | Self::R_X86_64_DTPMOD64
+            | Self::R_X86_64_DTPOFF64
+            | Self::R_X86_64_TPOFF64 => Some(8),
+            Self::R_X86_64_PC32
+            | Self::R_X86_64_GOT32
+            | Self::R_X86_64_PLT32
+            | Self::R_X86_64_GOTPCREL
+            | Self::R_X86_64_32
+            | Self::R_X86_64_32S
+            | Self::R_X86_64_DTPOFF32
+            | Self::R_X86_64_GOTTPOFF
+            | Self::R_X86_64_TPOFF32
+            | Self::R_X86_64_GOTPC32
+            | Self::R_X86_64_SIZE32
+            | Self::R_X86_64_GOTPCRELX
+            | Self::R_X86_64_REX_GOTPCRELX => Some(4),
+            Self::R_X86_64_16 | Self::R_X86_64_PC16 => Some(2),
+            Self::R_X86_64_8 | Self::R_X86_64_PC8 => Some(1),
+            _ => None,
+        }
+    }
+    /*
+     * value of the relocations computed from S (symbol), A (addend), P
+     * (place) and B (base) only, the ones needing a GOT, PLT or TLS layout
+     * return None
+     */
+    pub fn compute(&self, s: u64, a: u64, p: u64, b: u64) -> Option<u64> {
+        match self {
+            Self::R_X86_64_64 | Self::R_X86_64_32 | Self::R_X86_64_32S => Some(s.wrapping_add(a)),
+            Self::R_X86_64_16 | Self::R_X86_64_8 => Some(s.wrapping_add(a)),
+            Self::R_X86_64_PC32
+            | Self::R_X86_64_PC64
+            | Self::R_X86_64_PC16
+            | Self::R_X86_64_PC8 => Some(s.wrapping_add(a).wrapping_sub(p)),
+            Self::R_X86_64_GLOB_DAT | Self::R_X86_64_JUMP_SLOT => Some(s),
+            Self::R_X86_64_RELATIVE | Self::R_X86_64_RELATIVE64 | Self::R_X86_64_IRELATIVE => {
+                Some(b.wrapping_add(a))
+            }
+            _ => None,
+        }
+    }
+    /*
+     * the computed value fits in the field, 32S is sign extended and the
+     * pc relative ones are signed
+     */
+    pub fn fits(&self, value: u64) -> bool {
+        match self {
+            Self::R_X86_64_32 => value <= u32::MAX as u64,
+            Self::R_X86_64_32S | Self::R_X86_64_PC32 | Self::R_X86_64_PLT32 => {
+                value as i64 >= i32::MIN as i64 && value as i64 <= i32::MAX as i64
+            }
+            Self::R_X86_64_GOTPCREL | Self::R_X86_64_GOTPCRELX | Self::R_X86_64_REX_GOTPCRELX => {
+                value as i64 >= i32::MIN as i64 && value as i64 <= i32::MAX as i64
+            }
+            _ => match self.field_size() {
+                Some(8) | None => true,
+                Some(size) => {
+                    let bits = size * 8;
+                    let signed = value as i64;
+                    signed >= -(1i64 << (bits - 1)) && signed < (1i64 << bits)
+                }
+            },
+        }
+    }
+    pub fn is_tls(&self) -> bool {
+        matches!(
+            self,
+            Self::R_X86_64_DTPMOD64
+                | Self::R_X86_64_DTPOFF64
+                | Self::R_X86_64_TPOFF64
+                | Self::R_X86_64_TLSGD
+                | Self::R_X86_64_TLSLD
+                | Self::R_X86_64_DTPOFF32
+                | Self::R_X86_64_GOTTPOFF
+                | Self::R_X86_64_TPOFF32
+                | Self::R_X86_64_TLSDESC
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Elf64_Rela {
     pub r_offset: u64, /* Address of reference */
     pub r_info: u64,   /* Symbol index and type of relocation */
@@ -298,6 +598,21 @@ impl Into<Vec<u8>> for &Elf64_Rela {
 }
 
 impl Elf64_Rela {
+    pub fn new(
+        r_offset: u64,
+        relocation_type: RelocationType,
+        symbol_index: u32,
+        r_addend: u64,
+    ) -> Self {
+        let t: u32 = relocation_type.into();
+        Self {
+            r_offset,
+            r_info: ((symbol_index as u64) << 32) | t as u64,
+            r_addend,
+            relocation_type,
+            symbol_index,
+        }
+    }
     pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
         let (remaining, r_offset) = number::complete::le_u64(raw)?;
         let (remaining, r_info) = number::complete::le_u64(remaining)?;
@@ -0,0 +1,200 @@
+#![allow(non_camel_case_types)]
+
+use super::section::StringSection;
+use nom::number;
+use nom::IResult;
+use serde::Serialize;
+
+pub const VERSYM_HIDDEN: u16 = 0x8000;
+pub const VER_NDX_LOCAL: u16 = 0;
+pub const VER_NDX_GLOBAL: u16 = 1;
+pub const VER_FLG_BASE: u16 = 1;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Elf64_Verdef {
+    pub vd_version: u16, /* Version revision, 1 */
+    pub vd_flags: u16,   /* VER_FLG_BASE for the file's own version */
+    pub vd_ndx: u16,     /* Version index used in .gnu.version */
+    pub vd_cnt: u16,     /* Number of Elf64_Verdaux entries */
+    pub vd_hash: u32,    /* Elf hash of the version name */
+    pub vd_aux: u32,     /* Offset of the first Elf64_Verdaux from this entry */
+    pub vd_next: u32,    /* Offset of the next Elf64_Verdef from this entry */
+}
+
+impl Elf64_Verdef {
+    pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
+        let (remaining, vd_version) = number::complete::le_u16(raw)?;
+        let (remaining, vd_flags) = number::complete::le_u16(remaining)?;
+        let (remaining, vd_ndx) = number::complete::le_u16(remaining)?;
+        let (remaining, vd_cnt) = number::complete::le_u16(remaining)?;
+        let (remaining, vd_hash) = number::complete::le_u32(remaining)?;
+        let (remaining, vd_aux) = number::complete::le_u32(remaining)?;
+        let (remaining, vd_next) = number::complete::le_u32(remaining)?;
+        Ok((
+            remaining,
+            Self {
+                vd_version,
+                vd_flags,
+                vd_ndx,
+                vd_cnt,
+                vd_hash,
+                vd_aux,
+                vd_next,
+            },
+        ))
+    }
+}
+
+impl Into<Vec<u8>> for &Elf64_Verdef {
+    fn into(self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::new();
+        out.extend(self.vd_version.to_le_bytes());
+        out.extend(self.vd_flags.to_le_bytes());
+        out.extend(self.vd_ndx.to_le_bytes());
+        out.extend(self.vd_cnt.to_le_bytes());
+        out.extend(self.vd_hash.to_le_bytes());
+        out.extend(self.vd_aux.to_le_bytes());
+        out.extend(self.vd_next.to_le_bytes());
+        out
+    }
+}
+
+impl Into<Vec<u8>> for Elf64_Verdef {
+    fn into(self) -> Vec<u8> {
+        (&self).into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Elf64_Verneed {
+    pub vn_version: u16, /* Version revision, 1 */
+    pub vn_cnt: u16,     /* Number of Elf64_Vernaux entries */
+    pub vn_file: u32,    /* Name of the needed library in the linked string table */
+    pub vn_aux: u32,     /* Offset of the first Elf64_Vernaux from this entry */
+    pub vn_next: u32,    /* Offset of the next Elf64_Verneed from this entry */
+}
+
+impl Elf64_Verneed {
+    pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
+        let (remaining, vn_version) = number::complete::le_u16(raw)?;
+        let (remaining, vn_cnt) = number::complete::le_u16(remaining)?;
+        let (remaining, vn_file) = number::complete::le_u32(remaining)?;
+        let (remaining, vn_aux) = number::complete::le_u32(remaining)?;
+        let (remaining, vn_next) = number::complete::le_u32(remaining)?;
+        Ok((
+            remaining,
+            Self {
+                vn_version,
+                vn_cnt,
+                vn_file,
+                vn_aux,
+                vn_next,
+            },
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Elf64_Vernaux {
+    pub vna_hash: u32,  /* Elf hash of the version name */
+    pub vna_flags: u16, /* VER_FLG_WEAK */
+    pub vna_other: u16, /* Version index used in .gnu.version */
+    pub vna_name: u32,  /* Version name in the linked string table */
+    pub vna_next: u32,  /* Offset of the next Elf64_Vernaux from this entry */
+}
+
+impl Elf64_Vernaux {
+    pub fn parse(raw: &[u8]) -> IResult<&[u8], Self> {
+        let (remaining, vna_hash) = number::complete::le_u32(raw)?;
+        let (remaining, vna_flags) = number::complete::le_u16(remaining)?;
+        let (remaining, vna_other) = number::complete::le_u16(remaining)?;
+        let (remaining, vna_name) = number::complete::le_u32(remaining)?;
+        let (remaining, vna_next) = number::complete::le_u32(remaining)?;
+        Ok((
+            remaining,
+            Self {
+                vna_hash,
+                vna_flags,
+                vna_other,
+                vna_name,
+                vna_next,
+            },
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SymbolVersion {
+    pub name: String,
+    pub hidden: bool,         /* Set for foo@VER, clear for the default foo@@VER */
+    pub file: Option<String>, /* Library providing the version for needed versions */
+}
+
+/*
+ * version names by version index, built from .gnu.version_d and
+ * .gnu.version_r. both are linked lists with offsets relative to the
+ * current entry
+ */
+#[derive(Default, Debug, Serialize)]
+pub struct VersionNames {
+    pub names: Vec<(u16, String, Option<String>)>,
+}
+
+impl VersionNames {
+    pub fn add_definitions(&mut self, raw: &[u8], strings: &StringSection) {
+        let mut offset = 0usize;
+        while let Some(Ok((_, def))) = raw.get(offset..).map(Elf64_Verdef::parse) {
+            let aux = offset + def.vd_aux as usize;
+            if let Some(Ok((_, name))) = raw.get(aux..).map(number::complete::le_u32::<_, ()>) {
+                if let Some(name) = strings.get(name as usize) {
+                    self.names.push((def.vd_ndx, name, None));
+                }
+            }
+            if def.vd_next == 0 {
+                break;
+            }
+            offset += def.vd_next as usize;
+        }
+    }
+    pub fn add_needs(&mut self, raw: &[u8], strings: &StringSection) {
+        let mut offset = 0usize;
+        while let Some(Ok((_, need))) = raw.get(offset..).map(Elf64_Verneed::parse) {
+            let file = strings.get(need.vn_file as usize);
+            let mut aux = offset + need.vn_aux as usize;
+            for _ in 0..need.vn_cnt {
+                let entry = match raw.get(aux..).map(Elf64_Vernaux::parse) {
+                    Some(Ok((_, e))) => e,
+                    _ => break,
+                };
+                if let Some(name) = strings.get(entry.vna_name as usize) {
+                    self.names.push((entry.vna_other, name, file.clone()));
+                }
+                if entry.vna_next == 0 {
+                    break;
+                }
+                aux += entry.vna_next as usize;
+            }
+            if need.vn_next == 0 {
+                break;
+            }
+            offset += need.vn_next as usize;
+        }
+    }
+    /*
+     * version of a .gnu.version entry, local and base versions have none
+     */
+    pub fn version(&self, versym: u16) -> Option<SymbolVersion> {
+        let index = versym & !VERSYM_HIDDEN;
+        if index == VER_NDX_LOCAL || index == VER_NDX_GLOBAL {
+            return None;
+        }
+        self.names
+            .iter()
+            .find(|(i, _, _)| *i == index)
+            .map(|(_, name, file)| SymbolVersion {
+                name: name.clone(),
+                hidden: versym & VERSYM_HIDDEN != 0,
+                file: file.clone(),
+            })
+    }
+}
@@ -0,0 +1,294 @@
+use super::builder::null_header;
+use super::elf64::{resolve_symbol_names, Elf64};
+use super::error::ElfError;
+use super::section::section::{Section, SectionData};
+use super::section::section_header::Elf64Shdr;
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+use super::section::string_table::StringTableBuilder;
+use super::segment_map::section_in_segment;
+use enumflags2::BitFlags;
+
+pub(crate) const SHN_LORESERVE: u16 = 0xff00;
+
+/*
+ * sh_info holds a section index for relocation sections and for sections
+ * with SHF_INFO_LINK
+ */
+fn info_is_section(header: &Elf64Shdr) -> bool {
+    matches!(header.sh_type, ShType::SHT_REL | ShType::SHT_RELA)
+        || header.sh_flags.contains(SHFlags::SHF_INFO_LINK)
+}
+
+impl Elf64 {
+    /*
+     * index changes of a section in every place that holds one, map gives
+     * the new index of every old one or None for removed sections.
+     * symbols defined in a removed section become undefined
+     */
+    fn reindex_sections(&mut self, map: &[Option<u32>]) {
+        let new = |index: u32| map.get(index as usize).copied().flatten();
+        for section in self.sections.iter_mut() {
+            let header = &mut section.header;
+            if header.sh_link != 0 {
+                header.sh_link = new(header.sh_link).unwrap_or(0);
+            }
+            if header.sh_info != 0 && info_is_section(header) {
+                header.sh_info = new(header.sh_info).unwrap_or(0);
+            }
+            match &mut section.data {
+                SectionData::Symbol(symbols) => {
+                    for symbol in symbols.symbols.iter_mut() {
+                        if symbol.st_shndx != 0 && symbol.st_shndx < SHN_LORESERVE {
+                            symbol.st_shndx = new(symbol.st_shndx as u32).unwrap_or(0) as u16;
+                        }
+                    }
+                }
+                SectionData::Group(group) => {
+                    group.members = group.members.iter().filter_map(|m| new(*m)).collect();
+                    header.sh_size = 4 * (group.members.len() as u64 + 1);
+                }
+                _ => (),
+            }
+        }
+        self.header.e_shstrndx = new(self.header.e_shstrndx as u32).unwrap_or(0) as u16;
+    }
+    /*
+     * writes the names of all sections to .shstrtab again
+     */
+    fn update_section_names(&mut self) {
+        let shstrndx = self.header.e_shstrndx as usize;
+        if shstrndx == 0 || shstrndx >= self.sections.len() {
+            return;
+        }
+        let mut table = StringTableBuilder::new();
+        for section in &self.sections {
+            table.add(&section.name);
+        }
+        table.finalize();
+        for section in self.sections.iter_mut() {
+            section.header.sh_name = table
+                .offset(&section.name)
+                .unwrap_or(section.header.sh_name);
+        }
+        let shstrtab = &mut self.sections[shstrndx];
+        shstrtab.header.sh_size = table.len() as u64;
+        shstrtab.data = SectionData::String(table.data().into());
+    }
+    /*
+     * name of a symbol defined in section that a relocation section that
+     * is kept refers to, objcopy refuses to remove the section then
+     */
+    fn relocated_symbol_in(&self, section: usize, removed: &[bool]) -> Option<String> {
+        for (i, s) in self.sections.iter().enumerate() {
+            let indexes: Vec<u32> = match &s.data {
+                _ if removed[i] => continue,
+                SectionData::Rela(r) => r.rela_entries.iter().map(|r| r.symbol_index).collect(),
+                SectionData::Rel(r) => r
+                    .rel_entries
+                    .iter()
+                    .map(|r| (r.r_info >> 32) as u32)
+                    .collect(),
+                _ => continue,
+            };
+            let symbols = match self.linked_symbols(s.header.sh_link as usize) {
+                Some(symbols) => symbols,
+                None => continue,
+            };
+            let symbol = indexes
+                .iter()
+                .filter(|index| **index != 0)
+                .filter_map(|index| symbols.get(*index as usize))
+                .find(|symbol| symbol.st_shndx as usize == section);
+            if let Some(symbol) = symbol {
+                return Some(match symbol.name.is_empty() {
+                    true => self.sections[section].name.clone(),
+                    false => symbol.name.clone(),
+                });
+            }
+        }
+        None
+    }
+    /*
+     * drops every section marked in removed and moves the others down
+     */
+    pub(crate) fn remove_sections(&mut self, removed: &[bool]) {
+        /* members of a removed group are no longer in a group */
+        let ungrouped: Vec<u32> = self
+            .sections
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| removed[*i])
+            .filter_map(|(_, s)| match &s.data {
+                SectionData::Group(group) => Some(group.members.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        for member in ungrouped {
+            if let Some(section) = self.sections.get_mut(member as usize) {
+                section.header.sh_flags.remove(SHFlags::SHF_GROUP);
+            }
+        }
+        let mut next = 0;
+        let map: Vec<Option<u32>> = removed
+            .iter()
+            .map(|r| match r {
+                true => None,
+                false => {
+                    next += 1;
+                    Some(next - 1)
+                }
+            })
+            .collect();
+        self.reindex_sections(&map);
+        let mut removed = removed.iter();
+        self.sections.retain(|_| !removed.next().unwrap());
+        self.header.e_shnum = self.sections.len() as u16;
+        self.update_section_names();
+    }
+    fn editable_section(&self, index: usize) -> Result<&Section, ElfError> {
+        match self.sections.get(index) {
+            Some(section) if index != 0 => Ok(section),
+            _ => Err(ElfError::InvalidSection(index)),
+        }
+    }
+    /*
+     * like objcopy --add-section: the section goes at the end with an
+     * alignment of 1 and is laid out by the writer. files without section
+     * headers get the null section and .shstrtab first
+     */
+    pub fn add_section(
+        &mut self,
+        name: &str,
+        sh_type: ShType,
+        flags: BitFlags<SHFlags>,
+        data: Vec<u8>,
+    ) -> Result<usize, ElfError> {
+        if self.sections.is_empty() {
+            self.sections.push(Section {
+                header: null_header(),
+                name: String::new(),
+                data: SectionData::Unknown(Vec::new().into()),
+            });
+        }
+        if self.header.e_shstrndx == 0 {
+            let header = Elf64Shdr {
+                sh_type: ShType::SHT_STRTAB,
+                sh_addralign: 1,
+                ..null_header()
+            };
+            self.sections.push(Section {
+                data: SectionData::parse(&header, Some(&[0])),
+                header,
+                name: ".shstrtab".to_string(),
+            });
+            self.header.e_shstrndx = self.sections.len() as u16 - 1;
+        }
+        let header = Elf64Shdr {
+            sh_type,
+            sh_flags: flags,
+            sh_size: data.len() as u64,
+            sh_addralign: 1,
+            ..null_header()
+        };
+        self.sections.push(Section {
+            data: SectionData::parse(&header, Some(&data)),
+            header,
+            name: name.to_string(),
+        });
+        self.header.e_shnum = self.sections.len() as u16;
+        self.update_section_names();
+        Ok(self.sections.len() - 1)
+    }
+    /*
+     * like objcopy --remove-section, the relocation sections that apply
+     * to the section are removed with it. the other symbols defined in it
+     * become undefined
+     */
+    pub fn remove_section(&mut self, index: usize) -> Result<(), ElfError> {
+        self.editable_section(index)?;
+        if index == self.header.e_shstrndx as usize {
+            return Err(ElfError::InvalidSection(index));
+        }
+        let removed: Vec<bool> = self
+            .sections
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                i == index
+                    || (matches!(s.header.sh_type, ShType::SHT_REL | ShType::SHT_RELA)
+                        && s.header.sh_info as usize == index)
+            })
+            .collect();
+        if let Some(symbol) = self.relocated_symbol_in(index, &removed) {
+            return Err(ElfError::SymbolInRemovedSection(symbol));
+        }
+        self.remove_sections(&removed);
+        Ok(())
+    }
+    /*
+     * like objcopy --rename-section, .rel and .rela sections named after
+     * the section are renamed with it
+     */
+    pub fn rename_section(&mut self, index: usize, name: &str) -> Result<(), ElfError> {
+        let old = self.editable_section(index)?.name.clone();
+        for section in self.sections.iter_mut() {
+            let header = &section.header;
+            if matches!(header.sh_type, ShType::SHT_REL | ShType::SHT_RELA)
+                && header.sh_info as usize == index
+            {
+                for prefix in [".rela", ".rel"] {
+                    if section.name.strip_prefix(prefix) == Some(old.as_str()) {
+                        section.name = format!("{}{}", prefix, name);
+                        break;
+                    }
+                }
+            }
+        }
+        self.sections[index].name = name.to_string();
+        self.update_section_names();
+        Ok(())
+    }
+    /*
+     * like objcopy --update-section. sections in a segment keep their
+     * place in the file so their size cannot change
+     */
+    pub fn update_section(&mut self, index: usize, data: Vec<u8>) -> Result<(), ElfError> {
+        let section = self.editable_section(index)?;
+        if section.header.sh_type == ShType::SHT_NOBITS {
+            return Err(ElfError::InvalidSection(index));
+        }
+        let size = section.header.sh_size;
+        if data.len() as u64 != size && self.programs.iter().any(|p| section_in_segment(section, p))
+        {
+            return Err(ElfError::SizeMismatch {
+                expected: size,
+                actual: data.len() as u64,
+            });
+        }
+        let section = &mut self.sections[index];
+        section.header.sh_size = data.len() as u64;
+        section.data = SectionData::parse(&section.header, Some(&data));
+        /* symbol names come from the string table */
+        for i in 0..self.sections.len() {
+            let header = &self.sections[i].header;
+            if i == index || header.sh_link as usize == index {
+                resolve_symbol_names(&mut self.sections, i);
+            }
+        }
+        Ok(())
+    }
+    /*
+     * like objcopy --set-section-flags, the flags replace the old ones
+     */
+    pub fn set_section_flags(
+        &mut self,
+        index: usize,
+        flags: BitFlags<SHFlags>,
+    ) -> Result<(), ElfError> {
+        self.editable_section(index)?;
+        self.sections[index].header.sh_flags = flags;
+        Ok(())
+    }
+}
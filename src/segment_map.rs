@@ -0,0 +1,152 @@
+use super::elf64::Elf64;
+use super::program::p_type::PType;
+use super::program::program::Program;
+use super::section::section::Section;
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+use std::fmt::Write;
+
+const PT_GNU_SFRAME: u32 = 0x6474e554;
+const PT_GNU_MBIND_LO: u32 = 0x6474e555;
+const PT_GNU_MBIND_HI: u32 = 0x6474f554;
+
+/*
+ * .tbss only takes space in the PT_TLS segment, in every other segment it
+ * has a size of zero
+ */
+fn section_size(section: &Section, program: &Program) -> u64 {
+    let tls = section.header.sh_flags.contains(SHFlags::SHF_TLS);
+    match tls
+        && section.header.sh_type == ShType::SHT_NOBITS
+        && program.header.p_type != PType::PT_TLS
+    {
+        true => 0,
+        false => section.header.sh_size,
+    }
+}
+
+/*
+ * ELF_SECTION_IN_SEGMENT_STRICT of binutils, the rule readelf uses for the
+ * section to segment mapping
+ */
+pub fn section_in_segment(section: &Section, program: &Program) -> bool {
+    let s = &section.header;
+    let p = &program.header;
+    let tls = s.sh_flags.contains(SHFlags::SHF_TLS);
+    let alloc = s.sh_flags.contains(SHFlags::SHF_ALLOC);
+    let nobits = s.sh_type == ShType::SHT_NOBITS;
+    let p_type: u32 = p.p_type.into();
+    let size = section_size(section, program);
+    /* only PT_LOAD, PT_GNU_RELRO and PT_TLS hold TLS sections, PT_TLS only TLS sections */
+    let tls_ok = match tls {
+        true => matches!(
+            p.p_type,
+            PType::PT_TLS | PType::PT_GNU_RELRO | PType::PT_LOAD
+        ),
+        false => !matches!(p.p_type, PType::PT_TLS | PType::PT_PHDR),
+    };
+    /* loadable segments only hold SHF_ALLOC sections */
+    let alloc_ok = alloc
+        || !(matches!(
+            p.p_type,
+            PType::PT_LOAD
+                | PType::PT_DYNAMIC
+                | PType::PT_GNU_EH_FRAME
+                | PType::PT_GNU_STACK
+                | PType::PT_GNU_RELRO
+        ) || p_type == PT_GNU_SFRAME
+            || (PT_GNU_MBIND_LO..=PT_GNU_MBIND_HI).contains(&p_type));
+    let offset_ok = nobits
+        || (s.sh_offset >= p.p_offset
+            && s.sh_offset - p.p_offset <= p.p_filesz.wrapping_sub(1)
+            && (s.sh_offset - p.p_offset)
+                .checked_add(size)
+                .is_some_and(|end| end <= p.p_filesz));
+    let vma_ok = !alloc
+        || (s.sh_addr >= p.p_vaddr
+            && s.sh_addr - p.p_vaddr <= p.p_memsz.wrapping_sub(1)
+            && (s.sh_addr - p.p_vaddr)
+                .checked_add(size)
+                .is_some_and(|end| end <= p.p_memsz));
+    /* no empty sections at the start or the end of PT_DYNAMIC and PT_NOTE */
+    let empty_ok = !matches!(p.p_type, PType::PT_DYNAMIC | PType::PT_NOTE)
+        || s.sh_size != 0
+        || p.p_memsz == 0
+        || ((nobits || (s.sh_offset > p.p_offset && s.sh_offset - p.p_offset < p.p_filesz))
+            && (!alloc || (s.sh_addr > p.p_vaddr && s.sh_addr - p.p_vaddr < p.p_memsz)));
+    tls_ok && alloc_ok && offset_ok && vma_ok && empty_ok
+}
+
+impl Elf64 {
+    pub fn section_by_name(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+    pub fn section_index(&self, name: &str) -> Option<usize> {
+        self.sections.iter().position(|s| s.name == name)
+    }
+    pub fn sections_of_type(&self, sh_type: ShType) -> impl Iterator<Item = &Section> {
+        self.sections
+            .iter()
+            .filter(move |s| s.header.sh_type == sh_type)
+    }
+    /*
+     * the PT_LOAD segment holding the section, or the first other segment
+     * holding it for sections that are not loaded
+     */
+    pub fn segment_for_section(&self, index: usize) -> Option<&Program> {
+        let section = self.sections.get(index)?;
+        let mut segments = self
+            .programs
+            .iter()
+            .filter(|p| section_in_segment(section, p));
+        let first = segments.next()?;
+        match first.header.p_type {
+            PType::PT_LOAD => Some(first),
+            _ => segments
+                .find(|p| p.header.p_type == PType::PT_LOAD)
+                .or(Some(first)),
+        }
+    }
+    /*
+     * section indexes in each segment, in program header order. section 0
+     * and .tbss outside of PT_TLS are left out like readelf does
+     */
+    pub fn section_to_segment_mapping(&self) -> Vec<Vec<usize>> {
+        self.programs
+            .iter()
+            .map(|program| {
+                self.sections
+                    .iter()
+                    .enumerate()
+                    .skip(1)
+                    .filter(|(_, s)| {
+                        !(s.header.sh_flags.contains(SHFlags::SHF_TLS)
+                            && s.header.sh_type == ShType::SHT_NOBITS
+                            && program.header.p_type != PType::PT_TLS)
+                    })
+                    .filter(|(_, s)| section_in_segment(s, program))
+                    .map(|(i, _)| i)
+                    .collect()
+            })
+            .collect()
+    }
+    /*
+     * the mapping in the layout of readelf -l, which prints nothing for
+     * files without a section name table
+     */
+    pub fn section_to_segment_report(&self) -> String {
+        let shstrndx = self.header.e_shstrndx as usize;
+        if shstrndx == 0 || shstrndx >= self.sections.len() {
+            return String::new();
+        }
+        let mut out = String::from(" Section to Segment mapping:\n  Segment Sections...\n");
+        for (i, sections) in self.section_to_segment_mapping().iter().enumerate() {
+            write!(out, "   {:02}     ", i).unwrap();
+            for section in sections {
+                write!(out, "{} ", self.sections[*section].name).unwrap();
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
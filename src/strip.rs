@@ -0,0 +1,247 @@
+use super::debuginfo::debuglink::DebugLink;
+use super::elf64::Elf64;
+use super::error::ElfError;
+use super::header::e_type::EType;
+use super::section::section::SectionData;
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+use super::section::symbol::{Elf64_Sym, SymbolBinding, SymbolType};
+use super::section_edit::SHN_LORESERVE;
+use enumflags2::BitFlags;
+use std::path::Path;
+
+/*
+ * the sections bfd marks SEC_DEBUGGING
+ */
+fn is_debug_section(name: &str) -> bool {
+    name == ".line"
+        || name == ".gdb_index"
+        || [
+            ".debug",
+            ".zdebug",
+            ".stab",
+            ".gnu.debuglto_",
+            ".gnu.linkonce.wi.",
+        ]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+impl Elf64 {
+    /*
+     * symbols of the table that relocations or groups refer to
+     */
+    fn referenced_symbols(&self, table: usize, removed: &[bool]) -> Vec<bool> {
+        let count = self.linked_symbols(table).map_or(0, |s| s.len());
+        let mut referenced = vec![false; count];
+        let mut mark = |index: u32| {
+            if let Some(r) = referenced.get_mut(index as usize) {
+                *r = true;
+            }
+        };
+        for (i, section) in self.sections.iter().enumerate() {
+            if removed[i] || section.header.sh_link as usize != table {
+                continue;
+            }
+            match &section.data {
+                SectionData::Rela(r) => r.rela_entries.iter().for_each(|r| mark(r.symbol_index)),
+                SectionData::Rel(r) => r
+                    .rel_entries
+                    .iter()
+                    .for_each(|r| mark((r.r_info >> 32) as u32)),
+                SectionData::Group(_) => mark(section.header.sh_info),
+                _ => (),
+            }
+        }
+        referenced
+    }
+    /*
+     * keeps the symbols of the table marked in keep
+     */
+    pub(crate) fn remove_symbols(&mut self, table: usize, keep: &[bool]) {
+        let order: Vec<usize> = (0..keep.len()).filter(|i| keep[*i]).collect();
+        self.reorder_symbols(table, &order);
+    }
+    /*
+     * removes the sections marked in removed together with what depends on
+     * them: their relocations, groups left empty, and the relocations,
+     * groups and string table of a removed symbol table. symbols defined in
+     * removed sections go away, of the others the ones relocations and
+     * groups need are kept and keep_symbol decides about the rest. a symbol
+     * table left empty is removed from files that are not relocatable
+     */
+    fn strip_sections(&mut self, mut removed: Vec<bool>, keep_symbol: impl Fn(&Elf64_Sym) -> bool) {
+        removed[0] = false;
+        for (i, section) in self.sections.iter().enumerate() {
+            let header = &section.header;
+            removed[i] |= matches!(header.sh_type, ShType::SHT_REL | ShType::SHT_RELA)
+                && header.sh_info != 0
+                && removed[header.sh_info as usize];
+        }
+        for (i, section) in self.sections.iter().enumerate() {
+            if let SectionData::Group(group) = &section.data {
+                removed[i] |=
+                    !group.members.is_empty() && group.members.iter().all(|m| removed[*m as usize]);
+            }
+        }
+        let table = self.symtab_index();
+        let mut keep: Vec<bool> = Vec::new();
+        if let Some(table) = table.filter(|t| !removed[*t]) {
+            let referenced = self.referenced_symbols(table, &removed);
+            let symbols = self.linked_symbols(table).unwrap();
+            keep = symbols
+                .iter()
+                .enumerate()
+                .map(|(i, s)| {
+                    let in_removed = s.st_shndx != 0
+                        && s.st_shndx < SHN_LORESERVE
+                        && removed[s.st_shndx as usize];
+                    i == 0 || referenced[i] || (!in_removed && keep_symbol(s))
+                })
+                .collect();
+            let linked = self
+                .sections
+                .iter()
+                .enumerate()
+                .any(|(i, s)| !removed[i] && s.header.sh_link as usize == table);
+            if self.header.e_type != EType::ET_REL
+                && !linked
+                && keep.iter().filter(|k| **k).count() <= 1
+            {
+                removed[table] = true;
+            }
+        }
+        for i in 0..self.sections.len() {
+            let header = &self.sections[i].header;
+            let link = header.sh_link as usize;
+            removed[i] |= matches!(
+                header.sh_type,
+                ShType::SHT_REL | ShType::SHT_RELA | ShType::SHT_GROUP
+            ) && link != 0
+                && removed[link];
+        }
+        for (i, section) in self.sections.iter().enumerate() {
+            if section.header.sh_type != ShType::SHT_STRTAB || i == self.header.e_shstrndx as usize
+            {
+                continue;
+            }
+            let links = self
+                .sections
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.header.sh_link as usize == i);
+            let (mut dropped, mut kept) = (false, false);
+            for (j, _) in links {
+                dropped |= removed[j];
+                kept |= !removed[j];
+            }
+            removed[i] |= dropped && !kept;
+        }
+        if let Some(table) = table.filter(|t| !removed[*t]) {
+            self.remove_symbols(table, &keep);
+        }
+        self.remove_sections(&removed);
+    }
+    fn debug_sections(&self) -> Vec<bool> {
+        self.sections
+            .iter()
+            .map(|s| is_debug_section(&s.name))
+            .collect()
+    }
+    /*
+     * like strip --strip-debug: the debug sections and the symbols of
+     * source files are removed
+     */
+    pub fn strip_debug(&mut self) {
+        let removed = self.debug_sections();
+        self.strip_sections(removed, |s| s.symbol_type != SymbolType::STT_FILE);
+    }
+    /*
+     * like strip --strip-unneeded: on top of --strip-debug, relocatable
+     * files only keep the global symbols and the symbols relocations and
+     * groups need, other files lose .symtab unless relocations use it
+     */
+    pub fn strip_unneeded(&mut self) {
+        let removed = self.debug_sections();
+        let relocatable = self.header.e_type == EType::ET_REL;
+        self.strip_sections(removed, |s| {
+            relocatable && s.symbol_binding != SymbolBinding::STB_LOCAL
+        });
+    }
+    /*
+     * like strip --strip-all: the debug sections and .symtab are removed.
+     * relocatable files keep their relocations and groups, so .symtab
+     * stays with only the symbols they need
+     */
+    pub fn strip_all(&mut self) {
+        let mut removed = self.debug_sections();
+        if self.header.e_type != EType::ET_REL {
+            if let Some(table) = self.symtab_index() {
+                removed[table] = true;
+            }
+        }
+        self.strip_sections(removed, |_| false);
+    }
+    /*
+     * like objcopy --only-keep-debug: allocated sections other than notes
+     * become SHT_NOBITS placeholders that keep their address and size, and
+     * segments only keep the file contents that are left: the file header,
+     * the program headers and the notes
+     */
+    pub fn only_keep_debug(&mut self) {
+        let table_end =
+            self.header.e_phoff + self.header.e_phentsize as u64 * self.programs.len() as u64;
+        for section in self.sections.iter_mut() {
+            let header = &mut section.header;
+            if header.sh_flags.contains(SHFlags::SHF_ALLOC)
+                && !matches!(header.sh_type, ShType::SHT_NOTE | ShType::SHT_NOBITS)
+            {
+                header.sh_type = ShType::SHT_NOBITS;
+                section.data = SectionData::parse(header, None);
+            }
+        }
+        for program in self.programs.iter_mut() {
+            let header = &mut program.header;
+            let start = header.p_offset;
+            let end = start + header.p_filesz;
+            let mut filesz = match start < table_end {
+                true => table_end.min(end) - start,
+                false => 0,
+            };
+            for section in &self.sections {
+                let h = &section.header;
+                if h.sh_type != ShType::SHT_NOBITS
+                    && h.sh_flags.contains(SHFlags::SHF_ALLOC)
+                    && h.sh_offset >= start
+                    && h.sh_offset + h.sh_size <= end
+                {
+                    filesz = filesz.max(h.sh_offset + h.sh_size - start);
+                }
+            }
+            header.p_filesz = filesz;
+            program.data.inner.truncate(filesz as usize);
+        }
+    }
+    /*
+     * adds .gnu_debuglink naming the file the debug information was moved
+     * to, like objcopy only the file name without its directory is kept
+     */
+    pub fn add_gnu_debuglink(&mut self, path: &str, debug_file: &[u8]) -> Result<usize, ElfError> {
+        let filename = Path::new(path)
+            .file_name()
+            .map_or(path.to_string(), |f| f.to_string_lossy().into_owned());
+        let data: Vec<u8> = (&DebugLink::new(&filename, debug_file)).into();
+        if let Some(index) = self.section_index(".gnu_debuglink") {
+            self.update_section(index, data)?;
+            return Ok(index);
+        }
+        let index = self.add_section(
+            ".gnu_debuglink",
+            ShType::SHT_PROGBITS,
+            BitFlags::empty(),
+            data,
+        )?;
+        self.sections[index].header.sh_addralign = 4;
+        Ok(index)
+    }
+}
@@ -0,0 +1,202 @@
+use super::elf64::Elf64;
+use super::section::section::SectionData;
+use super::section::sh_type::ShType;
+use super::section::string_table::StringTableBuilder;
+use super::section::symbol::{Elf64_Sym, SymbolBinding, SymbolType};
+
+impl Elf64 {
+    pub(crate) fn symtab_index(&self) -> Option<usize> {
+        self.sections
+            .iter()
+            .position(|s| s.header.sh_type == ShType::SHT_SYMTAB)
+    }
+    /*
+     * puts the symbols of the table in the order of their old indexes in
+     * order, symbols left out are removed. relocations and group
+     * signatures follow their symbols. a string table that only this table
+     * uses is written again, a shared one gets the names that are missing
+     */
+    pub(crate) fn reorder_symbols(&mut self, table: usize, order: &[usize]) {
+        let count = self.linked_symbols(table).map_or(0, |s| s.len());
+        let mut map: Vec<Option<u32>> = vec![None; count];
+        for (new, old) in order.iter().enumerate() {
+            map[*old] = Some(new as u32);
+        }
+        let new = |index: u32| map.get(index as usize).copied().flatten().unwrap_or(0);
+        for section in self.sections.iter_mut() {
+            if section.header.sh_link as usize != table {
+                continue;
+            }
+            match &mut section.data {
+                SectionData::Rela(r) => {
+                    for rela in r.rela_entries.iter_mut() {
+                        rela.symbol_index = new(rela.symbol_index);
+                        rela.r_info =
+                            ((rela.symbol_index as u64) << 32) | (rela.r_info & 0xffffffff);
+                    }
+                }
+                SectionData::Rel(r) => {
+                    for rel in r.rel_entries.iter_mut() {
+                        rel.r_info = ((new((rel.r_info >> 32) as u32) as u64) << 32)
+                            | (rel.r_info & 0xffffffff);
+                    }
+                }
+                SectionData::Group(_) => section.header.sh_info = new(section.header.sh_info),
+                _ => (),
+            }
+        }
+        let link = self.sections[table].header.sh_link as usize;
+        let valid = link != 0 && link < self.sections.len();
+        let shared = link == self.header.e_shstrndx as usize
+            || self
+                .sections
+                .iter()
+                .enumerate()
+                .any(|(i, s)| i != table && s.header.sh_link as usize == link);
+        let mut strings = match (valid, shared) {
+            (true, true) => {
+                StringTableBuilder::from_table(&Into::<Vec<u8>>::into(&self.sections[link].data))
+            }
+            _ => StringTableBuilder::new(),
+        };
+        /* names the string table already holds at st_name */
+        let resolved: Vec<bool> = match (
+            self.sections.get(link).map(|s| &s.data),
+            self.linked_symbols(table),
+        ) {
+            (Some(SectionData::String(old)), Some(symbols)) if valid => {
+                let old = old.lookup();
+                symbols
+                    .iter()
+                    .map(|s| old.get(s.st_name as usize).as_deref() == Some(&s.name))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        let section = &mut self.sections[table];
+        let symbols = match &mut section.data {
+            SectionData::Symbol(s) => &mut s.symbols,
+            _ => return,
+        };
+        let mut old_symbols: Vec<Option<Elf64_Sym>> = symbols.drain(..).map(Some).collect();
+        symbols.extend(order.iter().filter_map(|i| old_symbols[*i].take()));
+        /* sh_info is one greater than the index of the last local symbol */
+        section.header.sh_info = symbols
+            .iter()
+            .position(|s| s.symbol_binding != SymbolBinding::STB_LOCAL)
+            .unwrap_or(symbols.len()) as u32;
+        section.header.sh_size = symbols.len() as u64 * section.header.sh_entsize;
+        if !valid {
+            return;
+        }
+        let renamed: Vec<bool> = order
+            .iter()
+            .map(|old| !shared || !resolved.get(*old).copied().unwrap_or(false))
+            .collect();
+        for (symbol, _) in symbols.iter().zip(&renamed).filter(|(_, r)| **r) {
+            strings.add(&symbol.name);
+        }
+        strings.finalize();
+        for (symbol, _) in symbols.iter_mut().zip(&renamed).filter(|(_, r)| **r) {
+            symbol.st_name = strings.offset(&symbol.name).unwrap_or(symbol.st_name);
+        }
+        let strtab = &mut self.sections[link];
+        strtab.header.sh_size = strings.len() as u64;
+        strtab.data = SectionData::String(strings.data().into());
+    }
+    /*
+     * applies edit to every symbol of .symtab but the null one and sorts
+     * the table again so that local symbols come first. returns the number
+     * of symbols edit changed
+     */
+    fn edit_symbols(&mut self, mut edit: impl FnMut(&mut Elf64_Sym) -> bool) -> usize {
+        let table = match self.symtab_index() {
+            Some(table) => table,
+            None => return 0,
+        };
+        let symbols = match &mut self.sections[table].data {
+            SectionData::Symbol(s) => &mut s.symbols,
+            _ => return 0,
+        };
+        let changed = symbols
+            .iter_mut()
+            .skip(1)
+            .map(&mut edit)
+            .filter(|c| *c)
+            .count();
+        if changed == 0 {
+            return 0;
+        }
+        let (mut order, globals): (Vec<usize>, Vec<usize>) = (0..symbols.len())
+            .partition(|i| *i == 0 || symbols[*i].symbol_binding == SymbolBinding::STB_LOCAL);
+        order.extend(globals);
+        self.reorder_symbols(table, &order);
+        changed
+    }
+    /*
+     * like objcopy --redefine-sym, every symbol named old is renamed
+     */
+    pub fn redefine_symbol(&mut self, old: &str, new: &str) -> usize {
+        self.edit_symbols(|s| {
+            let matches = s.name == old;
+            if matches {
+                s.name = new.to_string();
+            }
+            matches
+        })
+    }
+    /*
+     * like objcopy --prefix-symbols, section symbols keep their empty name
+     */
+    pub fn prefix_symbols(&mut self, prefix: &str) -> usize {
+        self.edit_symbols(|s| {
+            let named = !s.name.is_empty() && s.symbol_type != SymbolType::STT_SECTION;
+            if named {
+                s.name = format!("{}{}", prefix, s.name);
+            }
+            named
+        })
+    }
+    /*
+     * like objcopy --localize-symbol, undefined symbols stay global
+     */
+    pub fn localize_symbol(&mut self, name: &str) -> usize {
+        self.edit_symbols(|s| {
+            let matches =
+                s.name == name && s.is_defined() && s.symbol_binding != SymbolBinding::STB_LOCAL;
+            if matches {
+                s.set_binding(SymbolBinding::STB_LOCAL);
+            }
+            matches
+        })
+    }
+    /*
+     * like objcopy --globalize-symbol, section and file symbols stay local
+     */
+    pub fn globalize_symbol(&mut self, name: &str) -> usize {
+        self.edit_symbols(|s| {
+            let matches = s.name == name
+                && s.symbol_binding == SymbolBinding::STB_LOCAL
+                && !matches!(
+                    s.symbol_type,
+                    SymbolType::STT_SECTION | SymbolType::STT_FILE
+                );
+            if matches {
+                s.set_binding(SymbolBinding::STB_GLOBAL);
+            }
+            matches
+        })
+    }
+    /*
+     * like objcopy --weaken-symbol, for defined and undefined global symbols
+     */
+    pub fn weaken_symbol(&mut self, name: &str) -> usize {
+        self.edit_symbols(|s| {
+            let matches = s.name == name && s.symbol_binding == SymbolBinding::STB_GLOBAL;
+            if matches {
+                s.set_binding(SymbolBinding::STB_WEAK);
+            }
+            matches
+        })
+    }
+}
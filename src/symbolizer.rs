@@ -0,0 +1,136 @@
+use super::elf64::Elf64;
+use super::section::sh_flags::SHFlags;
+use super::section::symbol::{Elf64_Sym, SymbolBinding, SymbolType};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolRange {
+    pub start: u64, /* Link time address */
+    pub end: u64,   /* First address after the symbol */
+    pub name: String,
+}
+
+/*
+ * sorted, non overlapping address ranges of the function and object symbols
+ * of an elf. ranges are kept at link time addresses, the load bias is
+ * subtracted from every looked up address
+ */
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Symbolizer {
+    pub load_bias: u64,
+    pub ranges: Vec<SymbolRange>,
+}
+
+impl Symbolizer {
+    /*
+     * for PIE executables and shared objects, the difference between the
+     * runtime and link time addresses
+     */
+    pub fn load_bias(mut self, bias: u64) -> Self {
+        self.load_bias = bias;
+        self
+    }
+    pub fn symbolize(&self, addr: u64) -> Option<(&str, u64)> {
+        let addr = addr.wrapping_sub(self.load_bias);
+        let index = self.ranges.partition_point(|r| r.start <= addr);
+        let range = self.ranges.get(index.checked_sub(1)?)?;
+        match addr < range.end {
+            true => Some((range.name.as_str(), addr - range.start)),
+            false => None,
+        }
+    }
+    pub fn symbolize_all<'a>(
+        &'a self,
+        addrs: impl IntoIterator<Item = u64> + 'a,
+    ) -> impl Iterator<Item = Option<(&'a str, u64)>> + 'a {
+        addrs.into_iter().map(|a| self.symbolize(a))
+    }
+}
+
+struct Candidate {
+    start: u64,
+    size: u64,
+    limit: u64, /* end of the containing section, bounds zero sized symbols */
+    rank: u8,
+    name: String,
+}
+
+/*
+ * among aliases at the same address sized symbols win over zero sized ones,
+ * then global over weak over local, then functions over objects
+ */
+fn rank(symbol: &Elf64_Sym) -> u8 {
+    let binding = match symbol.symbol_binding {
+        SymbolBinding::STB_GLOBAL | SymbolBinding::STB_GNU_UNIQUE => 0,
+        SymbolBinding::STB_WEAK => 1,
+        _ => 2,
+    };
+    let kind = match symbol.symbol_type {
+        SymbolType::STT_FUNC | SymbolType::STT_GNU_IFUNC => 0,
+        _ => 1,
+    };
+    ((symbol.st_size == 0) as u8) << 3 | binding << 1 | kind
+}
+
+impl Elf64 {
+    /*
+     * index over .symtab (or the MiniDebugInfo symtab), .dynsym and the plt
+     * stubs. a zero sized symbol extends to the next symbol or to the end of
+     * its section
+     */
+    pub fn symbolizer(&self) -> Symbolizer {
+        let mut candidates: Vec<Candidate> = self
+            .lookup_symbols()
+            .into_iter()
+            .filter(|s| {
+                s.st_shndx != 0
+                    && s.st_value != 0
+                    && !s.name.is_empty()
+                    && matches!(
+                        s.symbol_type,
+                        SymbolType::STT_FUNC | SymbolType::STT_OBJECT | SymbolType::STT_GNU_IFUNC
+                    )
+            })
+            .map(|s| Candidate {
+                start: s.st_value,
+                size: s.st_size,
+                limit: self
+                    .sections
+                    .get(s.st_shndx as usize)
+                    .filter(|section| section.header.sh_flags.contains(SHFlags::SHF_ALLOC))
+                    .map(|section| section.header.sh_addr + section.header.sh_size)
+                    .unwrap_or(u64::MAX),
+                rank: rank(s),
+                name: s.name.clone(),
+            })
+            .collect();
+        candidates.extend(self.plt_stubs().into_iter().filter_map(|stub| {
+            Some(Candidate {
+                start: stub.address,
+                size: stub.size,
+                limit: u64::MAX,
+                rank: 0xff,
+                name: stub.name()?,
+            })
+        }));
+        candidates.sort_by(|a, b| (a.start, a.rank, &a.name).cmp(&(b.start, b.rank, &b.name)));
+        candidates.dedup_by_key(|c| c.start);
+        let mut ranges: Vec<SymbolRange> = Vec::with_capacity(candidates.len());
+        for (i, c) in candidates.iter().enumerate() {
+            let next = candidates.get(i + 1).map(|n| n.start).unwrap_or(u64::MAX);
+            let end = match c.size {
+                0 => next.min(c.limit).max(c.start.saturating_add(1)),
+                size => c.start.saturating_add(size).min(next),
+            };
+            ranges.push(SymbolRange {
+                start: c.start,
+                end,
+                name: c.name.clone(),
+            });
+        }
+        Symbolizer {
+            load_bias: 0,
+            ranges,
+        }
+    }
+}
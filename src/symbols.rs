@@ -0,0 +1,213 @@
+use super::elf64::Elf64;
+use super::section::section::SectionData;
+use super::section::sh_type::ShType;
+use super::section::symbol::{
+    Elf64_Sym, SectionIndex, SymbolBinding, SymbolType, SymbolVisibility,
+};
+use super::section::version::{SymbolVersion, VersionNames};
+use serde::Serialize;
+
+/*
+ * a symbol with the data found in the other sections of the file
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolEntry<'a> {
+    pub symbol: &'a Elf64_Sym,
+    pub table: usize, /* Section index of the symbol table */
+    pub index: usize, /* Index of the symbol in its table */
+    pub version: Option<SymbolVersion>,
+}
+
+impl SymbolEntry<'_> {
+    pub fn name(&self) -> &str {
+        &self.symbol.name
+    }
+    pub fn demangled_name(&self) -> String {
+        self.symbol.demangled_name()
+    }
+    /*
+     * name as printed by nm and readelf: foo@@VER for the default version,
+     * foo@VER for hidden and needed ones
+     */
+    pub fn versioned_name(&self) -> String {
+        match &self.version {
+            Some(v) if !v.hidden && self.symbol.is_defined() => {
+                format!("{}@@{}", self.symbol.name, v.name)
+            }
+            Some(v) => format!("{}@{}", self.symbol.name, v.name),
+            None => self.symbol.name.clone(),
+        }
+    }
+    pub fn section_index(&self) -> SectionIndex {
+        self.symbol.section_index()
+    }
+    pub fn visibility(&self) -> SymbolVisibility {
+        self.symbol.visibility()
+    }
+    pub fn binding(&self) -> SymbolBinding {
+        self.symbol.symbol_binding
+    }
+    pub fn is_defined(&self) -> bool {
+        self.symbol.is_defined()
+    }
+    pub fn is_function(&self) -> bool {
+        matches!(
+            self.symbol.symbol_type,
+            SymbolType::STT_FUNC | SymbolType::STT_GNU_IFUNC
+        )
+    }
+    /*
+     * visible outside of the file: not local and not hidden or internal
+     */
+    pub fn is_exported(&self) -> bool {
+        self.is_defined()
+            && self.binding() != SymbolBinding::STB_LOCAL
+            && matches!(
+                self.visibility(),
+                SymbolVisibility::STV_DEFAULT | SymbolVisibility::STV_PROTECTED
+            )
+    }
+}
+
+impl Elf64 {
+    /*
+     * .symtab entries, the null symbol at index 0 is skipped
+     */
+    pub fn symbols(&self) -> impl Iterator<Item = SymbolEntry<'_>> + '_ {
+        self.table_entries(ShType::SHT_SYMTAB)
+    }
+    /*
+     * .dynsym entries with their versions from .gnu.version
+     */
+    pub fn dynamic_symbols(&self) -> impl Iterator<Item = SymbolEntry<'_>> + '_ {
+        self.table_entries(ShType::SHT_DYNSYM)
+    }
+    /*
+     * the symbol table describing the whole file: .symtab, or .dynsym for
+     * stripped files
+     */
+    fn primary_symbols(&self) -> impl Iterator<Item = SymbolEntry<'_>> + '_ {
+        let has_symtab = self
+            .sections
+            .iter()
+            .any(|s| s.header.sh_type == ShType::SHT_SYMTAB);
+        match has_symtab {
+            true => self.table_entries(ShType::SHT_SYMTAB),
+            false => self.table_entries(ShType::SHT_DYNSYM),
+        }
+    }
+    /*
+     * looks in .symtab then .dynsym, definitions are preferred over
+     * undefined references and global over weak over local bindings. a
+     * symbol named exactly like name wins, as in relocatable files where
+     * .symver leaves foo@VER in the name, otherwise a versioned name
+     * (foo@VER or foo@@VER) only matches that version
+     */
+    pub fn symbol_by_name(&self, name: &str) -> Option<SymbolEntry<'_>> {
+        if let Some(s) = self.best_symbol(|s| s.name() == name) {
+            return Some(s);
+        }
+        let (base, version) = name.split_once('@')?;
+        let version = version.trim_start_matches('@');
+        self.best_symbol(|s| {
+            s.name() == base && s.version.as_ref().map(|v| v.name.as_str()) == Some(version)
+        })
+    }
+    fn best_symbol<P>(&self, predicate: P) -> Option<SymbolEntry<'_>>
+    where
+        P: Fn(&SymbolEntry) -> bool,
+    {
+        self.symbols()
+            .chain(self.dynamic_symbols())
+            .filter(|s| predicate(s))
+            .min_by_key(|s| {
+                let binding = match s.binding() {
+                    SymbolBinding::STB_GLOBAL | SymbolBinding::STB_GNU_UNIQUE => 0,
+                    SymbolBinding::STB_WEAK => 1,
+                    _ => 2,
+                };
+                (!s.is_defined(), binding)
+            })
+    }
+    pub fn defined_functions(&self) -> impl Iterator<Item = SymbolEntry<'_>> + '_ {
+        self.primary_symbols()
+            .filter(|s| s.is_defined() && s.is_function())
+    }
+    pub fn undefined_symbols(&self) -> impl Iterator<Item = SymbolEntry<'_>> + '_ {
+        self.primary_symbols()
+            .filter(|s| !s.is_defined() && !s.name().is_empty())
+    }
+    /*
+     * symbols other files can link against: .dynsym of executables and
+     * shared objects, .symtab of relocatable files
+     */
+    pub fn exported_symbols(&self) -> impl Iterator<Item = SymbolEntry<'_>> + '_ {
+        let has_dynsym = self
+            .sections
+            .iter()
+            .any(|s| s.header.sh_type == ShType::SHT_DYNSYM);
+        let entries = match has_dynsym {
+            true => self.table_entries(ShType::SHT_DYNSYM),
+            false => self.table_entries(ShType::SHT_SYMTAB),
+        };
+        entries.filter(|s| s.is_exported() && !s.name().is_empty())
+    }
+    fn table_entries(&self, sh_type: ShType) -> impl Iterator<Item = SymbolEntry<'_>> + '_ {
+        self.sections
+            .iter()
+            .enumerate()
+            .filter(move |(_, s)| s.header.sh_type == sh_type)
+            .flat_map(move |(table, s)| {
+                let versions = self.symbol_versions(table);
+                let symbols: &[Elf64_Sym] = match &s.data {
+                    SectionData::Symbol(t) => &t.symbols,
+                    _ => &[],
+                };
+                symbols
+                    .iter()
+                    .enumerate()
+                    .skip(1)
+                    .map(move |(index, symbol)| SymbolEntry {
+                        symbol,
+                        table,
+                        index,
+                        version: versions.get(index).cloned().flatten(),
+                    })
+            })
+    }
+    /*
+     * versions of the symbols of a table from the SHT_GNU_versym section
+     * linked to it, empty if the table is not versioned
+     */
+    pub fn symbol_versions(&self, table: usize) -> Vec<Option<SymbolVersion>> {
+        let versym = match self.sections.iter().find(|s| {
+            s.header.sh_type == ShType::SHT_GNU_versym && s.header.sh_link as usize == table
+        }) {
+            Some(s) => s,
+            None => return Vec::new(),
+        };
+        let mut names = VersionNames::default();
+        for section in &self.sections {
+            let strings = match self.sections.get(section.header.sh_link as usize) {
+                Some(s) => match &s.data {
+                    SectionData::String(strings) => strings,
+                    _ => continue,
+                },
+                None => continue,
+            };
+            match section.header.sh_type {
+                ShType::SHT_GNU_verdef => {
+                    names.add_definitions(&Into::<Vec<u8>>::into(&section.data), strings)
+                }
+                ShType::SHT_GNU_verneed => {
+                    names.add_needs(&Into::<Vec<u8>>::into(&section.data), strings)
+                }
+                _ => (),
+            }
+        }
+        let raw: Vec<u8> = (&versym.data).into();
+        raw.chunks_exact(2)
+            .map(|c| names.version(u16::from_le_bytes([c[0], c[1]])))
+            .collect()
+    }
+}
@@ -0,0 +1,112 @@
+use super::common::write_at;
+use super::elf64::Elf64;
+use super::error::ElfError;
+use super::section::compression::{self, ChType};
+use super::section::section_header::Elf64Shdr;
+use super::section::sh_flags::SHFlags;
+use super::section::sh_type::ShType;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WriteOptions {
+    /*
+     * compress non allocated .debug_* sections into SHF_COMPRESSED sections,
+     * a section is only compressed if that makes it smaller
+     */
+    pub compress_debug_sections: Option<ChType>,
+}
+
+impl Elf64 {
+    /*
+     * everything mapped by a segment or allocated stays at its offset,
+     * the other sections are laid out again after it and the section
+     * header table goes to the end of the file. files without segments
+     * (relocatable objects) are laid out again completely
+     */
+    pub fn write(&self, options: &WriteOptions) -> Result<Vec<u8>, ElfError> {
+        let mut header = self.header.clone();
+        let mut out: Vec<u8> = Vec::new();
+        let mut end = (header.e_ehsize as u64)
+            .max(header.e_phoff + header.e_phentsize as u64 * self.programs.len() as u64);
+        for program in self.programs.iter().filter(|p| p.header.p_filesz != 0) {
+            write_at(
+                &mut out,
+                program.header.p_offset as usize,
+                &program.data.inner,
+            );
+            end = end.max(program.header.p_offset + program.header.p_filesz);
+        }
+        /* after the contents of the segments, which may hold an older table */
+        let mut phoff = header.e_phoff as usize;
+        for program in &self.programs {
+            write_at(&mut out, phoff, &Into::<Vec<u8>>::into(&program.header));
+            phoff += header.e_phentsize as usize;
+        }
+        let in_segment = |h: &Elf64Shdr| {
+            self.programs.iter().any(|p| {
+                p.header.p_filesz != 0
+                    && h.sh_offset >= p.header.p_offset
+                    && h.sh_offset + h.sh_size <= p.header.p_offset + p.header.p_filesz
+            })
+        };
+        let mut headers: Vec<Elf64Shdr> = self.sections.iter().map(|s| s.header.clone()).collect();
+        let mut fixed: Vec<bool> = Vec::new();
+        for section in &self.sections {
+            let h = &section.header;
+            /* sections at offset 0 were added after linking and have no place yet */
+            let keep = h.sh_type == ShType::SHT_NULL
+                || (!self.programs.is_empty()
+                    && h.sh_offset != 0
+                    && (h.sh_flags.contains(SHFlags::SHF_ALLOC)
+                        || (h.sh_type != ShType::SHT_NOBITS && in_segment(h))));
+            if keep && !matches!(h.sh_type, ShType::SHT_NULL | ShType::SHT_NOBITS) {
+                write_at(
+                    &mut out,
+                    h.sh_offset as usize,
+                    &Into::<Vec<u8>>::into(&section.data),
+                );
+                end = end.max(h.sh_offset + h.sh_size);
+            }
+            fixed.push(keep);
+        }
+        for (i, section) in self.sections.iter().enumerate() {
+            if fixed[i] {
+                continue;
+            }
+            let h = &mut headers[i];
+            if h.sh_type == ShType::SHT_NOBITS {
+                h.sh_offset = end;
+                continue;
+            }
+            let mut data: Vec<u8> = (&section.data).into();
+            if let Some(ch_type) = options.compress_debug_sections {
+                if section.name.starts_with(".debug_")
+                    && !data.is_empty()
+                    && !h.sh_flags.contains(SHFlags::SHF_COMPRESSED)
+                {
+                    let compressed = compression::compress(&data, ch_type, h.sh_addralign)?;
+                    if compressed.len() < data.len() {
+                        data = compressed;
+                        h.sh_flags |= SHFlags::SHF_COMPRESSED;
+                        h.sh_addralign = 8;
+                    }
+                }
+            }
+            h.sh_offset = end.next_multiple_of(h.sh_addralign.max(1));
+            h.sh_size = data.len() as u64;
+            write_at(&mut out, h.sh_offset as usize, &data);
+            end = h.sh_offset + h.sh_size;
+        }
+        if headers.is_empty() {
+            header.e_shoff = 0;
+        } else {
+            header.e_shoff = end.next_multiple_of(8);
+            let mut shoff = header.e_shoff as usize;
+            for h in &headers {
+                write_at(&mut out, shoff, &Into::<Vec<u8>>::into(h));
+                shoff += header.e_shentsize as usize;
+            }
+        }
+        write_at(&mut out, 0, &Into::<Vec<u8>>::into(&header));
+        Ok(out)
+    }
+}
@@ -0,0 +1,25 @@
+#![allow(dead_code)]
+
+use simple_elf::writer::WriteOptions;
+use simple_elf::Elf64;
+use std::path::PathBuf;
+
+/*
+ * empty directory for the files of one test, removed again by the next
+ * run of the test
+ */
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("simple_elf-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/*
+ * writes elf and parses the output again
+ */
+pub fn reparse(elf: &Elf64) -> (Vec<u8>, Elf64) {
+    let raw = elf.write(&WriteOptions::default()).unwrap();
+    let elf = Elf64::from(&raw);
+    (raw, elf)
+}
@@ -0,0 +1,95 @@
+use enumflags2::BitFlags;
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::section::compression::{compress, decompress, decompress_zdebug, ChType};
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::writer::WriteOptions;
+use simple_elf::Elf64;
+
+fn debug_info() -> Vec<u8> {
+    (0..4096u32).map(|i| (i % 7) as u8).collect()
+}
+
+#[test]
+fn compress_round_trip() {
+    let data = debug_info();
+    for ch_type in [ChType::ELFCOMPRESS_ZLIB, ChType::ELFCOMPRESS_ZSTD] {
+        let compressed = compress(&data, ch_type, 1).unwrap();
+        assert!(compressed.len() < data.len());
+        let header: u32 = ch_type.into();
+        assert_eq!(compressed[..4], header.to_le_bytes());
+        assert_eq!(decompress(&compressed).unwrap(), data);
+    }
+    assert!(matches!(
+        compress(&data, ChType::UNSPECIFIED(9), 1),
+        Err(ElfError::UnsupportedCompression(9))
+    ));
+}
+
+#[test]
+fn decompress_checks_the_header() {
+    let mut compressed = compress(&debug_info(), ChType::ELFCOMPRESS_ZLIB, 1).unwrap();
+    compressed[8..16].copy_from_slice(&100u64.to_le_bytes());
+    assert!(matches!(
+        decompress(&compressed),
+        Err(ElfError::SizeMismatch { expected: 100, .. })
+    ));
+    assert!(matches!(
+        decompress(&[1, 0, 0]),
+        Err(ElfError::Truncated(_))
+    ));
+}
+
+#[test]
+fn legacy_zdebug_sections() {
+    let data = debug_info();
+    let stream = &compress(&data, ChType::ELFCOMPRESS_ZLIB, 1).unwrap()[24..];
+    let mut zdebug: Vec<u8> = b"ZLIB".to_vec();
+    zdebug.extend((data.len() as u64).to_be_bytes());
+    zdebug.extend(stream);
+    assert_eq!(decompress_zdebug(&zdebug).unwrap(), data);
+    let elf = ObjectBuilder::new()
+        .add_section(".zdebug_info", BitFlags::empty(), 1, zdebug)
+        .build()
+        .unwrap();
+    let section = elf
+        .sections
+        .iter()
+        .find(|s| s.name == ".zdebug_info")
+        .unwrap();
+    assert_eq!(section.decompressed_data().unwrap(), data);
+}
+
+#[test]
+fn writer_compresses_debug_sections() {
+    let text = vec![0xc3; 16];
+    let elf = ObjectBuilder::new()
+        .add_section(
+            ".text",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+            16,
+            text.clone(),
+        )
+        .add_section(".debug_info", BitFlags::empty(), 1, debug_info())
+        .add_section(".debug_str", BitFlags::empty(), 1, b"a\0".to_vec())
+        .build()
+        .unwrap();
+    for ch_type in [ChType::ELFCOMPRESS_ZLIB, ChType::ELFCOMPRESS_ZSTD] {
+        let options = WriteOptions {
+            compress_debug_sections: Some(ch_type),
+        };
+        let elf = Elf64::from(&elf.write(&options).unwrap());
+        let section = |name: &str| elf.sections.iter().find(|s| s.name == name).unwrap();
+        let info = section(".debug_info");
+        assert!(info.header.sh_flags.contains(SHFlags::SHF_COMPRESSED));
+        assert!(info.header.sh_size < 4096);
+        assert_eq!(info.decompressed_data().unwrap(), debug_info());
+        /* compressing would make it bigger */
+        let strings = section(".debug_str");
+        assert!(!strings.header.sh_flags.contains(SHFlags::SHF_COMPRESSED));
+        assert_eq!(strings.decompressed_data().unwrap(), b"a\0");
+        let code = section(".text");
+        assert!(!code.header.sh_flags.contains(SHFlags::SHF_COMPRESSED));
+        assert_eq!(code.decompressed_data().unwrap(), text);
+    }
+}
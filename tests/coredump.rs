@@ -0,0 +1,93 @@
+use simple_elf::coredump::builder::CoreBuilder;
+use simple_elf::coredump::nt_file::FileMapping;
+use simple_elf::coredump::prstatus::{Elf64_Prpsinfo, Elf64_Prstatus, UserRegs};
+use simple_elf::error::ElfError;
+use simple_elf::header::e_type::EType;
+use simple_elf::program::program_header::PFlags;
+use simple_elf::writer::WriteOptions;
+use simple_elf::Elf64;
+
+fn core() -> Vec<u8> {
+    let regs = UserRegs {
+        rip: 0x401000,
+        rsp: 0x7ffffffde000,
+        ..Default::default()
+    };
+    CoreBuilder::new()
+        .process(Elf64_Prpsinfo::new(42, "crash", "./crash --now"))
+        .add_thread(Elf64_Prstatus::new(42, regs), Some(vec![0xaa; 512]))
+        .add_thread(Elf64_Prstatus::new(43, UserRegs::default()), None)
+        .add_memory_region(0x400000, PFlags::Read | PFlags::Execute, vec![0x90; 0x1000])
+        .add_memory_region(0x600000, PFlags::Read | PFlags::Write, b"hello".to_vec())
+        .add_unreadable_region(0x7ffff7a00000, 0x2000, PFlags::Read.into())
+        .add_file_mapping(FileMapping {
+            start: 0x400000,
+            end: 0x401000,
+            file_offset: 0x1000,
+            path: "/usr/bin/crash".to_string(),
+        })
+        .auxv(vec![(6, 0x1000), (0, 0)])
+        .siginfo(vec![11, 0, 0, 0])
+        .build()
+        .unwrap()
+        .write(&WriteOptions::default())
+        .unwrap()
+}
+
+#[test]
+fn core_file_round_trip() {
+    let raw = core();
+    let elf = Elf64::from(&raw);
+    assert_eq!(elf.header.e_type, EType::ET_CORE);
+    let core = elf.core().unwrap();
+    let process = core.process.as_ref().unwrap();
+    assert_eq!(process.pr_pid, 42);
+    assert_eq!(process.pr_fname, "crash");
+    assert_eq!(process.pr_psargs, "./crash --now");
+    assert_eq!(core.threads.len(), 2);
+    assert_eq!(core.threads[0].status.pr_pid, 42);
+    assert_eq!(core.threads[0].status.pr_reg.rip, 0x401000);
+    assert_eq!(core.threads[0].status.pr_fpvalid, 1);
+    assert_eq!(core.threads[0].fpregs.as_deref(), Some(&[0xaa; 512][..]));
+    assert_eq!(core.threads[1].status.pr_pid, 43);
+    assert!(core.threads[1].fpregs.is_none());
+    assert_eq!(core.files.len(), 1);
+    assert_eq!(core.files[0].path, "/usr/bin/crash");
+    assert_eq!(core.files[0].file_offset, 0x1000);
+    assert_eq!(core.auxv, vec![(6, 0x1000), (0, 0)]);
+    assert_eq!(core.siginfo.as_deref(), Some(&[11, 0, 0, 0][..]));
+}
+
+#[test]
+fn core_memory_reads() {
+    let raw = core();
+    let elf = Elf64::from(&raw);
+    let core = elf.core().unwrap();
+    assert_eq!(core.read_memory(0x600000, 5).unwrap(), b"hello");
+    assert_eq!(core.read_memory(0x400ffe, 2).unwrap(), vec![0x90; 2]);
+    /* regions that were not dumped read as zero */
+    assert_eq!(core.read_memory(0x7ffff7a00010, 4).unwrap(), vec![0; 4]);
+    assert!(core.read_memory(0x500000, 1).is_none());
+    assert!(core.read_memory(0x400fff, 2).is_none());
+    assert!(core.read_memory(u64::MAX, 2).is_none());
+    let region = core.region_for_address(0x600002).unwrap();
+    assert_eq!(region.flags, PFlags::Read | PFlags::Write);
+}
+
+#[test]
+fn core_builder_rejects_bad_mappings() {
+    let unaligned = CoreBuilder::new()
+        .add_file_mapping(FileMapping {
+            start: 0x400000,
+            end: 0x401000,
+            file_offset: 0x10,
+            path: "/bin/true".to_string(),
+        })
+        .build();
+    assert!(matches!(
+        unaligned,
+        Err(ElfError::UnalignedFileOffset(0x10))
+    ));
+    let page_size = CoreBuilder::new().page_size(3000).build();
+    assert!(matches!(page_size, Err(ElfError::InvalidPageSize(3000))));
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{reparse, temp_dir};
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::debuginfo::debuglink::DebugLink;
+use simple_elf::debuginfo::locator::DebugFileLocator;
+use simple_elf::note::note::Note;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::Elf64;
+use std::fs;
+use std::path::Path;
+
+const BUILD_ID: [u8; 4] = [0xde, 0xad, 0xbe, 0xef];
+
+fn with_build_id(build_id: &[u8]) -> Vec<u8> {
+    let note = Note::new("GNU", 3, build_id.to_vec()).to_bytes(4);
+    let elf = ObjectBuilder::new()
+        .add_section(".note.gnu.build-id", SHFlags::SHF_ALLOC.into(), 4, note)
+        .add_section(
+            ".text",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+            16,
+            vec![0xc3],
+        )
+        .build()
+        .unwrap();
+    reparse(&elf).0
+}
+
+#[test]
+fn build_id_from_note_section() {
+    let raw = with_build_id(&BUILD_ID);
+    assert_eq!(Elf64::from(&raw).build_id(), Some(BUILD_ID.to_vec()));
+    assert_eq!(
+        DebugFileLocator::build_id_path(Path::new("/usr/lib/debug"), &BUILD_ID),
+        Some("/usr/lib/debug/.build-id/de/adbeef.debug".into())
+    );
+    assert_eq!(DebugFileLocator::build_id_path(Path::new("/d"), &[1]), None);
+}
+
+#[test]
+fn find_by_build_id() {
+    let dir = temp_dir("build-id");
+    let raw = with_build_id(&BUILD_ID);
+    let path = DebugFileLocator::build_id_path(&dir, &BUILD_ID).unwrap();
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, &raw).unwrap();
+    let locator = DebugFileLocator::with_directories([&dir]);
+    let found = locator
+        .find(&Elf64::from(&raw), dir.join("program"))
+        .unwrap();
+    assert_eq!(found.path, path);
+    /* the candidate must carry the same build id */
+    assert!(locator
+        .find_by_build_id(&[0xde, 0xad, 0xbe, 0xee])
+        .is_none());
+    fs::write(&path, with_build_id(&[1, 2, 3, 4])).unwrap();
+    assert!(locator.find_by_build_id(&BUILD_ID).is_none());
+}
+
+#[test]
+fn truncated_build_id_candidate_is_skipped() {
+    let dir = temp_dir("truncated");
+    let raw = with_build_id(&BUILD_ID);
+    let path = DebugFileLocator::build_id_path(&dir, &BUILD_ID).unwrap();
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, &raw[..raw.len() - 64]).unwrap();
+    let locator = DebugFileLocator::with_directories([&dir]);
+    assert!(locator.find_by_build_id(&BUILD_ID).is_none());
+}
+
+#[test]
+fn find_by_debuglink() {
+    let dir = temp_dir("debuglink");
+    let debug = with_build_id(&BUILD_ID);
+    fs::create_dir_all(dir.join(".debug")).unwrap();
+    fs::write(dir.join(".debug/program.debug"), &debug).unwrap();
+    let mut program = Elf64::from(&with_build_id(&[1, 2, 3, 4]));
+    program
+        .add_gnu_debuglink("/elsewhere/program.debug", &debug)
+        .unwrap();
+    let (raw, program) = reparse(&program);
+    fs::write(dir.join("program"), raw).unwrap();
+    let link = program.debuglink().unwrap();
+    assert_eq!(link, DebugLink::new("program.debug", &debug));
+    let locator = DebugFileLocator::with_directories(Vec::<String>::new());
+    let found = locator.find(&program, dir.join("program")).unwrap();
+    assert_eq!(found.path.file_name().unwrap(), "program.debug");
+    assert_eq!(found.elf.build_id(), Some(BUILD_ID.to_vec()));
+    /* a file with another crc is not the debug file */
+    fs::write(
+        dir.join(".debug/program.debug"),
+        with_build_id(&[5, 6, 7, 8]),
+    )
+    .unwrap();
+    assert!(locator.find(&program, dir.join("program")).is_none());
+}
@@ -0,0 +1,58 @@
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::symbol::{demangle, SymbolBinding, SymbolType};
+use simple_elf::Elf64;
+
+const CPP: &str = "_ZN3foo3barEi";
+const RUST: &str = "_ZN4core3fmt5write17h0123456789abcdefE";
+
+fn object() -> Elf64 {
+    let text = SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR;
+    [CPP, RUST, "main"]
+        .iter()
+        .enumerate()
+        .fold(
+            ObjectBuilder::new().add_section(".text", text, 16, vec![0xc3; 3]),
+            |b, (i, name)| {
+                b.add_symbol(
+                    name,
+                    Some(".text"),
+                    i as u64,
+                    1,
+                    SymbolType::STT_FUNC,
+                    SymbolBinding::STB_GLOBAL,
+                )
+            },
+        )
+        .build()
+        .unwrap()
+}
+
+#[cfg(feature = "demangle")]
+#[test]
+fn names_are_demangled() {
+    assert_eq!(demangle(CPP).as_deref(), Some("foo::bar(int)"));
+    /* without the hash the itanium demangler would print */
+    assert_eq!(demangle(RUST).as_deref(), Some("core::fmt::write"));
+    assert_eq!(
+        demangle("_RNvC6_123foo3bar").as_deref(),
+        Some("123foo::bar")
+    );
+    assert_eq!(demangle("main"), None);
+    assert_eq!(demangle("_Z"), None);
+    let elf = object();
+    let name = |n: &str| elf.symbol_by_name(n).unwrap().demangled_name();
+    assert_eq!(name(CPP), "foo::bar(int)");
+    assert_eq!(name(RUST), "core::fmt::write");
+    assert_eq!(name("main"), "main");
+}
+
+#[cfg(not(feature = "demangle"))]
+#[test]
+fn names_are_kept_without_the_feature() {
+    assert_eq!(demangle(CPP), None);
+    let elf = object();
+    for name in [CPP, RUST, "main"] {
+        assert_eq!(elf.symbol_by_name(name).unwrap().demangled_name(), name);
+    }
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::executable::ExecutableBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::header::e_type::EType;
+use simple_elf::image::PAGE_SIZE;
+use simple_elf::program::p_type::PType;
+use simple_elf::program::program_header::PFlags;
+use simple_elf::section::symbol::{Elf64_Rela, RelocationType, SymbolType};
+
+/* mov $42, %edi; mov $60, %eax; syscall */
+const EXIT_42: [u8; 12] = [0xbf, 0x2a, 0, 0, 0, 0xb8, 0x3c, 0, 0, 0, 0x0f, 0x05];
+
+/*
+ * _start exits with 42, .data points to message and .text loads the
+ * address of .rodata pc relative after the exit
+ */
+fn executable() -> ExecutableBuilder {
+    let mut code = EXIT_42.to_vec();
+    code.extend([0x48, 0x8d, 0x35, 0, 0, 0, 0]); /* lea 0(%rip), %rsi */
+    ExecutableBuilder::new()
+        .add_code(".text", 16, code)
+        .add_rodata(".rodata", 8, b"hello\0".to_vec())
+        .add_data(".data", 8, vec![0; 8])
+        .add_bss(".bss", 16, 32)
+        .add_symbol("_start", ".text", 0, 12, SymbolType::STT_FUNC)
+        .add_symbol("message", ".rodata", 0, 6, SymbolType::STT_OBJECT)
+        .add_relocation(
+            ".data",
+            "message",
+            Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 1),
+        )
+        .add_relocation(
+            ".text",
+            ".rodata",
+            Elf64_Rela::new(15, RelocationType::R_X86_64_PC32, 0, -4i64 as u64),
+        )
+}
+
+#[test]
+fn segments_and_relocations() {
+    let (_, elf) = reparse(&executable().base_address(0x10000).build().unwrap());
+    assert_eq!(elf.header.e_type, EType::ET_EXEC);
+    let address = |name: &str| elf.section_by_name(name).unwrap().header.sh_addr;
+    assert_eq!(elf.header.e_entry, address(".text"));
+    let loads: Vec<_> = elf
+        .programs
+        .iter()
+        .filter(|p| p.header.p_type == PType::PT_LOAD)
+        .map(|p| &p.header)
+        .collect();
+    assert_eq!(loads.len(), 3);
+    assert_eq!((loads[0].p_offset, loads[0].p_vaddr), (0, 0x10000));
+    assert_eq!(loads[0].p_flags, PFlags::Read);
+    assert_eq!(loads[1].p_flags, PFlags::Read | PFlags::Execute);
+    assert_eq!(loads[2].p_flags, PFlags::Read | PFlags::Write);
+    assert!(loads
+        .iter()
+        .all(|p| p.p_offset % PAGE_SIZE == p.p_vaddr % PAGE_SIZE));
+    /* .bss only takes memory */
+    assert_eq!(loads[2].p_memsz, address(".bss") + 32 - loads[2].p_vaddr);
+    assert!(loads[2].p_filesz < loads[2].p_memsz);
+    let data: Vec<u8> = (&elf.section_by_name(".data").unwrap().data).into();
+    assert_eq!(
+        u64::from_le_bytes(data.try_into().unwrap()),
+        address(".rodata") + 1
+    );
+    let text: Vec<u8> = (&elf.section_by_name(".text").unwrap().data).into();
+    let displacement = i32::from_le_bytes(text[15..19].try_into().unwrap()) as i64;
+    assert_eq!(
+        address(".text") as i64 + 19 + displacement,
+        address(".rodata") as i64
+    );
+    assert_eq!(
+        elf.symbol_by_name("message").unwrap().symbol.st_value,
+        address(".rodata")
+    );
+}
+
+#[test]
+fn optional_headers() {
+    let elf = executable().phdr(true).entry("message").build().unwrap();
+    let phdr = &elf.programs[0].header;
+    assert_eq!(phdr.p_type, PType::PT_PHDR);
+    assert_eq!(
+        (phdr.p_offset, phdr.p_filesz),
+        (64, 56 * elf.programs.len() as u64)
+    );
+    assert_eq!(
+        elf.header.e_entry,
+        elf.section_by_name(".rodata").unwrap().header.sh_addr
+    );
+    assert_eq!(
+        elf.programs.last().unwrap().header.p_type,
+        PType::PT_GNU_STACK
+    );
+    let (_, elf) = reparse(&executable().gnu_stack(false).symtab(false).build().unwrap());
+    assert!(elf
+        .programs
+        .iter()
+        .all(|p| p.header.p_type != PType::PT_GNU_STACK));
+    assert!(elf.section_by_name(".symtab").is_none());
+    assert!(elf.section_by_name(".text").is_some());
+    let (raw, elf) = reparse(&executable().section_headers(false).build().unwrap());
+    assert!(elf.sections.is_empty());
+    assert_eq!(elf.header.e_shnum, 0);
+    assert_eq!(raw.len() as u64, {
+        let last = &elf
+            .programs
+            .iter()
+            .rev()
+            .find(|p| p.header.p_type == PType::PT_LOAD)
+            .unwrap()
+            .header;
+        last.p_offset + last.p_filesz
+    });
+}
+
+#[test]
+fn unresolved_names() {
+    assert!(matches!(
+        executable().entry("main").build(),
+        Err(ElfError::UnresolvedSymbol(n)) if n == "main"
+    ));
+    let missing = executable().add_relocation(
+        ".data",
+        "missing",
+        Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 0),
+    );
+    assert!(matches!(missing.build(), Err(ElfError::UnresolvedSymbol(n)) if n == "missing"));
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn runs_without_a_loader() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = common::temp_dir("executable");
+    let path = dir.join("exit42");
+    let (raw, _) = reparse(&executable().build().unwrap());
+    std::fs::write(&path, raw).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let status = std::process::Command::new(&path).status().unwrap();
+    assert_eq!(status.code(), Some(42));
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use common::reparse;
+use enumflags2::BitFlags;
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::sh_type::ShType;
+use simple_elf::section::symbol::{SymbolBinding, SymbolType};
+use simple_elf::Elf64;
+
+/*
+ * object with the comdat group foo holding .text.foo, .data.foo is not in
+ * a group
+ */
+fn object() -> (Elf64, usize) {
+    let mut elf = ObjectBuilder::new()
+        .add_section(
+            ".text.foo",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR | SHFlags::SHF_GROUP,
+            16,
+            vec![0xc3],
+        )
+        .add_section(
+            ".data.foo",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+            8,
+            vec![0; 8],
+        )
+        .add_symbol(
+            "foo",
+            Some(".text.foo"),
+            0,
+            1,
+            SymbolType::STT_FUNC,
+            SymbolBinding::STB_WEAK,
+        )
+        .build()
+        .unwrap();
+    let text = elf.section_index(".text.foo").unwrap() as u32;
+    let symtab = elf.section_index(".symtab").unwrap() as u32;
+    let signature = elf.symbol_by_name("foo").unwrap().index as u32;
+    let data: Vec<u8> = [1, text].iter().flat_map(|w| w.to_le_bytes()).collect();
+    let group = elf
+        .add_section(".group", ShType::SHT_GROUP, BitFlags::empty(), data)
+        .unwrap();
+    let header = &mut elf.sections[group].header;
+    header.sh_link = symtab;
+    header.sh_info = signature;
+    header.sh_entsize = 4;
+    header.sh_addralign = 4;
+    (reparse(&elf).1, group)
+}
+
+#[test]
+fn comdat_group_is_parsed() {
+    let (elf, group) = object();
+    let groups: Vec<_> = elf.groups().collect();
+    assert_eq!(groups.len(), 1);
+    let (index, g) = groups[0];
+    assert_eq!(index, group);
+    assert!(g.is_comdat());
+    let text = elf.section_index(".text.foo").unwrap();
+    assert_eq!(g.members, vec![text as u32]);
+    assert_eq!(elf.group_signature(group).unwrap().name(), "foo");
+    assert_eq!(elf.group_signature_name(group).as_deref(), Some("foo"));
+    assert_eq!(elf.group_of(text), Some(group));
+    assert_eq!(elf.group_of(elf.section_index(".data.foo").unwrap()), None);
+}
+
+#[test]
+fn group_members_are_edited() {
+    let (mut elf, group) = object();
+    let text = elf.section_index(".text.foo").unwrap();
+    let data = elf.section_index(".data.foo").unwrap();
+    elf.add_group_member(group, data).unwrap();
+    assert!(elf.sections[data]
+        .header
+        .sh_flags
+        .contains(SHFlags::SHF_GROUP));
+    assert_eq!(elf.sections[group].header.sh_size, 12);
+    let (_, mut elf) = reparse(&elf);
+    assert_eq!(
+        elf.groups().next().unwrap().1.members,
+        vec![text as u32, data as u32]
+    );
+    assert_eq!(elf.group_of(data), Some(group));
+    elf.remove_group_member(group, text).unwrap();
+    assert!(!elf.sections[text]
+        .header
+        .sh_flags
+        .contains(SHFlags::SHF_GROUP));
+    assert_eq!(elf.group_of(text), None);
+    assert_eq!(elf.sections[group].header.sh_size, 8);
+    assert!(matches!(
+        elf.remove_group_member(group, text),
+        Err(ElfError::InvalidSection(_))
+    ));
+    assert!(matches!(
+        elf.add_group_member(group, group),
+        Err(ElfError::InvalidSection(_))
+    ));
+    assert!(matches!(
+        elf.add_group_member(text, data),
+        Err(ElfError::InvalidSection(_))
+    ));
+}
+
+#[test]
+fn removing_a_group_ungroups_its_members() {
+    let (mut elf, group) = object();
+    elf.remove_section(group).unwrap();
+    let (_, elf) = reparse(&elf);
+    assert_eq!(elf.groups().count(), 0);
+    let text = elf.section_index(".text.foo").unwrap();
+    assert!(!elf.sections[text]
+        .header
+        .sh_flags
+        .contains(SHFlags::SHF_GROUP));
+}
@@ -0,0 +1,114 @@
+use simple_elf::builder::executable::ExecutableBuilder;
+use simple_elf::builder::shared::SharedLibraryBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::image::PAGE_SIZE;
+use simple_elf::program::p_type::PType;
+use simple_elf::program::program_header::PFlags;
+use simple_elf::section::symbol::SymbolType;
+use simple_elf::Elf64;
+
+fn executable() -> Elf64 {
+    ExecutableBuilder::new()
+        .add_code(".text", 16, vec![0xc3; 32])
+        .add_data(".data", 8, (1..=16).collect())
+        .add_bss(".bss", 8, 64)
+        .add_symbol("_start", ".text", 0, 1, SymbolType::STT_FUNC)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn segments_are_mapped_at_their_addresses() {
+    let elf = executable();
+    let address = |name: &str| elf.section_by_name(name).unwrap().header.sh_addr;
+    let (text, data, bss) = (address(".text"), address(".data"), address(".bss"));
+    let mut image = elf.load_image(0).unwrap();
+    assert_eq!(image.load_bias, 0);
+    assert_eq!(image.entry, elf.header.e_entry);
+    assert_eq!(image.read(text, 2), Some(vec![0xc3, 0xc3]));
+    assert_eq!(image.read(data, 16), Some((1..=16).collect()));
+    assert_eq!(image.read(bss, 64), Some(vec![0; 64]));
+    assert_eq!(
+        image.permissions(text),
+        Some(PFlags::Read | PFlags::Execute)
+    );
+    assert_eq!(image.permissions(bss), Some(PFlags::Read | PFlags::Write));
+    /* the rest of the last page of .bss is mapped and zero */
+    let last = (bss + 64) | (PAGE_SIZE - 1);
+    assert_eq!(image.read(last, 1), Some(vec![0]));
+    assert!(!image.is_mapped(last + 1));
+    assert_eq!(image.read(last, 2), None);
+    assert!(matches!(
+        image.write(last, &[1, 2]),
+        Err(ElfError::Unmapped(a)) if a == last + 1
+    ));
+    image.write_u64(bss + 8, 0x1122334455667788).unwrap();
+    assert_eq!(image.read_u64(bss + 8), Some(0x1122334455667788));
+    assert_eq!(image.read_u64(bss), Some(0));
+    let regions = image.regions();
+    /* the headers, .text and .data with .bss */
+    assert_eq!(regions.len(), 3);
+    assert!(regions.iter().all(|r| r.start % PAGE_SIZE == 0 && !r.relro));
+    assert_eq!(regions[0].flags, PFlags::Read);
+    assert!(regions[1].start <= text && text < regions[1].end);
+    assert!(regions[2].start <= bss && bss < regions[2].end);
+}
+
+#[test]
+fn shared_objects_are_loaded_at_the_base() {
+    let elf = SharedLibraryBuilder::new()
+        .soname("libimage.so")
+        .add_code(".text", 16, vec![0xc3; 16])
+        .build()
+        .unwrap();
+    let text = elf.section_by_name(".text").unwrap().header.sh_addr;
+    let image = elf.load_image(0x7f0000001234).unwrap();
+    /* the base is aligned down to the largest p_align */
+    assert_eq!(image.load_bias, 0x7f0000001000);
+    assert_eq!(image.read(image.load_bias + text, 1), Some(vec![0xc3]));
+    assert!(!image.is_mapped(text));
+}
+
+#[test]
+fn relro_splits_regions() {
+    let mut elf = executable();
+    let data = elf.section_by_name(".data").unwrap().header.sh_addr;
+    let stack = elf
+        .programs
+        .iter_mut()
+        .find(|p| p.header.p_type == PType::PT_GNU_STACK)
+        .unwrap();
+    /* the first page of .data, the page after it stays writable */
+    stack.header.p_type = PType::PT_GNU_RELRO;
+    stack.header.p_vaddr = data;
+    stack.header.p_memsz = (data | (PAGE_SIZE - 1)) + 1 - data;
+    let load = elf
+        .programs
+        .iter_mut()
+        .find(|p| p.header.p_type == PType::PT_LOAD && p.header.p_vaddr == data)
+        .unwrap();
+    load.header.p_memsz += PAGE_SIZE;
+    let image = elf.load_image(0).unwrap();
+    assert!(image.is_relro(data));
+    assert!(!image.is_relro((data | (PAGE_SIZE - 1)) + 1));
+    let regions = image.regions();
+    assert_eq!(regions.len(), 4);
+    assert!(regions[2].relro && !regions[3].relro);
+    assert_eq!(regions[2].end, regions[3].start);
+    assert_eq!(regions[2].flags, regions[3].flags);
+}
+
+#[test]
+fn segments_the_kernel_refuses() {
+    let mut elf = executable();
+    let index = elf
+        .programs
+        .iter()
+        .position(|p| p.header.p_type == PType::PT_LOAD)
+        .unwrap();
+    elf.programs[index].header.p_filesz = elf.programs[index].header.p_memsz + 1;
+    assert!(matches!(elf.load_image(0), Err(ElfError::InvalidSegment(i)) if i == index));
+    elf.programs[index].header.p_filesz -= 1;
+    elf.programs[index].header.p_memsz = u64::MAX;
+    assert!(matches!(elf.load_image(0), Err(ElfError::InvalidSegment(i)) if i == index));
+}
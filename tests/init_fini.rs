@@ -0,0 +1,126 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::linker::{LinkOptions, Linker, OutputType};
+use simple_elf::program::p_type::PType;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::symbol::{Elf64_Rela, RelocationType, SymbolBinding, SymbolType};
+use simple_elf::writer::WriteOptions;
+use simple_elf::Elf64;
+
+fn object() -> Elf64 {
+    let function = |b: ObjectBuilder, name: &str, offset: u64| {
+        b.add_symbol(
+            name,
+            Some(".text"),
+            offset,
+            1,
+            SymbolType::STT_FUNC,
+            SymbolBinding::STB_GLOBAL,
+        )
+    };
+    let pointer =
+        |offset: u64, addend: u64| Elf64_Rela::new(offset, RelocationType::R_X86_64_64, 0, addend);
+    let b = ObjectBuilder::new()
+        .add_section(
+            ".text",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+            16,
+            vec![0xc3; 3],
+        )
+        .add_section(
+            ".init_array",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+            8,
+            vec![0; 16],
+        )
+        .add_section(
+            ".fini_array",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+            8,
+            vec![0; 8],
+        );
+    let b = function(function(function(b, "ctor", 0), "dtor", 1), "_start", 2);
+    b.add_relocation(".init_array", "ctor", pointer(0, 0))
+        .add_relocation(".init_array", ".text", pointer(8, 2))
+        .add_relocation(".fini_array", "dtor", pointer(0, 0))
+        .build()
+        .unwrap()
+}
+
+fn link(object: &Elf64, output_type: OutputType) -> Elf64 {
+    let options = LinkOptions {
+        output_type,
+        ..Default::default()
+    };
+    let output = Linker::new(options)
+        .add_object("a.o", object)
+        .link()
+        .unwrap();
+    reparse(&output.elf).1
+}
+
+#[test]
+fn relocatable_arrays() {
+    let (_, elf) = reparse(&object());
+    let init = elf.init_array();
+    assert_eq!(init.len(), 2);
+    assert_eq!((init[0].slot, init[0].address), (0, 0));
+    assert_eq!(init[0].symbol.as_deref(), Some("ctor"));
+    assert_eq!((init[1].slot, init[1].address), (8, 2));
+    let fini = elf.fini_array();
+    assert_eq!(fini.len(), 1);
+    assert_eq!(fini[0].symbol.as_deref(), Some("dtor"));
+    assert!(elf.preinit_array().is_empty());
+}
+
+#[test]
+fn linked_arrays() {
+    let object = object();
+    for output_type in [OutputType::Executable, OutputType::StaticPie] {
+        let elf = link(&object, output_type);
+        let address = |name: &str| elf.symbol_by_name(name).unwrap().symbol.st_value;
+        let init = elf.init_array();
+        assert_eq!(init.len(), 2);
+        assert_eq!(init[0].address, address("ctor"));
+        assert_eq!(init[0].symbol.as_deref(), Some("ctor"));
+        assert_eq!(init[1].address, address("_start"));
+        assert_eq!(init[1].symbol.as_deref(), Some("_start"));
+        assert_eq!(init[1].slot, init[0].slot + 8);
+        let fini = elf.fini_array();
+        assert_eq!(fini[0].address, address("dtor"));
+        assert!(!fini[0].resolver);
+    }
+}
+
+#[test]
+fn arrays_without_section_headers() {
+    let elf = link(&object(), OutputType::StaticPie);
+    let expected: Vec<u64> = elf.init_array().iter().map(|e| e.address).collect();
+    let init_array = elf.section_by_name(".init_array").unwrap().header.sh_addr;
+    let dynamic = elf
+        .programs
+        .iter()
+        .find(|p| p.header.p_type == PType::PT_DYNAMIC)
+        .unwrap()
+        .header
+        .p_offset as usize;
+    /*
+     * the linker writes no DT_INIT_ARRAY, it takes the place of entries
+     * the arrays do not need
+     */
+    let mut raw = elf.write(&WriteOptions::default()).unwrap();
+    for (i, (tag, value)) in [(25u64, init_array), (27, 16)].into_iter().enumerate() {
+        let entry = dynamic + 16 * (i + 2);
+        raw[entry..entry + 8].copy_from_slice(&tag.to_le_bytes());
+        raw[entry + 8..entry + 16].copy_from_slice(&value.to_le_bytes());
+    }
+    /* e_shoff, e_shnum and e_shstrndx */
+    raw[0x28..0x30].fill(0);
+    raw[0x3c..0x40].fill(0);
+    let elf = Elf64::from(&raw);
+    assert!(elf.sections.is_empty());
+    let init: Vec<u64> = elf.init_array().iter().map(|e| e.address).collect();
+    assert_eq!(init, expected);
+}
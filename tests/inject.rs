@@ -0,0 +1,254 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::inject::InjectOptions;
+use simple_elf::linker::{LinkOptions, Linker, OutputType};
+use simple_elf::program::p_type::PType;
+use simple_elf::program::program_header::PFlags;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::symbol::{Elf64_Rela, RelocationType, SymbolBinding, SymbolType};
+use simple_elf::writer::WriteOptions;
+use simple_elf::Elf64;
+
+/*
+ * _start exits with status, a word of .data, value returns 42 and short
+ * is too small to hook
+ */
+fn program(output_type: OutputType) -> Elf64 {
+    let code = vec![
+        0xb8, 0x3c, 0, 0, 0, /* mov $60, %eax */
+        0x8b, 0x3d, 0, 0, 0, 0, /* mov status(%rip), %edi */
+        0x0f, 0x05, /* syscall */
+        0xb8, 0x2a, 0, 0, 0, 0xc3, /* mov $42, %eax; ret */
+        0x31, 0xc0, 0xc3, /* xor %eax, %eax; ret */
+    ];
+    let symbol =
+        |b: ObjectBuilder, name: &str, section: &str, offset: u64, size: u64, symbol_type| {
+            b.add_symbol(
+                name,
+                Some(section),
+                offset,
+                size,
+                symbol_type,
+                SymbolBinding::STB_GLOBAL,
+            )
+        };
+    let b = ObjectBuilder::new()
+        .add_section(
+            ".text",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+            16,
+            code,
+        )
+        .add_section(
+            ".data",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+            4,
+            1u32.to_le_bytes().to_vec(),
+        );
+    let b = symbol(b, "_start", ".text", 0, 13, SymbolType::STT_FUNC);
+    let b = symbol(b, "value", ".text", 13, 6, SymbolType::STT_FUNC);
+    let b = symbol(b, "short", ".text", 19, 3, SymbolType::STT_FUNC);
+    let b = symbol(b, "status", ".data", 0, 4, SymbolType::STT_OBJECT);
+    let object = b
+        .add_relocation(
+            ".text",
+            "status",
+            Elf64_Rela::new(7, RelocationType::R_X86_64_PC32, 0, -4i64 as u64),
+        )
+        .build()
+        .unwrap();
+    let options = LinkOptions {
+        output_type,
+        ..Default::default()
+    };
+    let output = Linker::new(options)
+        .add_object("a.o", &object)
+        .link()
+        .unwrap();
+    reparse(&output.elf).1
+}
+
+fn address(elf: &Elf64, name: &str) -> u64 {
+    elf.symbol_by_name(name).unwrap().symbol.st_value
+}
+
+fn rip_relative(from: u64, to: u64) -> [u8; 4] {
+    (to.wrapping_sub(from) as i32).to_le_bytes()
+}
+
+/*
+ * copies the word of the injected data to status before the program
+ * starts, the addresses are those of a first injection of the same size
+ */
+fn inject_status(elf: &mut Elf64, options: &InjectOptions) -> u64 {
+    let data = 9u32.to_le_bytes();
+    let first = {
+        let (_, mut copy) = reparse(elf);
+        copy.inject(&[0x90; 12], &data, options).unwrap()
+    };
+    let mut code = vec![0x8b, 0x05]; /* mov data(%rip), %eax */
+    code.extend(rip_relative(first.code_address + 6, first.data_address));
+    code.extend([0x89, 0x05]); /* mov %eax, status(%rip) */
+    code.extend(rip_relative(
+        first.code_address + 12,
+        address(elf, "status"),
+    ));
+    let injection = elf.inject(&code, &data, options).unwrap();
+    assert_eq!(injection.code_address, first.code_address);
+    assert_eq!(injection.data_address, first.data_address);
+    injection.code_address
+}
+
+#[test]
+fn segments_are_added() {
+    let mut elf = program(OutputType::Executable);
+    let entry = elf.header.e_entry;
+    let options = InjectOptions {
+        redirect_entry: true,
+        ..Default::default()
+    };
+    let code_address = inject_status(&mut elf, &options);
+    let (_, elf) = reparse(&elf);
+    assert_eq!(elf.header.e_entry, code_address);
+    let load = |address: u64| {
+        elf.programs
+            .iter()
+            .find(|p| p.header.p_type == PType::PT_LOAD && p.header.p_vaddr == address)
+            .map(|p| p.header.p_flags)
+            .unwrap()
+    };
+    assert_eq!(load(code_address), PFlags::Read | PFlags::Execute);
+    let data_address = (code_address + 17 + 0xfff) & !0xfff;
+    assert_eq!(load(data_address), PFlags::Read | PFlags::Write);
+    let image = elf.load_image(0).unwrap();
+    assert_eq!(image.read(data_address, 4).unwrap(), 9u32.to_le_bytes());
+    /* the code ends with a jump to the old entry */
+    let jump = image.read(code_address + 12, 5).unwrap();
+    assert_eq!(jump[0], 0xe9);
+    let displacement = i32::from_le_bytes(jump[1..].try_into().unwrap()) as i64;
+    assert_eq!(code_address as i64 + 17 + displacement, entry as i64);
+}
+
+#[test]
+fn data_is_optional() {
+    let mut elf = program(OutputType::Executable);
+    let entry = elf.header.e_entry;
+    let injection = elf.inject(&[0xc3], &[], &InjectOptions::default()).unwrap();
+    assert_eq!(injection.data_address, 0);
+    assert_eq!(injection.original_entry, entry);
+    assert_eq!(elf.header.e_entry, entry);
+}
+
+#[test]
+fn notes_give_their_entry() {
+    let elf = program(OutputType::Executable);
+    let stack = elf
+        .programs
+        .iter()
+        .position(|p| p.header.p_type == PType::PT_GNU_STACK)
+        .unwrap();
+    let mut raw = elf.write(&WriteOptions::default()).unwrap();
+    let entry = elf.header.e_phoff as usize + 56 * stack;
+    let note: u32 = PType::PT_NOTE.into();
+    raw[entry..entry + 4].copy_from_slice(&note.to_le_bytes());
+    let mut elf = Elf64::from(&raw);
+    let (phoff, count) = (elf.header.e_phoff, elf.programs.len());
+    let options = InjectOptions {
+        convert_note: true,
+        ..Default::default()
+    };
+    elf.inject(&[0xc3], &[], &options).unwrap();
+    let (_, elf) = reparse(&elf);
+    assert_eq!((elf.header.e_phoff, elf.programs.len()), (phoff, count));
+    assert!(elf
+        .programs
+        .iter()
+        .all(|p| p.header.p_type != PType::PT_NOTE));
+}
+
+#[test]
+fn hooks_that_are_refused() {
+    let mut elf = program(OutputType::Executable);
+    assert!(matches!(
+        elf.hook_function("missing", 0x400000),
+        Err(ElfError::UnresolvedSymbol(n)) if n == "missing"
+    ));
+    assert!(matches!(
+        elf.hook_function("status", 0x400000),
+        Err(ElfError::UnresolvedSymbol(_))
+    ));
+    assert!(matches!(
+        elf.hook_function("short", 0x400000),
+        Err(ElfError::SizeMismatch {
+            expected: 5,
+            actual: 3
+        })
+    ));
+    assert!(matches!(
+        elf.hook_function("value", 0x7fff00000000),
+        Err(ElfError::RelocationOverflow(_))
+    ));
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn run(elf: &Elf64, name: &str) -> Option<i32> {
+    use common::temp_dir;
+    use std::os::unix::fs::PermissionsExt;
+    let path = temp_dir("inject").join(name);
+    std::fs::write(&path, reparse(elf).0).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::process::Command::new(&path).status().unwrap().code()
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn injected_code_runs_first() {
+    for (output_type, name) in [
+        (OutputType::Executable, "exec"),
+        (OutputType::StaticPie, "pie"),
+    ] {
+        let mut elf = program(output_type);
+        assert_eq!(run(&elf, name), Some(1));
+        let options = InjectOptions {
+            redirect_entry: true,
+            ..Default::default()
+        };
+        inject_status(&mut elf, &options);
+        assert_eq!(run(&elf, name), Some(9));
+    }
+}
+
+/*
+ * the injected entry calls value, whose hook returns 3
+ */
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[test]
+fn hooked_functions_jump_to_the_hook() {
+    let mut elf = program(OutputType::StaticPie);
+    let hook = elf
+        .inject(&[0xb8, 3, 0, 0, 0, 0xc3], &[], &InjectOptions::default())
+        .unwrap();
+    let replaced = elf.hook_function("value", hook.code_address).unwrap();
+    assert_eq!(replaced, vec![0xb8, 0x2a, 0, 0, 0]);
+    /* status is the result of value */
+    let options = InjectOptions {
+        redirect_entry: true,
+        ..Default::default()
+    };
+    let first = {
+        let (_, mut copy) = reparse(&elf);
+        copy.inject(&[0x90; 11], &[], &options).unwrap()
+    };
+    let mut code = vec![0xe8]; /* call value */
+    code.extend(rip_relative(first.code_address + 5, address(&elf, "value")));
+    code.extend([0x89, 0x05]); /* mov %eax, status(%rip) */
+    code.extend(rip_relative(
+        first.code_address + 11,
+        address(&elf, "status"),
+    ));
+    elf.inject(&code, &[], &options).unwrap();
+    assert_eq!(run(&elf, "hooked"), Some(3));
+}
@@ -0,0 +1,165 @@
+#![cfg(all(target_os = "linux", target_arch = "x86_64"))]
+
+use simple_elf::builder::executable::ExecutableBuilder;
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::header::e_type::EType;
+use simple_elf::jit::JitModule;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::symbol::{Elf64_Rela, RelocationType, SymbolBinding, SymbolType};
+use simple_elf::Elf64;
+
+extern "C" fn double(x: i32) -> i32 {
+    x * 2
+}
+
+fn function(b: ObjectBuilder, name: &str, section: &str, offset: u64, size: u64) -> ObjectBuilder {
+    b.add_symbol(
+        name,
+        Some(section),
+        offset,
+        size,
+        SymbolType::STT_FUNC,
+        SymbolBinding::STB_GLOBAL,
+    )
+}
+
+/*
+ * answer returns 42, twice tail calls double(21) through a plt stub,
+ * patched runs from a section that is both writable and executable and
+ * table points to answer
+ */
+fn object() -> ObjectBuilder {
+    let text = SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR;
+    let code = vec![
+        0xb8, 0x2a, 0, 0, 0, 0xc3, /* mov $42, %eax; ret */
+        0xbf, 0x15, 0, 0, 0, 0xe9, 0, 0, 0, 0, /* mov $21, %edi; jmp double */
+    ];
+    let b = ObjectBuilder::new()
+        .add_section(".text", text, 16, code)
+        .add_section(
+            ".text.patched",
+            text | SHFlags::SHF_WRITE,
+            16,
+            vec![0xb8, 7, 0, 0, 0, 0xc3],
+        )
+        .add_section(
+            ".data",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+            8,
+            vec![0; 8],
+        );
+    let b = function(b, "answer", ".text", 0, 6);
+    let b = function(b, "twice", ".text", 6, 10);
+    let b = function(b, "patched", ".text.patched", 0, 6);
+    b.add_symbol(
+        "table",
+        Some(".data"),
+        0,
+        8,
+        SymbolType::STT_OBJECT,
+        SymbolBinding::STB_GLOBAL,
+    )
+    .add_symbol(
+        "double",
+        None,
+        0,
+        0,
+        SymbolType::STT_NOTYPE,
+        SymbolBinding::STB_GLOBAL,
+    )
+    .add_relocation(
+        ".text",
+        "double",
+        Elf64_Rela::new(12, RelocationType::R_X86_64_PLT32, 0, -4i64 as u64),
+    )
+    .add_relocation(
+        ".data",
+        "answer",
+        Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 0),
+    )
+}
+
+fn resolver(name: &str) -> Option<u64> {
+    match name {
+        "double" => Some(double as extern "C" fn(i32) -> i32 as usize as u64),
+        _ => None,
+    }
+}
+
+#[test]
+fn code_runs_after_loading() {
+    let elf = object().build().unwrap();
+    let module = JitModule::load(&elf, resolver).unwrap();
+    let mut names: Vec<&str> = module.symbols().keys().map(|s| s.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["answer", "patched", "table", "twice"]);
+    let answer = module.symbol_address("answer").unwrap();
+    assert_eq!(module.symbol_address("twice"), Some(answer + 6));
+    assert_eq!(module.symbol_address("double"), None);
+    unsafe {
+        let call = |name: &str| module.function::<extern "C" fn() -> i32>(name).unwrap()();
+        assert_eq!(call("answer"), 42);
+        assert_eq!(call("twice"), 42);
+        assert_eq!(call("patched"), 7);
+        let table = module.symbol_address("table").unwrap() as *const u64;
+        assert_eq!(table.read(), answer);
+    }
+}
+
+#[test]
+fn undefined_symbols() {
+    let elf = object().build().unwrap();
+    assert!(matches!(
+        JitModule::load(&elf, |_| None),
+        Err(ElfError::UnresolvedSymbol(name)) if name == "double"
+    ));
+    /* an unresolved weak symbol is 0 */
+    let elf = object()
+        .add_symbol(
+            "optional",
+            None,
+            0,
+            0,
+            SymbolType::STT_NOTYPE,
+            SymbolBinding::STB_WEAK,
+        )
+        .add_relocation(
+            ".data",
+            "optional",
+            Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 0),
+        )
+        .build()
+        .unwrap();
+    let module = JitModule::load(&elf, resolver).unwrap();
+    let table = module.symbol_address("table").unwrap() as *const u64;
+    assert_eq!(unsafe { table.read() }, 0);
+}
+
+#[test]
+fn files_that_are_refused() {
+    let elf = object()
+        .add_relocation(
+            ".data",
+            "answer",
+            Elf64_Rela::new(4, RelocationType::R_X86_64_64, 0, 0),
+        )
+        .build()
+        .unwrap();
+    assert!(matches!(
+        JitModule::load(&elf, resolver),
+        Err(ElfError::RelocationOutOfSection(4))
+    ));
+    let executable: Elf64 = ExecutableBuilder::new()
+        .add_code(".text", 16, vec![0xc3])
+        .add_symbol("_start", ".text", 0, 1, SymbolType::STT_FUNC)
+        .build()
+        .unwrap();
+    assert!(matches!(
+        JitModule::load(&executable, resolver),
+        Err(ElfError::WrongFileType {
+            expected: EType::ET_REL,
+            actual: EType::ET_EXEC
+        })
+    ));
+}
@@ -0,0 +1,252 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::header::e_type::EType;
+use simple_elf::linker::{LinkOptions, Linker, OutputType};
+use simple_elf::section::section::SectionData;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::symbol::{Elf64_Rela, RelocationType, SymbolBinding, SymbolType};
+use simple_elf::Elf64;
+
+/* call 0; ret */
+fn text() -> ObjectBuilder {
+    ObjectBuilder::new().add_section(
+        ".text",
+        SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+        16,
+        vec![0xe8, 0, 0, 0, 0, 0xc3],
+    )
+}
+
+fn function(b: ObjectBuilder, name: &str) -> ObjectBuilder {
+    b.add_symbol(
+        name,
+        Some(".text"),
+        0,
+        6,
+        SymbolType::STT_FUNC,
+        SymbolBinding::STB_GLOBAL,
+    )
+}
+
+fn call(b: ObjectBuilder, name: &str) -> ObjectBuilder {
+    let undefined = b.add_symbol(
+        name,
+        None,
+        0,
+        0,
+        SymbolType::STT_NOTYPE,
+        SymbolBinding::STB_GLOBAL,
+    );
+    undefined.add_relocation(
+        ".text",
+        name,
+        Elf64_Rela::new(1, RelocationType::R_X86_64_PLT32, 0, -4i64 as u64),
+    )
+}
+
+fn link(output_type: OutputType, objects: &[(&str, &Elf64)]) -> Result<Elf64, ElfError> {
+    let options = LinkOptions {
+        output_type,
+        ..Default::default()
+    };
+    let linker = objects.iter().fold(Linker::new(options), |l, (name, elf)| {
+        l.add_object(name, elf)
+    });
+    linker.link().map(|output| {
+        assert!(output.warnings.is_empty(), "{:?}", output.warnings);
+        output.elf
+    })
+}
+
+fn diagnostics(result: Result<Elf64, ElfError>) -> Vec<String> {
+    match result {
+        Err(ElfError::Link(diagnostics)) => diagnostics,
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn calls_between_objects() {
+    let a = call(function(text(), "_start"), "helper").build().unwrap();
+    let b = function(text(), "helper").build().unwrap();
+    for output_type in [OutputType::Executable, OutputType::StaticPie] {
+        let (_, elf) = reparse(&link(output_type, &[("a.o", &a), ("b.o", &b)]).unwrap());
+        let address = |name: &str| elf.symbol_by_name(name).unwrap().symbol.st_value;
+        let (start, helper) = (address("_start"), address("helper"));
+        assert_eq!(elf.header.e_entry, start);
+        let code: Vec<u8> = (&elf.section_by_name(".text").unwrap().data).into();
+        let offset = (start - elf.section_by_name(".text").unwrap().header.sh_addr) as usize;
+        let displacement = i32::from_le_bytes(code[offset + 1..offset + 5].try_into().unwrap());
+        assert_eq!(start as i64 + 5 + displacement as i64, helper as i64);
+        match output_type {
+            OutputType::Executable => {
+                assert_eq!(elf.header.e_type, EType::ET_EXEC);
+                assert_eq!(start & !0xfff, 0x401000);
+            }
+            OutputType::StaticPie => assert_eq!(elf.header.e_type, EType::ET_DYN),
+        }
+    }
+}
+
+#[test]
+fn static_pie_pointers_are_relative() {
+    let a = function(text(), "_start")
+        .add_section(
+            ".data",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+            8,
+            vec![0; 8],
+        )
+        .add_relocation(
+            ".data",
+            "_start",
+            Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 2),
+        )
+        .build()
+        .unwrap();
+    let elf = link(OutputType::StaticPie, &[("a.o", &a)]).unwrap();
+    let start = elf.symbol_by_name("_start").unwrap().symbol.st_value;
+    let rela = match &elf.section_by_name(".rela.dyn").unwrap().data {
+        SectionData::Rela(r) => &r.rela_entries,
+        _ => panic!(".rela.dyn is not SHT_RELA"),
+    };
+    assert_eq!(rela.len(), 1);
+    assert_eq!(rela[0].relocation_type, RelocationType::R_X86_64_RELATIVE);
+    assert_eq!(
+        rela[0].r_offset,
+        elf.section_by_name(".data").unwrap().header.sh_addr
+    );
+    assert_eq!(rela[0].r_addend, start + 2);
+}
+
+#[test]
+fn init_arrays_sorted_by_priority() {
+    let pointer = Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 0);
+    let b = [".init_array", ".init_array.200", ".init_array.00100"]
+        .iter()
+        .fold(function(text(), "_start"), |b, name| {
+            b.add_section(name, SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE, 8, vec![0; 8])
+        });
+    let b = [("plain", 1), ("late", 2), ("early", 3)]
+        .iter()
+        .fold(b, |b, (name, offset)| {
+            b.add_symbol(
+                name,
+                Some(".text"),
+                *offset,
+                1,
+                SymbolType::STT_FUNC,
+                SymbolBinding::STB_GLOBAL,
+            )
+        });
+    let a = b
+        .add_relocation(".init_array", "plain", pointer.clone())
+        .add_relocation(".init_array.200", "late", pointer.clone())
+        .add_relocation(".init_array.00100", "early", pointer)
+        .build()
+        .unwrap();
+    let (_, elf) = reparse(&link(OutputType::Executable, &[("a.o", &a)]).unwrap());
+    assert_eq!(
+        elf.sections
+            .iter()
+            .filter(|s| s.name.starts_with(".init_array"))
+            .count(),
+        1
+    );
+    let address = |name: &str| elf.symbol_by_name(name).unwrap().symbol.st_value;
+    let order: Vec<u64> = elf.init_array().iter().map(|e| e.address).collect();
+    assert_eq!(
+        order,
+        vec![address("early"), address("late"), address("plain")]
+    );
+}
+
+#[test]
+fn diagnostics_like_ld() {
+    let a = call(function(text(), "_start"), "missing").build().unwrap();
+    let b = function(text(), "_start").build().unwrap();
+    assert_eq!(
+        diagnostics(link(OutputType::Executable, &[("a.o", &a), ("b.o", &b)])),
+        vec![
+            "b.o: in function `_start':\n(.text+0x0): multiple definition of `_start'; a.o:(.text+0x0): first defined here",
+            "a.o: in function `_start':\n(.text+0x1): undefined reference to `missing'",
+        ]
+    );
+    let absolute = function(text(), "_start")
+        .add_section(
+            ".data",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+            8,
+            vec![0; 8],
+        )
+        .add_relocation(
+            ".data",
+            "_start",
+            Elf64_Rela::new(0, RelocationType::R_X86_64_32, 0, 0),
+        )
+        .build()
+        .unwrap();
+    assert!(link(OutputType::Executable, &[("a.o", &absolute)]).is_ok());
+    assert_eq!(
+        diagnostics(link(OutputType::StaticPie, &[("a.o", &absolute)])),
+        vec!["a.o: relocation R_X86_64_32 against symbol `_start' can not be used when making a PIE object; recompile with -fPIE"]
+    );
+    assert_eq!(
+        diagnostics(link(OutputType::Executable, &[("a.o", &a)])).len(),
+        1
+    );
+    /* only relocatable objects are linked */
+    let executable = link(OutputType::Executable, &[("b.o", &b)]).unwrap();
+    assert_eq!(
+        diagnostics(link(
+            OutputType::Executable,
+            &[("b.o", &b), ("a.out", &executable)]
+        )),
+        vec!["a.out: file format not recognized"]
+    );
+}
+
+#[test]
+fn warnings() {
+    let b = function(text(), "other").build().unwrap();
+    let output = Linker::new(LinkOptions::default())
+        .add_object("b.o", &b)
+        .link()
+        .unwrap();
+    let default = output.elf.section_by_name(".text").unwrap().header.sh_addr;
+    assert_eq!(output.elf.header.e_entry, default);
+    assert_eq!(
+        output.warnings,
+        vec![format!(
+            "warning: cannot find entry symbol _start; defaulting to {:016x}",
+            default
+        )]
+    );
+    let pointer = function(text(), "_start")
+        .add_section(".rodata", SHFlags::SHF_ALLOC.into(), 8, vec![0; 8])
+        .add_relocation(
+            ".rodata",
+            "_start",
+            Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 0),
+        )
+        .build()
+        .unwrap();
+    let options = LinkOptions {
+        output_type: OutputType::StaticPie,
+        ..Default::default()
+    };
+    let output = Linker::new(options)
+        .add_object("a.o", &pointer)
+        .link()
+        .unwrap();
+    assert_eq!(
+        output.warnings,
+        vec![
+            "a.o: warning: relocation against `_start' in read-only section `.rodata'",
+            "warning: creating DT_TEXTREL in a PIE"
+        ]
+    );
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::reparse;
+use enumflags2::BitFlags;
+use simple_elf::builder::executable::ExecutableBuilder;
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::section::section::StringSection;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::sh_type::ShType;
+use simple_elf::section::symbol::{SymbolBinding, SymbolType};
+use simple_elf::writer::WriteOptions;
+use simple_elf::Elf64;
+use std::io::BufReader;
+
+fn executable(symtab: bool) -> Elf64 {
+    ExecutableBuilder::new()
+        .add_code(
+            ".text",
+            16,
+            vec![0x31, 0xff, 0xb8, 0x3c, 0, 0, 0, 0x0f, 0x05],
+        )
+        .add_symbol("_start", ".text", 0, 2, SymbolType::STT_FUNC)
+        .add_symbol("exit_now", ".text", 2, 7, SymbolType::STT_FUNC)
+        .symtab(symtab)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn symbols_from_gnu_debugdata() {
+    let mini = executable(true).write(&WriteOptions::default()).unwrap();
+    let mut compressed: Vec<u8> = Vec::new();
+    lzma_rs::xz_compress(&mut BufReader::new(mini.as_slice()), &mut compressed).unwrap();
+    let mut stripped = executable(false);
+    assert!(stripped.symbols().next().is_none());
+    stripped
+        .add_section(
+            ".gnu_debugdata",
+            ShType::SHT_PROGBITS,
+            BitFlags::empty(),
+            compressed,
+        )
+        .unwrap();
+    let (_, elf) = reparse(&stripped);
+    let mini = elf.mini_debuginfo().unwrap();
+    let exit_now = mini.symbol_by_name("exit_now").unwrap().symbol.st_value;
+    assert_eq!(
+        elf.symbol_for_address(exit_now + 3).unwrap().name(),
+        "exit_now"
+    );
+    assert_eq!(
+        elf.symbol_for_address(exit_now - 1).unwrap().name(),
+        "_start"
+    );
+}
+
+#[test]
+fn names_in_the_tail_of_another_string() {
+    let strings = StringSection::from(b"\0foo_bar\0baz\0");
+    assert_eq!(strings.get(1).as_deref(), Some("foo_bar"));
+    assert_eq!(strings.get(5).as_deref(), Some("bar"));
+    assert_eq!(strings.get(8), None);
+    assert_eq!(strings.get(11).as_deref(), Some("z"));
+    let lookup = strings.lookup();
+    for offset in 0..16 {
+        assert_eq!(lookup.get(offset), strings.get(offset));
+    }
+}
+
+#[test]
+fn symbol_names_and_types_are_decoded() {
+    let elf = ObjectBuilder::new()
+        .add_section(
+            ".text",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+            16,
+            vec![0xc3; 4],
+        )
+        .add_symbol(
+            "foo_bar",
+            Some(".text"),
+            0,
+            1,
+            SymbolType::STT_FUNC,
+            SymbolBinding::STB_GLOBAL,
+        )
+        .add_symbol(
+            "bar",
+            Some(".text"),
+            1,
+            1,
+            SymbolType::STT_OBJECT,
+            SymbolBinding::STB_WEAK,
+        )
+        .add_symbol(
+            "pick",
+            Some(".text"),
+            2,
+            2,
+            SymbolType::STT_GNU_IFUNC,
+            SymbolBinding::STB_GLOBAL,
+        )
+        .build()
+        .unwrap();
+    let (_, elf) = reparse(&elf);
+    let bar = elf.symbol_by_name("bar").unwrap();
+    let foo_bar = elf.symbol_by_name("foo_bar").unwrap();
+    /* the string table builder stores bar in the tail of foo_bar */
+    assert_eq!(bar.symbol.st_name, foo_bar.symbol.st_name + 4);
+    assert_eq!(bar.symbol.symbol_type, SymbolType::STT_OBJECT);
+    assert_eq!(bar.binding(), SymbolBinding::STB_WEAK);
+    let pick = elf.symbol_by_name("pick").unwrap();
+    assert_eq!(pick.symbol.st_info, 0x1a);
+    assert_eq!(pick.symbol.symbol_type, SymbolType::STT_GNU_IFUNC);
+    assert_eq!(pick.binding(), SymbolBinding::STB_GLOBAL);
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::object::{ObjectBuilder, ABS_SECTION, COMMON_SECTION};
+use simple_elf::error::ElfError;
+use simple_elf::header::e_type::EType;
+use simple_elf::section::section::SectionData;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::sh_type::ShType;
+use simple_elf::section::symbol::{
+    Elf64_Rela, Elf64_Sym, RelocationType, SectionIndex, SymbolBinding, SymbolType,
+};
+use simple_elf::Elf64;
+
+fn object() -> ObjectBuilder {
+    let code = SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR;
+    let data = SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE;
+    let symbol = |b: ObjectBuilder, name: &str, section, value, symbol_type, binding| {
+        b.add_symbol(name, section, value, 4, symbol_type, binding)
+    };
+    let b = ObjectBuilder::new()
+        .file_name("a.c")
+        .add_section(".text", code, 16, vec![0xc3; 8])
+        .add_section(".data", data, 8, vec![0; 16])
+        .add_nobits_section(".bss", data, 32, 64)
+        .add_section(".init_array", data, 8, vec![0; 8]);
+    let b = symbol(
+        b,
+        "main",
+        Some(".text"),
+        0,
+        SymbolType::STT_FUNC,
+        SymbolBinding::STB_GLOBAL,
+    );
+    let b = symbol(
+        b,
+        "helper",
+        Some(".text"),
+        4,
+        SymbolType::STT_FUNC,
+        SymbolBinding::STB_LOCAL,
+    );
+    let b = symbol(
+        b,
+        "weak",
+        Some(".data"),
+        8,
+        SymbolType::STT_OBJECT,
+        SymbolBinding::STB_WEAK,
+    );
+    let b = symbol(
+        b,
+        "value",
+        Some(ABS_SECTION),
+        0x1234,
+        SymbolType::STT_NOTYPE,
+        SymbolBinding::STB_GLOBAL,
+    );
+    let b = symbol(
+        b,
+        "shared",
+        Some(COMMON_SECTION),
+        16,
+        SymbolType::STT_OBJECT,
+        SymbolBinding::STB_GLOBAL,
+    );
+    b.add_relocation(
+        ".text",
+        "puts",
+        Elf64_Rela::new(1, RelocationType::R_X86_64_PLT32, 0, -4i64 as u64),
+    )
+    .add_relocation(
+        ".data",
+        ".bss",
+        Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 8),
+    )
+    .add_relocation(
+        ".init_array",
+        "helper",
+        Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 0),
+    )
+}
+
+fn symtab(elf: &Elf64) -> &Vec<Elf64_Sym> {
+    match &elf.section_by_name(".symtab").unwrap().data {
+        SectionData::Symbol(s) => &s.symbols,
+        _ => panic!(".symtab is not a symbol table"),
+    }
+}
+
+fn relocations<'a>(elf: &'a Elf64, name: &str) -> &'a Vec<Elf64_Rela> {
+    match &elf.section_by_name(name).unwrap().data {
+        SectionData::Rela(r) => &r.rela_entries,
+        _ => panic!("{} is not SHT_RELA", name),
+    }
+}
+
+#[test]
+fn sections_in_the_order_of_gas() {
+    let (_, elf) = reparse(&object().build().unwrap());
+    assert_eq!(elf.header.e_type, EType::ET_REL);
+    let names: Vec<&str> = elf.sections.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "",
+            ".text",
+            ".rela.text",
+            ".data",
+            ".rela.data",
+            ".bss",
+            ".init_array",
+            ".rela.init_array",
+            ".note.GNU-stack",
+            ".symtab",
+            ".strtab",
+            ".shstrtab"
+        ]
+    );
+    let section = |name: &str| &elf.section_by_name(name).unwrap().header;
+    assert_eq!(section(".bss").sh_type, ShType::SHT_NOBITS);
+    assert_eq!(
+        (section(".bss").sh_size, section(".bss").sh_addralign),
+        (64, 32)
+    );
+    assert_eq!(section(".init_array").sh_type, ShType::SHT_INIT_ARRAY);
+    let rela = section(".rela.data");
+    assert_eq!(rela.sh_type, ShType::SHT_RELA);
+    assert_eq!(rela.sh_info as usize, elf.section_index(".data").unwrap());
+    assert_eq!(rela.sh_link as usize, elf.section_index(".symtab").unwrap());
+    assert!(rela.sh_flags.contains(SHFlags::SHF_INFO_LINK));
+    let data: Vec<u8> = (&elf.section_by_name(".text").unwrap().data).into();
+    assert_eq!(data, vec![0xc3; 8]);
+}
+
+#[test]
+fn symbols_and_relocations() {
+    let (_, elf) = reparse(&object().build().unwrap());
+    let symbols = symtab(&elf);
+    assert_eq!(symbols[1].symbol_type, SymbolType::STT_FILE);
+    assert_eq!(symbols[1].name(), "a.c");
+    /* one STT_SECTION symbol per added section, then the locals */
+    let sections = symbols
+        .iter()
+        .filter(|s| s.symbol_type == SymbolType::STT_SECTION)
+        .count();
+    assert_eq!(sections, 5);
+    let first_global = elf.section_by_name(".symtab").unwrap().header.sh_info as usize;
+    assert!(symbols[..first_global]
+        .iter()
+        .all(|s| s.symbol_binding == SymbolBinding::STB_LOCAL));
+    let globals: Vec<&str> = symbols[first_global..].iter().map(|s| s.name()).collect();
+    assert_eq!(globals, vec!["main", "weak", "value", "shared", "puts"]);
+    let symbol = |name: &str| elf.symbol_by_name(name).unwrap();
+    assert_eq!(symbol("value").section_index(), SectionIndex::SHN_ABS);
+    assert_eq!(symbol("value").symbol.st_value, 0x1234);
+    assert_eq!(symbol("shared").section_index(), SectionIndex::SHN_COMMON);
+    assert_eq!(symbol("weak").binding(), SymbolBinding::STB_WEAK);
+    assert!(!symbol("puts").is_defined());
+    let index = |name: &str| symbols.iter().position(|s| s.name() == name).unwrap() as u32;
+    let text = relocations(&elf, ".rela.text");
+    assert_eq!(text[0].symbol_index, index("puts"));
+    assert_eq!(text[0].relocation_type, RelocationType::R_X86_64_PLT32);
+    assert_eq!(text[0].r_addend, -4i64 as u64);
+    /* relocations against a section name use its STT_SECTION symbol */
+    let data = &relocations(&elf, ".rela.data")[0];
+    let bss = &symbols[data.symbol_index as usize];
+    assert_eq!(bss.symbol_type, SymbolType::STT_SECTION);
+    assert_eq!(bss.st_shndx as usize, elf.section_index(".bss").unwrap());
+    assert_eq!(data.r_addend, 8);
+    assert_eq!(
+        relocations(&elf, ".rela.init_array")[0].symbol_index,
+        index("helper")
+    );
+}
+
+#[test]
+fn invalid_objects() {
+    let duplicate = object().add_symbol(
+        "main",
+        Some(".data"),
+        0,
+        0,
+        SymbolType::STT_OBJECT,
+        SymbolBinding::STB_GLOBAL,
+    );
+    assert!(matches!(duplicate.build(), Err(ElfError::DuplicateSymbol(n)) if n == "main"));
+    let missing = object().add_symbol(
+        "nowhere",
+        Some(".rodata"),
+        0,
+        0,
+        SymbolType::STT_OBJECT,
+        SymbolBinding::STB_GLOBAL,
+    );
+    assert!(matches!(missing.build(), Err(ElfError::UnknownSection(n)) if n == ".rodata"));
+    let missing = object().add_relocation(
+        ".rodata",
+        "main",
+        Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 0),
+    );
+    assert!(matches!(missing.build(), Err(ElfError::UnknownSection(n)) if n == ".rodata"));
+}
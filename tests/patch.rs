@@ -0,0 +1,242 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::executable::ExecutableBuilder;
+use simple_elf::builder::shared::SharedLibraryBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::program::p_type::PType;
+use simple_elf::program::program_header::PFlags;
+use simple_elf::section::elf64_dyn::DynamicTag;
+use simple_elf::section::symbol::{SymbolBinding, SymbolType, SymbolVisibility};
+use simple_elf::writer::WriteOptions;
+use simple_elf::Elf64;
+
+const INTERPRETER: &str = "/lib/ld.so";
+
+fn library() -> Elf64 {
+    SharedLibraryBuilder::new()
+        .soname("libpatch.so")
+        .needed("libc.so.6")
+        .add_code(".text", 16, vec![0xb8, 0x2a, 0, 0, 0, 0xc3])
+        .add_symbol(
+            "answer",
+            ".text",
+            0,
+            6,
+            SymbolType::STT_FUNC,
+            SymbolBinding::STB_GLOBAL,
+            SymbolVisibility::STV_DEFAULT,
+        )
+        .build()
+        .unwrap()
+}
+
+/*
+ * the edits have to survive writing the file out
+ */
+fn edited(elf: &mut Elf64, edit: impl FnOnce(&mut Elf64) -> Result<(), ElfError>) -> Elf64 {
+    edit(elf).unwrap();
+    let (_, elf) = reparse(elf);
+    assert!(elf.load_image(0x7f0000000000).is_ok());
+    assert_eq!(
+        elf.symbol_by_name("answer").map(|s| s.name().to_string()),
+        Some("answer".to_string())
+    );
+    elf
+}
+
+#[test]
+fn needed_libraries() {
+    let mut elf = library();
+    assert_eq!(elf.needed(), vec!["libc.so.6"]);
+    elf = edited(&mut elf, |e| e.add_needed("libm.so.6"));
+    assert_eq!(elf.needed(), vec!["libc.so.6", "libm.so.6"]);
+    elf = edited(&mut elf, |e| e.add_needed("libm.so.6"));
+    assert_eq!(elf.needed().len(), 2);
+    /* more entries than the table has room for move it to a writable place */
+    let dynamic = |elf: &Elf64| {
+        elf.programs
+            .iter()
+            .find(|p| p.header.p_type == PType::PT_DYNAMIC)
+            .unwrap()
+            .header
+            .clone()
+    };
+    let before = dynamic(&elf);
+    for i in 0..16 {
+        elf = edited(&mut elf, |e| e.add_needed(&format!("libextra{}.so", i)));
+    }
+    assert_eq!(elf.needed().len(), 18);
+    let after = dynamic(&elf);
+    assert_ne!(after.p_vaddr, before.p_vaddr);
+    assert!(after.p_filesz > before.p_filesz);
+    let image = elf.load_image(0).unwrap();
+    assert_eq!(
+        image.permissions(after.p_vaddr),
+        Some(PFlags::Read | PFlags::Write)
+    );
+    elf = edited(&mut elf, |e| e.replace_needed("libm.so.6", "libdl.so.2"));
+    assert_eq!(elf.needed()[..2], ["libc.so.6", "libdl.so.2"]);
+    for i in 0..16 {
+        elf = edited(&mut elf, |e| e.remove_needed(&format!("libextra{}.so", i)));
+    }
+    assert_eq!(elf.needed(), vec!["libc.so.6", "libdl.so.2"]);
+    assert!(matches!(
+        elf.remove_needed("libz.so.1"),
+        Err(ElfError::UnknownNeeded(n)) if n == "libz.so.1"
+    ));
+    assert!(matches!(
+        elf.replace_needed("libz.so.1", "libc.so.6"),
+        Err(ElfError::UnknownNeeded(_))
+    ));
+}
+
+#[test]
+fn soname_and_search_paths() {
+    let mut elf = library();
+    elf = edited(&mut elf, |e| {
+        e.set_soname("libpatch-with-a-much-longer-name.so.1")
+    });
+    assert_eq!(
+        elf.soname().as_deref(),
+        Some("libpatch-with-a-much-longer-name.so.1")
+    );
+    elf = edited(&mut elf, |e| e.set_rpath("$ORIGIN/lib"));
+    assert_eq!(elf.rpath().as_deref(), Some("$ORIGIN/lib"));
+    assert_eq!(elf.runpath(), None);
+    /* DT_RUNPATH takes the place of DT_RPATH */
+    elf = edited(&mut elf, |e| e.set_runpath("/opt/lib"));
+    assert_eq!(
+        (elf.rpath(), elf.runpath().as_deref()),
+        (None, Some("/opt/lib"))
+    );
+    let count = |elf: &Elf64, tag| {
+        elf.dynamic()
+            .unwrap()
+            .entries
+            .iter()
+            .filter(|e| e.d_tag == tag)
+            .count()
+    };
+    assert_eq!(count(&elf, DynamicTag::DT_RUN_PATH), 1);
+    assert_eq!(count(&elf, DynamicTag::DT_RPATH), 0);
+    elf = edited(&mut elf, |e| e.remove_rpath());
+    assert_eq!((elf.rpath(), elf.runpath()), (None, None));
+    assert_eq!(elf.needed(), vec!["libc.so.6"]);
+}
+
+/*
+ * a static executable whose PT_GNU_STACK is turned into a PT_INTERP
+ * naming the path in .rodata
+ */
+fn with_interpreter() -> Elf64 {
+    let mut path = INTERPRETER.as_bytes().to_vec();
+    path.push(0);
+    let elf = ExecutableBuilder::new()
+        .add_code(".text", 16, vec![0xc3])
+        .add_rodata(".rodata", 1, path.clone())
+        .add_symbol("_start", ".text", 0, 1, SymbolType::STT_FUNC)
+        .build()
+        .unwrap();
+    let rodata = &elf.section_by_name(".rodata").unwrap().header;
+    let stack = elf
+        .programs
+        .iter()
+        .position(|p| p.header.p_type == PType::PT_GNU_STACK)
+        .unwrap();
+    let mut raw = elf.write(&WriteOptions::default()).unwrap();
+    let entry = elf.header.e_phoff as usize + 56 * stack;
+    let interp: u32 = PType::PT_INTERP.into();
+    raw[entry..entry + 4].copy_from_slice(&interp.to_le_bytes());
+    let fields = [
+        rodata.sh_offset,
+        rodata.sh_addr,
+        rodata.sh_addr,
+        rodata.sh_size,
+        rodata.sh_size,
+        1,
+    ];
+    for (i, value) in fields.iter().enumerate() {
+        raw[entry + 8 + 8 * i..entry + 16 + 8 * i].copy_from_slice(&value.to_le_bytes());
+    }
+    Elf64::from(&raw)
+}
+
+#[test]
+fn interpreter() {
+    let mut elf = with_interpreter();
+    assert_eq!(elf.interpreter().as_deref(), Some(INTERPRETER));
+    let interp = |elf: &Elf64| {
+        elf.programs
+            .iter()
+            .find(|p| p.header.p_type == PType::PT_INTERP)
+            .unwrap()
+            .header
+            .clone()
+    };
+    let before = interp(&elf);
+    /* a shorter path is written in place */
+    elf.set_interpreter("/lib/a.so").unwrap();
+    let (_, mut elf) = reparse(&elf);
+    assert_eq!(elf.interpreter().as_deref(), Some("/lib/a.so"));
+    assert_eq!(interp(&elf).p_vaddr, before.p_vaddr);
+    assert_eq!(interp(&elf).p_filesz, 10);
+    let long = "/lib64/ld-linux-x86-64.so.2";
+    elf.set_interpreter(long).unwrap();
+    let (_, elf) = reparse(&elf);
+    assert_eq!(elf.interpreter().as_deref(), Some(long));
+    let moved = interp(&elf);
+    assert_ne!(moved.p_vaddr, before.p_vaddr);
+    assert_eq!(moved.p_filesz, long.len() as u64 + 1);
+    /* the new place is mapped */
+    let image = elf.load_image(0).unwrap();
+    assert_eq!(
+        image.read(moved.p_vaddr, long.len()),
+        Some(long.as_bytes().to_vec())
+    );
+}
+
+#[test]
+fn files_without_the_tables() {
+    let mut executable = ExecutableBuilder::new()
+        .add_code(".text", 16, vec![0xc3])
+        .add_symbol("_start", ".text", 0, 1, SymbolType::STT_FUNC)
+        .build()
+        .unwrap();
+    assert_eq!(executable.interpreter(), None);
+    assert!(executable.needed().is_empty());
+    assert!(matches!(
+        executable.set_interpreter(INTERPRETER),
+        Err(ElfError::UnknownSection(n)) if n == ".interp"
+    ));
+    assert!(matches!(
+        executable.add_needed("libc.so.6"),
+        Err(ElfError::UnknownSection(n)) if n == ".dynamic"
+    ));
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64", target_env = "gnu"))]
+#[test]
+fn loaded_after_editing() {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+    let mut elf = library();
+    for i in 0..16 {
+        elf.set_soname(&format!("libpatched-{}-with-a-long-name.so", i))
+            .unwrap();
+    }
+    elf.add_needed("libm.so.6").unwrap();
+    elf.set_runpath("$ORIGIN").unwrap();
+    let dir = common::temp_dir("patch");
+    let path = dir.join("libpatched.so");
+    std::fs::write(&path, reparse(&elf).0).unwrap();
+    let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    unsafe {
+        let handle = libc::dlopen(path.as_ptr(), libc::RTLD_NOW);
+        assert!(!handle.is_null(), "{:?}", CStr::from_ptr(libc::dlerror()));
+        let answer = libc::dlsym(handle, c"answer".as_ptr());
+        let answer = std::mem::transmute::<*mut libc::c_void, extern "C" fn() -> i32>(answer);
+        assert_eq!(answer(), 42);
+        libc::dlclose(handle);
+    }
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::shared::SharedLibraryBuilder;
+use simple_elf::plt::PltKind;
+use simple_elf::program::p_type::PType;
+use simple_elf::section::elf64_dyn::DynamicTag;
+use simple_elf::section::symbol::{Elf64_Rela, RelocationType};
+use simple_elf::writer::WriteOptions;
+use simple_elf::Elf64;
+
+/*
+ * library calling puts through a .plt.got stub: jmp *puts@GOT(%rip)
+ * padded to 16 bytes
+ */
+fn library() -> Elf64 {
+    let mut stub = vec![0xff, 0x25, 0, 0, 0, 0];
+    stub.resize(16, 0x90);
+    SharedLibraryBuilder::new()
+        .soname("libcall.so")
+        .needed("libc.so.6")
+        .add_code(".plt.got", 16, stub)
+        .add_data(".got", 8, vec![0; 8])
+        .add_relocation(
+            ".plt.got",
+            ".got",
+            Elf64_Rela::new(2, RelocationType::R_X86_64_PC32, 0, -4i64 as u64),
+        )
+        .add_relocation(
+            ".got",
+            "puts",
+            Elf64_Rela::new(0, RelocationType::R_X86_64_GLOB_DAT, 0, 0),
+        )
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn stubs_and_slots_of_imports() {
+    let (_, elf) = reparse(&library());
+    let got = elf.section_by_name(".got").unwrap().header.sh_addr;
+    let plt = elf.section_by_name(".plt.got").unwrap().header.sh_addr;
+    let slots = elf.got_slots();
+    assert_eq!(slots.len(), 1);
+    assert_eq!(slots[0].address, got);
+    assert_eq!(slots[0].relocation_type, RelocationType::R_X86_64_GLOB_DAT);
+    assert_eq!(slots[0].symbol.as_deref(), Some("puts"));
+    let stubs = elf.plt_stubs();
+    assert_eq!(stubs.len(), 1);
+    assert_eq!(stubs[0].address, plt);
+    assert_eq!(stubs[0].kind, PltKind::PltGot);
+    assert_eq!(stubs[0].got_slot, Some(got));
+    assert_eq!(stubs[0].name().as_deref(), Some("puts@plt"));
+    assert_eq!(
+        elf.plt_symbol_for_address(plt + 7).as_deref(),
+        Some("puts@plt")
+    );
+    assert_eq!(elf.plt_symbol_for_address(plt + 16), None);
+}
+
+/*
+ * without section headers the plt is found through DT_X86_64_PLT, its
+ * tags replace the hash tables the stubs do not need
+ */
+fn without_section_headers(entsize: u64) -> Elf64 {
+    let elf = library();
+    let plt = &elf.section_by_name(".plt.got").unwrap().header;
+    let dynamic = elf
+        .programs
+        .iter()
+        .find(|p| p.header.p_type == PType::PT_DYNAMIC)
+        .unwrap()
+        .header
+        .p_offset as usize;
+    let replaced = [
+        (DynamicTag::DT_HASH, DynamicTag::DT_X86_64_PLT, plt.sh_addr),
+        (
+            DynamicTag::DT_GNU_HASH,
+            DynamicTag::DT_X86_64_PLTSZ,
+            plt.sh_size,
+        ),
+        (DynamicTag::DT_SYMENT, DynamicTag::DT_X86_64_PLTENT, entsize),
+    ];
+    let entries = elf.dynamic().unwrap().entries;
+    let mut raw = elf.write(&WriteOptions::default()).unwrap();
+    for (old, new, value) in replaced {
+        let i = entries.iter().position(|e| e.d_tag == old).unwrap();
+        let tag: u64 = new.into();
+        raw[dynamic + 16 * i..dynamic + 16 * i + 8].copy_from_slice(&tag.to_le_bytes());
+        raw[dynamic + 16 * i + 8..dynamic + 16 * i + 16].copy_from_slice(&value.to_le_bytes());
+    }
+    /* e_shoff, e_shnum and e_shstrndx */
+    raw[0x28..0x30].fill(0);
+    raw[0x3c..0x40].fill(0);
+    Elf64::from(&raw)
+}
+
+#[test]
+fn stubs_through_dynamic_tags() {
+    for entsize in [16, 0] {
+        let elf = without_section_headers(entsize);
+        assert!(elf.sections.is_empty());
+        let stubs = elf.plt_stubs();
+        assert_eq!(stubs.len(), 1);
+        assert_eq!(stubs[0].size, 16);
+        assert_eq!(stubs[0].kind, PltKind::Plt);
+        assert_eq!(stubs[0].name().as_deref(), Some("puts@plt"));
+        assert_eq!(elf.got_slots()[0].symbol.as_deref(), Some("puts"));
+    }
+}
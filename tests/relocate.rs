@@ -0,0 +1,190 @@
+use simple_elf::builder::shared::SharedLibraryBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::program::p_type::PType;
+use simple_elf::relocate::ResolveRequest;
+use simple_elf::section::elf64_dyn::DynamicTag;
+use simple_elf::section::symbol::{
+    Elf64_Rela, RelocationType, SymbolBinding, SymbolType, SymbolVisibility,
+};
+use simple_elf::writer::WriteOptions;
+use simple_elf::Elf64;
+
+const BASE: u64 = 0x7f0000000000;
+
+/*
+ * .data holds function + 4, puts, a free slot and 0x1234, .got the
+ * address of select
+ */
+fn library() -> Elf64 {
+    let absolute =
+        |offset: u64, addend: u64| Elf64_Rela::new(offset, RelocationType::R_X86_64_64, 0, addend);
+    let mut data = vec![0u8; 32];
+    data[24..32].copy_from_slice(&0x1234u64.to_le_bytes());
+    SharedLibraryBuilder::new()
+        .soname("libreloc.so")
+        .needed("libc.so.6")
+        .add_code(".text", 16, vec![0xc3; 16])
+        .add_data(".data", 8, data)
+        .add_data(".got", 8, vec![0; 8])
+        .add_symbol(
+            "function",
+            ".text",
+            0,
+            8,
+            SymbolType::STT_FUNC,
+            SymbolBinding::STB_GLOBAL,
+            SymbolVisibility::STV_DEFAULT,
+        )
+        .add_relocation(".data", "function", absolute(0, 4))
+        .add_relocation(".data", "puts", absolute(8, 0))
+        .add_relocation(
+            ".got",
+            "select",
+            Elf64_Rela::new(0, RelocationType::R_X86_64_GLOB_DAT, 0, 0),
+        )
+        .build()
+        .unwrap()
+}
+
+fn address(elf: &Elf64, name: &str) -> u64 {
+    elf.section_by_name(name).unwrap().header.sh_addr
+}
+
+#[test]
+fn relocations_with_a_resolver() {
+    let elf = library();
+    let mut image = elf.load_image(BASE).unwrap();
+    let mut asked: Vec<(String, RelocationType)> = Vec::new();
+    let report = elf
+        .apply_dynamic_relocations(&mut image, BASE, |request| match request {
+            ResolveRequest::Symbol {
+                name,
+                relocation_type,
+                ..
+            } => {
+                asked.push((name.to_string(), *relocation_type));
+                Some(0x1000 * (asked.len() as u64))
+            }
+            ResolveRequest::TlsBlockOffset => None,
+        })
+        .unwrap();
+    assert_eq!(report.applied, 3);
+    assert!(report.pending.is_empty());
+    assert_eq!(
+        asked,
+        vec![
+            ("puts".to_string(), RelocationType::R_X86_64_64),
+            ("select".to_string(), RelocationType::R_X86_64_GLOB_DAT)
+        ]
+    );
+    let (text, data) = (address(&elf, ".text"), address(&elf, ".data"));
+    /* the R_X86_64_RELATIVE of function + 4 */
+    assert_eq!(image.read_u64(BASE + data), Some(BASE + text + 4));
+    assert_eq!(image.read_u64(BASE + data + 8), Some(0x1000));
+    assert_eq!(image.read_u64(BASE + address(&elf, ".got")), Some(0x2000));
+    /* the writable .dynamic is rebased */
+    let dynamic = address(&elf, ".dynamic");
+    let entries = elf.dynamic().unwrap().entries;
+    let strtab = entries
+        .iter()
+        .position(|e| e.d_tag == DynamicTag::DT_STRTAB)
+        .unwrap() as u64;
+    assert_eq!(
+        image.read_u64(BASE + dynamic + 16 * strtab + 8),
+        Some(BASE + address(&elf, ".dynstr"))
+    );
+}
+
+#[test]
+fn unresolved_imports() {
+    let elf = library();
+    let mut image = elf.load_image(BASE).unwrap();
+    let result = elf.apply_dynamic_relocations(&mut image, BASE, |_| None);
+    assert!(matches!(result, Err(ElfError::UnresolvedSymbol(name)) if name == "puts"));
+}
+
+/*
+ * select becomes an STT_GNU_IFUNC defined at function and DT_RELR, in
+ * place of the hash tables, relocates the last slot of .data
+ */
+fn with_ifunc_and_relr() -> Elf64 {
+    let elf = library();
+    let (text, data) = (address(&elf, ".text"), address(&elf, ".data"));
+    let text_index = elf.section_index(".text").unwrap() as u16;
+    let dynsym = &elf.section_by_name(".dynsym").unwrap().header;
+    let dynstr = &elf.section_by_name(".dynstr").unwrap().header;
+    let data_offset = elf.section_by_name(".data").unwrap().header.sh_offset as usize;
+    let dynamic = elf
+        .programs
+        .iter()
+        .find(|p| p.header.p_type == PType::PT_DYNAMIC)
+        .unwrap()
+        .header
+        .p_offset as usize;
+    let entries = elf.dynamic().unwrap().entries;
+    let mut raw = elf.write(&WriteOptions::default()).unwrap();
+    let symbols = dynsym.sh_offset as usize..(dynsym.sh_offset + dynsym.sh_size) as usize;
+    let select = symbols
+        .step_by(24)
+        .find(|s| {
+            let name = u32::from_le_bytes(raw[*s..*s + 4].try_into().unwrap()) as usize;
+            raw[dynstr.sh_offset as usize + name..].starts_with(b"select\0")
+        })
+        .unwrap();
+    /* STB_GLOBAL and STT_GNU_IFUNC */
+    raw[select + 4] = 0x1a;
+    raw[select + 6..select + 8].copy_from_slice(&text_index.to_le_bytes());
+    raw[select + 8..select + 16].copy_from_slice(&text.to_le_bytes());
+    /* one RELR entry in the free slot */
+    raw[data_offset + 16..data_offset + 24].copy_from_slice(&(data + 24).to_le_bytes());
+    let replaced = [
+        (DynamicTag::DT_HASH, DynamicTag::DT_RELR, data + 16),
+        (DynamicTag::DT_GNU_HASH, DynamicTag::DT_RELRSZ, 8),
+    ];
+    for (old, new, value) in replaced {
+        let i = entries.iter().position(|e| e.d_tag == old).unwrap();
+        let tag: u64 = new.into();
+        raw[dynamic + 16 * i..dynamic + 16 * i + 8].copy_from_slice(&tag.to_le_bytes());
+        raw[dynamic + 16 * i + 8..dynamic + 16 * i + 16].copy_from_slice(&value.to_le_bytes());
+    }
+    Elf64::from(&raw)
+}
+
+#[test]
+fn ifunc_references_are_pending() {
+    let elf = with_ifunc_and_relr();
+    let (text, data, got) = (
+        address(&elf, ".text"),
+        address(&elf, ".data"),
+        address(&elf, ".got"),
+    );
+    let mut image = elf.load_image(BASE).unwrap();
+    let report = elf
+        .apply_dynamic_relocations(&mut image, BASE, |request| match request {
+            ResolveRequest::Symbol { name: "puts", .. } => Some(0x1000),
+            _ => None,
+        })
+        .unwrap();
+    /* the RELR entry, the R_X86_64_RELATIVE and puts */
+    assert_eq!(report.applied, 3);
+    assert_eq!(image.read_u64(BASE + data + 24), Some(BASE + 0x1234));
+    assert_eq!(report.pending.len(), 1);
+    let pending = &report.pending[0];
+    assert_eq!(pending.place, BASE + got);
+    assert_eq!(pending.relocation_type, RelocationType::R_X86_64_GLOB_DAT);
+    assert_eq!(pending.symbol.as_deref(), Some("select"));
+    /* the address of the resolver function */
+    assert_eq!(pending.value, BASE + text);
+    assert_eq!(image.read_u64(BASE + got), Some(0));
+}
+
+#[test]
+fn ifunc_references_given_by_the_resolver() {
+    let elf = with_ifunc_and_relr();
+    let mut image = elf.load_image(BASE).unwrap();
+    let report = elf
+        .apply_dynamic_relocations(&mut image, BASE, |_| Some(0x5000))
+        .unwrap();
+    assert!(report.pending.is_empty());
+    assert_eq!(image.read_u64(BASE + address(&elf, ".got")), Some(0x5000));
+}
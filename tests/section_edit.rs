@@ -0,0 +1,211 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::executable::ExecutableBuilder;
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::section::section::SectionData;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::sh_type::ShType;
+use simple_elf::section::symbol::{Elf64_Rela, RelocationType, SymbolBinding, SymbolType};
+use simple_elf::Elf64;
+
+/*
+ * .data points to main, .rodata holds the string of .text
+ */
+fn object() -> Elf64 {
+    let symbol = |b: ObjectBuilder, name: &str, section: &str, symbol_type| {
+        b.add_symbol(
+            name,
+            Some(section),
+            0,
+            4,
+            symbol_type,
+            SymbolBinding::STB_GLOBAL,
+        )
+    };
+    let b = ObjectBuilder::new()
+        .add_section(
+            ".text",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+            16,
+            vec![0x48, 0x8d, 0x3d, 0, 0, 0, 0, 0xc3],
+        )
+        .add_section(
+            ".data",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE,
+            8,
+            vec![0; 8],
+        )
+        .add_section(".rodata", SHFlags::SHF_ALLOC.into(), 1, b"hi\0".to_vec());
+    let b = symbol(b, "main", ".text", SymbolType::STT_FUNC);
+    let b = symbol(b, "counter", ".data", SymbolType::STT_OBJECT);
+    let b = symbol(b, "greeting", ".rodata", SymbolType::STT_OBJECT);
+    b.add_relocation(
+        ".data",
+        "main",
+        Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 0),
+    )
+    .add_relocation(
+        ".text",
+        "greeting",
+        Elf64_Rela::new(3, RelocationType::R_X86_64_PC32, 0, -4i64 as u64),
+    )
+    .build()
+    .unwrap()
+}
+
+fn names(elf: &Elf64) -> Vec<&str> {
+    elf.sections.iter().map(|s| s.name.as_str()).collect()
+}
+
+#[test]
+fn remove_sections() {
+    let mut elf = object();
+    let data = elf.section_index(".data").unwrap();
+    elf.remove_section(data).unwrap();
+    let (_, elf) = reparse(&elf);
+    assert!(!names(&elf).contains(&".data"));
+    assert!(!names(&elf).contains(&".rela.data"));
+    assert_eq!(elf.header.e_shnum as usize, elf.sections.len());
+    assert_eq!(
+        elf.sections[elf.header.e_shstrndx as usize].name,
+        ".shstrtab"
+    );
+    /* the symbols of the sections after it still point to them */
+    let symtab = elf.section_index(".symtab").unwrap();
+    let rela = &elf.section_by_name(".rela.text").unwrap().header;
+    assert_eq!(rela.sh_link as usize, symtab);
+    assert_eq!(rela.sh_info as usize, elf.section_index(".text").unwrap());
+    let greeting = elf.symbol_by_name("greeting").unwrap();
+    assert_eq!(
+        greeting.symbol.st_shndx as usize,
+        elf.section_index(".rodata").unwrap()
+    );
+    assert!(!elf.symbol_by_name("counter").unwrap().is_defined());
+}
+
+#[test]
+fn sections_that_cannot_be_removed() {
+    let mut elf = object();
+    let rodata = elf.section_index(".rodata").unwrap();
+    assert!(matches!(
+        elf.remove_section(rodata),
+        Err(ElfError::SymbolInRemovedSection(s)) if s == "greeting"
+    ));
+    let shstrndx = elf.header.e_shstrndx as usize;
+    assert!(matches!(
+        elf.remove_section(shstrndx),
+        Err(ElfError::InvalidSection(i)) if i == shstrndx
+    ));
+    assert!(matches!(
+        elf.remove_section(0),
+        Err(ElfError::InvalidSection(0))
+    ));
+    let count = elf.sections.len();
+    assert!(matches!(
+        elf.rename_section(count, ".x"),
+        Err(ElfError::InvalidSection(i)) if i == count
+    ));
+    assert_eq!(elf.sections.len(), count);
+}
+
+#[test]
+fn rename_and_set_flags() {
+    let mut elf = object();
+    let text = elf.section_index(".text").unwrap();
+    elf.rename_section(text, ".text.startup").unwrap();
+    elf.set_section_flags(text, SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE)
+        .unwrap();
+    let (_, elf) = reparse(&elf);
+    assert_eq!(elf.sections[text].name, ".text.startup");
+    assert!(names(&elf).contains(&".rela.text.startup"));
+    assert!(!names(&elf).contains(&".rela.text"));
+    assert_eq!(
+        elf.sections[text].header.sh_flags,
+        SHFlags::SHF_ALLOC | SHFlags::SHF_WRITE
+    );
+}
+
+#[test]
+fn add_sections() {
+    let mut elf = object();
+    let index = elf
+        .add_section(
+            ".comment",
+            ShType::SHT_PROGBITS,
+            SHFlags::SHF_MERGE | SHFlags::SHF_STRINGS,
+            b"simple_elf\0".to_vec(),
+        )
+        .unwrap();
+    assert_eq!(elf.header.e_shnum as usize, elf.sections.len());
+    let (_, elf) = reparse(&elf);
+    assert_eq!(elf.sections[index].name, ".comment");
+    let data: Vec<u8> = (&elf.sections[index].data).into();
+    assert_eq!(data, b"simple_elf\0");
+    /* a file without section headers gets the null section and .shstrtab */
+    let mut executable = ExecutableBuilder::new()
+        .section_headers(false)
+        .add_code(".text", 16, vec![0xc3])
+        .add_symbol("_start", ".text", 0, 1, SymbolType::STT_FUNC)
+        .build()
+        .unwrap();
+    let index = executable
+        .add_section(
+            ".note.tag",
+            ShType::SHT_NOTE,
+            Default::default(),
+            vec![0; 12],
+        )
+        .unwrap();
+    let (_, executable) = reparse(&executable);
+    assert_eq!(index, 2);
+    assert_eq!(names(&executable), vec!["", ".shstrtab", ".note.tag"]);
+    assert_eq!(executable.header.e_shstrndx, 1);
+}
+
+#[test]
+fn update_sections() {
+    let mut elf = object();
+    let rodata = elf.section_index(".rodata").unwrap();
+    elf.update_section(rodata, b"hello\0".to_vec()).unwrap();
+    /* symbol names are taken from the updated string table */
+    let strtab = elf.section_index(".strtab").unwrap();
+    let mut strings: Vec<u8> = (&elf.sections[strtab].data).into();
+    let at = strings.windows(4).position(|w| w == b"main").unwrap();
+    strings[at..at + 4].copy_from_slice(b"MAIN");
+    elf.update_section(strtab, strings).unwrap();
+    let (_, elf) = reparse(&elf);
+    let data: Vec<u8> = (&elf.sections[rodata].data).into();
+    assert_eq!(data, b"hello\0");
+    assert!(elf.symbol_by_name("MAIN").is_some());
+    assert!(elf.symbol_by_name("main").is_none());
+    assert!(matches!(
+        &elf.sections[elf.section_index(".symtab").unwrap()].data,
+        SectionData::Symbol(_)
+    ));
+    /* loaded sections keep their size */
+    let mut executable = ExecutableBuilder::new()
+        .add_code(".text", 16, vec![0xc3; 4])
+        .add_bss(".bss", 8, 8)
+        .add_symbol("_start", ".text", 0, 1, SymbolType::STT_FUNC)
+        .build()
+        .unwrap();
+    let text = executable.section_index(".text").unwrap();
+    assert!(matches!(
+        executable.update_section(text, vec![0x90; 5]),
+        Err(ElfError::SizeMismatch {
+            expected: 4,
+            actual: 5
+        })
+    ));
+    executable.update_section(text, vec![0x90; 4]).unwrap();
+    let bss = executable.section_index(".bss").unwrap();
+    assert!(matches!(
+        executable.update_section(bss, vec![0; 8]),
+        Err(ElfError::InvalidSection(i)) if i == bss
+    ));
+    let (_, executable) = reparse(&executable);
+    let data: Vec<u8> = (&executable.sections[text].data).into();
+    assert_eq!(data, vec![0x90; 4]);
+}
@@ -0,0 +1,76 @@
+use simple_elf::builder::executable::ExecutableBuilder;
+use simple_elf::program::p_type::PType;
+use simple_elf::section::sh_type::ShType;
+use simple_elf::section::symbol::SymbolType;
+use simple_elf::segment_map::section_in_segment;
+use simple_elf::Elf64;
+
+fn executable() -> Elf64 {
+    ExecutableBuilder::new()
+        .add_code(".text", 16, vec![0xc3; 32])
+        .add_rodata(".rodata", 8, vec![1; 8])
+        .add_data(".data", 8, vec![0; 16])
+        .add_bss(".bss", 8, 64)
+        .add_symbol("_start", ".text", 0, 1, SymbolType::STT_FUNC)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn sections_by_name_and_type() {
+    let elf = executable();
+    let text = elf.section_index(".text").unwrap();
+    assert_eq!(elf.sections[text].name, ".text");
+    assert_eq!(
+        elf.section_by_name(".bss").unwrap().header.sh_type,
+        ShType::SHT_NOBITS
+    );
+    assert!(elf.section_by_name(".missing").is_none());
+    assert_eq!(elf.section_index(".missing"), None);
+    let progbits: Vec<&str> = elf
+        .sections_of_type(ShType::SHT_PROGBITS)
+        .map(|s| s.name.as_str())
+        .collect();
+    assert_eq!(progbits, vec![".rodata", ".text", ".data"]);
+}
+
+#[test]
+fn sections_in_segments() {
+    let elf = executable();
+    let index = |name: &str| elf.section_index(name).unwrap();
+    let mapping = elf.section_to_segment_mapping();
+    assert_eq!(mapping.len(), elf.programs.len());
+    assert_eq!(
+        mapping,
+        vec![
+            vec![index(".rodata")],
+            vec![index(".text")],
+            vec![index(".data"), index(".bss")],
+            vec![]
+        ]
+    );
+    let segment = elf.segment_for_section(index(".bss")).unwrap();
+    assert_eq!(segment.header.p_type, PType::PT_LOAD);
+    assert!(segment.header.p_memsz > segment.header.p_filesz);
+    /* sections that are not loaded are in no segment */
+    assert!(elf.segment_for_section(index(".symtab")).is_none());
+    assert!(elf.segment_for_section(elf.sections.len()).is_none());
+    let report = elf.section_to_segment_report();
+    assert!(report.starts_with(" Section to Segment mapping:\n"));
+    assert!(report.contains("   01     .text \n"));
+    assert!(report.contains("   02     .data .bss \n"));
+}
+
+#[test]
+fn sizes_past_the_end_of_the_address_space() {
+    let mut elf = executable();
+    let text = elf.section_index(".text").unwrap();
+    let load = &elf.programs[1];
+    assert!(section_in_segment(&elf.sections[text], load));
+    elf.sections[text].header.sh_size = u64::MAX;
+    assert!(!section_in_segment(&elf.sections[text], &elf.programs[1]));
+    assert!(elf.segment_for_section(text).is_none());
+    /* readelf prints nothing without a section name table */
+    elf.header.e_shstrndx = 0;
+    assert_eq!(elf.section_to_segment_report(), "");
+}
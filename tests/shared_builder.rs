@@ -0,0 +1,210 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::shared::{gnu_hash, SharedLibraryBuilder};
+use simple_elf::error::ElfError;
+use simple_elf::header::e_type::EType;
+use simple_elf::program::p_type::PType;
+use simple_elf::section::elf64_dyn::DynamicTag;
+use simple_elf::section::section::SectionData;
+use simple_elf::section::symbol::{
+    Elf64_Rela, RelocationType, SymbolBinding, SymbolType, SymbolVisibility,
+};
+use simple_elf::Elf64;
+
+fn symbol(
+    b: SharedLibraryBuilder,
+    name: &str,
+    section: &str,
+    offset: u64,
+    symbol_type: SymbolType,
+    visibility: SymbolVisibility,
+) -> SharedLibraryBuilder {
+    b.add_symbol(
+        name,
+        section,
+        offset,
+        8,
+        symbol_type,
+        SymbolBinding::STB_GLOBAL,
+        visibility,
+    )
+}
+
+/*
+ * answer returns 42 and version V1 of old returns 1, pointer points to
+ * answer and imported to getpid of libc
+ */
+fn library() -> SharedLibraryBuilder {
+    let (function, object) = (SymbolType::STT_FUNC, SymbolType::STT_OBJECT);
+    let default = SymbolVisibility::STV_DEFAULT;
+    let code = vec![
+        0xb8, 0x2a, 0, 0, 0, 0xc3, 0xcc, 0xcc, /* mov $42, %eax; ret */
+        0xb8, 0x01, 0, 0, 0, 0xc3, 0xcc, 0xcc, /* mov $1, %eax; ret */
+    ];
+    let b = SharedLibraryBuilder::new()
+        .soname("libanswer.so")
+        .needed("libc.so.6")
+        .add_code(".text", 16, code)
+        .add_data(".data", 8, vec![0; 16]);
+    let b = symbol(b, "answer", ".text", 0, function, default);
+    let b = symbol(b, "old@V1", ".text", 8, function, default);
+    let b = symbol(b, "answer@@V2", ".text", 0, function, default);
+    let b = symbol(
+        b,
+        "hidden",
+        ".text",
+        8,
+        function,
+        SymbolVisibility::STV_HIDDEN,
+    );
+    let b = symbol(b, "pointer", ".data", 0, object, default);
+    let b = symbol(b, "imported", ".data", 8, object, default);
+    let absolute = |offset: u64| Elf64_Rela::new(offset, RelocationType::R_X86_64_64, 0, 0);
+    b.add_relocation(".data", "answer", absolute(0))
+        .add_relocation(".data", "getpid", absolute(8))
+}
+
+fn dynamic_names(elf: &Elf64) -> Vec<String> {
+    elf.dynamic_symbols()
+        .map(|s| s.name().to_string())
+        .collect()
+}
+
+#[test]
+fn dynamic_section_and_symbols() {
+    let (_, elf) = reparse(&library().build().unwrap());
+    assert_eq!(elf.header.e_type, EType::ET_DYN);
+    assert_eq!(elf.soname().as_deref(), Some("libanswer.so"));
+    let dynamic = elf.dynamic().unwrap();
+    assert_eq!(dynamic.get(DynamicTag::DT_VERDEFNUM), Some(3));
+    assert_eq!(dynamic.get(DynamicTag::DT_RELA_COUNT), Some(1));
+    assert_eq!(dynamic.get(DynamicTag::DT_TEXTREL), None);
+    let names = dynamic_names(&elf);
+    assert_eq!(names[0], "getpid");
+    assert!(!names.contains(&"hidden".to_string()));
+    let mut exported: Vec<String> = elf.exported_symbols().map(|s| s.versioned_name()).collect();
+    exported.sort();
+    assert_eq!(
+        exported,
+        vec!["answer", "answer@@V2", "imported", "old@V1", "pointer"]
+    );
+    let rela = match &elf.section_by_name(".rela.dyn").unwrap().data {
+        SectionData::Rela(r) => &r.rela_entries,
+        _ => panic!(".rela.dyn is not SHT_RELA"),
+    };
+    let data = elf.section_by_name(".data").unwrap().header.sh_addr;
+    let text = elf.section_by_name(".text").unwrap().header.sh_addr;
+    assert_eq!(rela[0].relocation_type, RelocationType::R_X86_64_RELATIVE);
+    assert_eq!((rela[0].r_offset, rela[0].r_addend), (data, text));
+    assert_eq!(rela[1].relocation_type, RelocationType::R_X86_64_64);
+    assert_eq!(names[rela[1].symbol_index as usize - 1], "getpid");
+}
+
+/*
+ * every export is found by walking its .gnu.hash chain like ld.so
+ */
+#[test]
+fn gnu_hash_finds_the_exports() {
+    let (_, elf) = reparse(&library().build().unwrap());
+    let table: Vec<u8> = (&elf.section_by_name(".gnu.hash").unwrap().data).into();
+    let word = |i: usize| u32::from_le_bytes(table[4 * i..4 * i + 4].try_into().unwrap());
+    let (nbuckets, symoffset, bloom_size) = (word(0), word(1), word(2));
+    let buckets = 4 + 2 * bloom_size as usize;
+    let chains = buckets + nbuckets as usize;
+    let names = dynamic_names(&elf);
+    for name in &names[symoffset as usize - 1..] {
+        let h = gnu_hash(name);
+        let mut index = word(buckets + (h % nbuckets) as usize);
+        let found = loop {
+            let chain = word(chains + (index - symoffset) as usize);
+            if chain | 1 == h | 1 && names[index as usize - 1] == *name {
+                break Some(index);
+            }
+            if chain & 1 == 1 {
+                break None;
+            }
+            index += 1;
+        };
+        assert!(found.is_some(), "{}", name);
+    }
+}
+
+#[test]
+fn thread_local_symbols() {
+    let b = SharedLibraryBuilder::new()
+        .soname("libtls.so")
+        .add_code(".text", 16, vec![0xc3])
+        .add_tbss(".tbss", 16, 64);
+    let b = symbol(
+        b,
+        "counter",
+        ".tbss",
+        32,
+        SymbolType::STT_TLS,
+        SymbolVisibility::STV_DEFAULT,
+    );
+    let (_, elf) = reparse(&b.build().unwrap());
+    let tls = elf
+        .programs
+        .iter()
+        .find(|p| p.header.p_type == PType::PT_TLS)
+        .unwrap();
+    assert_eq!((tls.header.p_memsz, tls.header.p_filesz), (64, 0));
+    assert_eq!(tls.header.p_align, 16);
+    /* the value of a TLS symbol is its offset in the segment */
+    assert_eq!(elf.symbol_by_name("counter").unwrap().symbol.st_value, 32);
+}
+
+#[test]
+fn relocations_ld_refuses() {
+    let narrow = library().add_relocation(
+        ".data",
+        "answer",
+        Elf64_Rela::new(0, RelocationType::R_X86_64_32, 0, 0),
+    );
+    assert!(matches!(
+        narrow.build(),
+        Err(ElfError::Link(d)) if d == vec!["relocation R_X86_64_32 against symbol `answer' can not be used when making a shared object; recompile with -fPIC"]
+    ));
+    let call = library().add_relocation(
+        ".text",
+        "puts",
+        Elf64_Rela::new(1, RelocationType::R_X86_64_PLT32, 0, -4i64 as u64),
+    );
+    assert!(matches!(
+        call.build(),
+        Err(ElfError::UnsupportedRelocation(_))
+    ));
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64", target_env = "gnu"))]
+#[test]
+fn loaded_by_the_dynamic_linker() {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+    let dir = common::temp_dir("shared");
+    let path = dir.join("libanswer.so");
+    let (raw, _) = reparse(&library().build().unwrap());
+    std::fs::write(&path, raw).unwrap();
+    let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    let name = |n: &str| CString::new(n).unwrap();
+    unsafe {
+        let handle = libc::dlopen(path.as_ptr(), libc::RTLD_NOW);
+        assert!(!handle.is_null(), "{:?}", CStr::from_ptr(libc::dlerror()));
+        let answer = libc::dlsym(handle, name("answer").as_ptr());
+        let call = |f: *mut libc::c_void| {
+            std::mem::transmute::<*mut libc::c_void, extern "C" fn() -> i32>(f)()
+        };
+        assert_eq!(call(answer), 42);
+        let old = libc::dlvsym(handle, name("old").as_ptr(), name("V1").as_ptr());
+        assert_eq!(call(old), 1);
+        assert!(libc::dlsym(handle, name("hidden").as_ptr()).is_null());
+        let pointer = libc::dlsym(handle, name("pointer").as_ptr()) as *const usize;
+        assert_eq!(*pointer, answer as usize);
+        let imported = libc::dlsym(handle, name("imported").as_ptr()) as *const usize;
+        let getpid = libc::dlsym(libc::RTLD_DEFAULT, name("getpid").as_ptr());
+        assert_eq!(*imported, getpid as usize);
+        libc::dlclose(handle);
+    }
+}
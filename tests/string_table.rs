@@ -0,0 +1,87 @@
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::string_table::StringTableBuilder;
+use simple_elf::section::symbol::{Elf64_Rela, RelocationType};
+
+fn string_at(table: &[u8], offset: u32) -> &str {
+    let tail = &table[offset as usize..];
+    let end = tail.iter().position(|c| *c == 0).unwrap();
+    std::str::from_utf8(&tail[..end]).unwrap()
+}
+
+#[test]
+fn tails_and_duplicates_are_stored_once() {
+    let mut table = StringTableBuilder::new();
+    for s in ["main", "in", "domain", "ain", "main", "printf", "f"] {
+        table.add(s);
+    }
+    table.finalize();
+    assert_eq!(table.data(), b"\0domain\0printf\0");
+    for s in ["main", "in", "domain", "ain", "printf", "f", ""] {
+        assert_eq!(string_at(table.data(), table.offset(s).unwrap()), s);
+    }
+    assert_eq!(
+        table.offset("domain").unwrap() + 2,
+        table.offset("main").unwrap()
+    );
+    assert!(matches!(
+        table.offset("dom"),
+        Err(ElfError::MissingString(s)) if s == "dom"
+    ));
+}
+
+#[test]
+fn offsets_do_not_change() {
+    let mut table = StringTableBuilder::new();
+    let main = table.insert("main");
+    assert_eq!(main, 1);
+    /* domain would hold main as its tail but main is already placed */
+    table.add("domain");
+    table.add("ain");
+    table.finalize();
+    assert_eq!(table.offset("main").unwrap(), main);
+    assert_eq!(string_at(table.data(), table.offset("ain").unwrap()), "ain");
+    assert_eq!(table.insert("main"), main);
+    assert_eq!(table.len(), table.data().len());
+}
+
+#[test]
+fn existing_tables_are_kept() {
+    let mut table = StringTableBuilder::from_table(b"\0libc.so.6\0foobar");
+    assert_eq!(table.data(), b"\0libc.so.6\0foobar\0");
+    /* strings and tails already in the table are found there */
+    assert_eq!(table.insert("libc.so.6"), 1);
+    assert_eq!(table.insert("bar"), 14);
+    let offset = table.insert("libm.so.6");
+    assert_eq!(offset, 18);
+    assert_eq!(string_at(table.data(), offset), "libm.so.6");
+    assert_eq!(StringTableBuilder::from_table(b"").data(), b"\0");
+}
+
+/*
+ * .text shares the bytes of .rela.text in the section name table
+ */
+#[test]
+fn section_names_share_their_tails() {
+    let elf = ObjectBuilder::new()
+        .add_section(
+            ".text",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+            16,
+            vec![0; 8],
+        )
+        .add_relocation(
+            ".text",
+            "puts",
+            Elf64_Rela::new(0, RelocationType::R_X86_64_64, 0, 0),
+        )
+        .build()
+        .unwrap();
+    let text = elf.section_by_name(".text").unwrap().header.sh_name;
+    let rela = elf.section_by_name(".rela.text").unwrap().header.sh_name;
+    assert_eq!(rela + 5, text);
+    let shstrtab: Vec<u8> = (&elf.sections[elf.header.e_shstrndx as usize].data).into();
+    assert_eq!(string_at(&shstrtab, text), ".text");
+    assert_eq!(shstrtab.windows(6).filter(|w| w == b".text\0").count(), 1);
+}
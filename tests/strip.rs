@@ -0,0 +1,203 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::executable::ExecutableBuilder;
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::debuginfo::debuglink::debuglink_crc;
+use simple_elf::program::p_type::PType;
+use simple_elf::section::section::SectionData;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::sh_type::ShType;
+use simple_elf::section::symbol::{Elf64_Rela, RelocationType, SymbolBinding, SymbolType};
+use simple_elf::Elf64;
+
+/*
+ * main calls used and puts, helper is not referenced and .debug_info
+ * points into .text
+ */
+fn object() -> Elf64 {
+    let symbol = |b: ObjectBuilder, name: &str, offset: u64, binding| {
+        b.add_symbol(
+            name,
+            Some(".text"),
+            offset,
+            1,
+            SymbolType::STT_FUNC,
+            binding,
+        )
+    };
+    let call =
+        |offset: u64| Elf64_Rela::new(offset, RelocationType::R_X86_64_PLT32, 0, -4i64 as u64);
+    let b = ObjectBuilder::new()
+        .file_name("a.c")
+        .add_section(
+            ".text",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+            16,
+            vec![0xe8, 0, 0, 0, 0, 0xe8, 0, 0, 0, 0, 0xc3, 0xc3, 0xc3],
+        )
+        .add_section(".debug_info", Default::default(), 1, vec![0; 16]);
+    let b = symbol(b, "helper", 10, SymbolBinding::STB_LOCAL);
+    let b = symbol(b, "used", 11, SymbolBinding::STB_LOCAL);
+    let b = symbol(b, "main", 0, SymbolBinding::STB_GLOBAL);
+    b.add_relocation(".text", "used", call(1))
+        .add_relocation(".text", "puts", call(6))
+        .add_relocation(
+            ".debug_info",
+            ".text",
+            Elf64_Rela::new(8, RelocationType::R_X86_64_64, 0, 12),
+        )
+        .build()
+        .unwrap()
+}
+
+fn stripped(strip: fn(&mut Elf64)) -> Elf64 {
+    let mut elf = object();
+    strip(&mut elf);
+    let (_, elf) = reparse(&elf);
+    assert_eq!(elf.header.e_shnum as usize, elf.sections.len());
+    elf
+}
+
+fn section_names(elf: &Elf64) -> Vec<&str> {
+    elf.sections.iter().map(|s| s.name.as_str()).collect()
+}
+
+fn symbol_names(elf: &Elf64) -> Vec<String> {
+    elf.symbols()
+        .filter(|s| s.symbol.symbol_type != SymbolType::STT_SECTION)
+        .map(|s| s.name().to_string())
+        .collect()
+}
+
+/*
+ * names of the symbols of .rela.text, they have to survive the
+ * renumbering of .symtab
+ */
+fn relocated(elf: &Elf64) -> Vec<String> {
+    let symbols = match &elf.section_by_name(".symtab").unwrap().data {
+        SectionData::Symbol(s) => &s.symbols,
+        _ => panic!(".symtab is not a symbol table"),
+    };
+    match &elf.section_by_name(".rela.text").unwrap().data {
+        SectionData::Rela(r) => r
+            .rela_entries
+            .iter()
+            .map(|r| symbols[r.symbol_index as usize].name().to_string())
+            .collect(),
+        _ => panic!(".rela.text is not SHT_RELA"),
+    }
+}
+
+#[test]
+fn strip_debug() {
+    assert_eq!(
+        symbol_names(&object()),
+        vec!["a.c", "helper", "used", "main", "puts"]
+    );
+    let elf = stripped(Elf64::strip_debug);
+    assert!(!section_names(&elf).contains(&".debug_info"));
+    assert!(!section_names(&elf).contains(&".rela.debug_info"));
+    assert_eq!(symbol_names(&elf), vec!["helper", "used", "main", "puts"]);
+    assert_eq!(relocated(&elf), vec!["used", "puts"]);
+}
+
+#[test]
+fn strip_unneeded() {
+    let elf = stripped(Elf64::strip_unneeded);
+    assert_eq!(symbol_names(&elf), vec!["used", "main", "puts"]);
+    assert_eq!(relocated(&elf), vec!["used", "puts"]);
+    let symtab = &elf.section_by_name(".symtab").unwrap().header;
+    /* sh_info is one past the last local symbol */
+    let symbols: Vec<_> = elf.symbols().collect();
+    assert_eq!(
+        symbols[symtab.sh_info as usize - 1].binding(),
+        SymbolBinding::STB_GLOBAL
+    );
+    assert_eq!(
+        symbols[symtab.sh_info as usize - 2].binding(),
+        SymbolBinding::STB_LOCAL
+    );
+}
+
+#[test]
+fn strip_all() {
+    /* relocatable files keep what the relocations need */
+    let elf = stripped(Elf64::strip_all);
+    assert_eq!(symbol_names(&elf), vec!["used", "puts"]);
+    assert_eq!(relocated(&elf), vec!["used", "puts"]);
+    let mut executable = ExecutableBuilder::new()
+        .add_code(".text", 16, vec![0xc3])
+        .add_symbol("_start", ".text", 0, 1, SymbolType::STT_FUNC)
+        .build()
+        .unwrap();
+    executable
+        .add_section(
+            ".debug_str",
+            ShType::SHT_PROGBITS,
+            Default::default(),
+            b"x\0".to_vec(),
+        )
+        .unwrap();
+    executable.strip_all();
+    let (_, executable) = reparse(&executable);
+    assert_eq!(section_names(&executable), vec!["", ".text", ".shstrtab"]);
+    assert_eq!(executable.header.e_shstrndx, 2);
+}
+
+#[test]
+fn only_keep_debug() {
+    let mut executable = ExecutableBuilder::new()
+        .add_code(".text", 16, vec![0xc3; 64])
+        .add_data(".data", 8, vec![1; 32])
+        .add_symbol("_start", ".text", 0, 1, SymbolType::STT_FUNC)
+        .build()
+        .unwrap();
+    executable
+        .add_section(
+            ".debug_info",
+            ShType::SHT_PROGBITS,
+            Default::default(),
+            vec![7; 8],
+        )
+        .unwrap();
+    let text = executable.section_by_name(".text").unwrap().header.clone();
+    executable.only_keep_debug();
+    let (_, debug) = reparse(&executable);
+    let placeholder = &debug.section_by_name(".text").unwrap().header;
+    assert_eq!(placeholder.sh_type, ShType::SHT_NOBITS);
+    assert_eq!(
+        (placeholder.sh_addr, placeholder.sh_size),
+        (text.sh_addr, text.sh_size)
+    );
+    let data: Vec<u8> = (&debug.section_by_name(".debug_info").unwrap().data).into();
+    assert_eq!(data, vec![7; 8]);
+    assert!(debug.symbol_by_name("_start").is_some());
+    let loads: Vec<_> = debug
+        .programs
+        .iter()
+        .filter(|p| p.header.p_type == PType::PT_LOAD)
+        .collect();
+    /* the first segment keeps the headers, the others nothing */
+    assert_eq!(
+        loads[0].header.p_filesz,
+        64 + 56 * debug.programs.len() as u64
+    );
+    assert!(loads[1..].iter().all(|p| p.header.p_filesz == 0));
+    assert!(loads.iter().all(|p| p.header.p_memsz > 0));
+}
+
+#[test]
+fn debuglink() {
+    let mut elf = object();
+    elf.strip_debug();
+    let index = elf
+        .add_gnu_debuglink("/usr/lib/debug/a.debug", b"one")
+        .unwrap();
+    assert_eq!(elf.sections[index].header.sh_addralign, 4);
+    assert_eq!(elf.add_gnu_debuglink("b.debug", b"two").unwrap(), index);
+    let (_, elf) = reparse(&elf);
+    let link = elf.debuglink().unwrap();
+    assert_eq!(link.filename, "b.debug");
+    assert_eq!(link.crc, debuglink_crc(b"two"));
+}
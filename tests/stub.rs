@@ -0,0 +1,183 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::object::{ObjectBuilder, ABS_SECTION};
+use simple_elf::builder::shared::SharedLibraryBuilder;
+use simple_elf::builder::stub::StubBody;
+use simple_elf::error::ElfError;
+use simple_elf::section::elf64_dyn::DynamicTag;
+use simple_elf::section::sh_type::ShType;
+use simple_elf::section::symbol::{SectionIndex, SymbolBinding, SymbolType, SymbolVisibility};
+use simple_elf::Elf64;
+
+fn library() -> Elf64 {
+    let symbol = |b: SharedLibraryBuilder,
+                  name: &str,
+                  section: &str,
+                  offset: u64,
+                  size: u64,
+                  symbol_type: SymbolType,
+                  binding: SymbolBinding| {
+        b.add_symbol(
+            name,
+            section,
+            offset,
+            size,
+            symbol_type,
+            binding,
+            SymbolVisibility::STV_DEFAULT,
+        )
+    };
+    let (global, weak) = (SymbolBinding::STB_GLOBAL, SymbolBinding::STB_WEAK);
+    let b = SharedLibraryBuilder::new()
+        .soname("libreal.so.1")
+        .needed("libc.so.6")
+        .add_code(
+            ".text",
+            16,
+            vec![0xb8, 7, 0, 0, 0, 0xc3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xc3],
+        )
+        .add_rodata(".rodata", 16, vec![1; 32])
+        .add_data(".data", 8, vec![2; 24])
+        .add_tbss(".tbss", 8, 16);
+    let b = symbol(
+        b,
+        "compute@@V2",
+        ".text",
+        0,
+        6,
+        SymbolType::STT_FUNC,
+        global,
+    );
+    let b = symbol(
+        b,
+        "compute_alias",
+        ".text",
+        0,
+        6,
+        SymbolType::STT_FUNC,
+        weak,
+    );
+    let b = symbol(
+        b,
+        "compute@V1",
+        ".text",
+        16,
+        1,
+        SymbolType::STT_FUNC,
+        global,
+    );
+    let b = symbol(
+        b,
+        "table",
+        ".rodata",
+        16,
+        16,
+        SymbolType::STT_OBJECT,
+        global,
+    );
+    let b = symbol(b, "state", ".data", 8, 12, SymbolType::STT_OBJECT, global);
+    let b = symbol(b, "tls", ".tbss", 8, 8, SymbolType::STT_TLS, global);
+    let b = symbol(
+        b,
+        "limit",
+        ABS_SECTION,
+        0x400,
+        0,
+        SymbolType::STT_NOTYPE,
+        global,
+    );
+    b.add_symbol(
+        "internal",
+        ".text",
+        16,
+        1,
+        SymbolType::STT_FUNC,
+        global,
+        SymbolVisibility::STV_HIDDEN,
+    )
+    .build()
+    .unwrap()
+}
+
+/*
+ * exports with everything but their address
+ */
+fn exports(elf: &Elf64) -> Vec<(String, SymbolType, SymbolBinding, u64)> {
+    let mut exports: Vec<_> = elf
+        .exported_symbols()
+        .map(|s| {
+            (
+                s.versioned_name(),
+                s.symbol.symbol_type,
+                s.binding(),
+                s.symbol.st_size,
+            )
+        })
+        .collect();
+    exports.sort_by(|a, b| a.0.cmp(&b.0));
+    exports
+}
+
+#[test]
+fn stubs_keep_the_interface() {
+    let library = library();
+    let (_, stub) = reparse(&library.stub_library(StubBody::Trap).unwrap());
+    assert_eq!(stub.soname().as_deref(), Some("libreal.so.1"));
+    assert_eq!(exports(&stub), exports(&library));
+    assert!(stub.symbol_by_name("internal").is_none());
+    assert!(stub.undefined_symbols().next().is_none());
+    let dynamic = stub.dynamic().unwrap();
+    assert!(dynamic
+        .entries
+        .iter()
+        .all(|e| e.d_tag != DynamicTag::DT_NEEDED));
+    let symbol = |name: &str| stub.symbol_by_name(name).unwrap();
+    /* aliases stay aliases, the other functions get their own slot */
+    assert_eq!(
+        symbol("compute@@V2").symbol.st_value,
+        symbol("compute_alias").symbol.st_value
+    );
+    assert_ne!(
+        symbol("compute@V1").symbol.st_value,
+        symbol("compute@@V2").symbol.st_value
+    );
+    let section = |name: &str| {
+        stub.sections[symbol(name).symbol.st_shndx as usize]
+            .name
+            .clone()
+    };
+    assert_eq!(section("table"), ".rodata");
+    assert_eq!(section("state"), ".bss");
+    assert_eq!(section("tls"), ".tbss");
+    assert_eq!(symbol("limit").section_index(), SectionIndex::SHN_ABS);
+    assert_eq!(symbol("limit").symbol.st_value, 0x400);
+    /* the alignment of the address is kept */
+    assert_eq!(symbol("table").symbol.st_value % 16, 0);
+    assert_eq!(
+        stub.section_by_name(".bss").unwrap().header.sh_type,
+        ShType::SHT_NOBITS
+    );
+}
+
+#[test]
+fn stub_bodies() {
+    let library = library();
+    for (body, code) in [
+        (StubBody::Trap, vec![0x0f, 0x0b]),
+        (StubBody::Return, vec![0x31, 0xc0, 0xc3]),
+    ] {
+        let (_, stub) = reparse(&library.stub_library(body).unwrap());
+        let address = stub.symbol_by_name("compute@V1").unwrap().symbol.st_value;
+        assert_eq!(stub.read_at_address(address, code.len()), Some(&code[..]));
+    }
+}
+
+#[test]
+fn files_without_dynamic_symbols() {
+    let object = ObjectBuilder::new().build().unwrap();
+    assert!(matches!(
+        object.stub_library(StubBody::Trap),
+        Err(ElfError::UnknownSection(n)) if n == ".dynsym"
+    ));
+}
@@ -0,0 +1,188 @@
+mod common;
+
+use common::reparse;
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::error::ElfError;
+use simple_elf::linker::{LinkOptions, Linker};
+use simple_elf::section::section::SectionData;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::symbol::{Elf64_Rela, RelocationType, SymbolBinding, SymbolType};
+use simple_elf::Elf64;
+
+/*
+ * _start calls helper, exported and puts
+ */
+fn object() -> Elf64 {
+    let function = |b: ObjectBuilder, name: &str, offset: u64, binding| {
+        b.add_symbol(
+            name,
+            Some(".text"),
+            offset,
+            1,
+            SymbolType::STT_FUNC,
+            binding,
+        )
+    };
+    let call =
+        |offset: u64| Elf64_Rela::new(offset, RelocationType::R_X86_64_PLT32, 0, -4i64 as u64);
+    let mut code = [0xe8, 0, 0, 0, 0].repeat(3);
+    code.extend([0xc3, 0xc3, 0xc3]);
+    let b = ObjectBuilder::new().file_name("a.c").add_section(
+        ".text",
+        SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+        16,
+        code,
+    );
+    let b = function(b, "helper", 15, SymbolBinding::STB_LOCAL);
+    let b = function(b, "_start", 0, SymbolBinding::STB_GLOBAL);
+    let b = function(b, "exported", 16, SymbolBinding::STB_GLOBAL);
+    b.add_relocation(".text", "helper", call(1))
+        .add_relocation(".text", "exported", call(6))
+        .add_relocation(".text", "puts", call(11))
+        .build()
+        .unwrap()
+}
+
+/*
+ * names and bindings of .symtab after writing the file, and the names
+ * the relocations point to
+ */
+fn symbols(elf: &Elf64) -> (Vec<(String, SymbolBinding)>, Vec<String>) {
+    let (_, elf) = reparse(elf);
+    let table = match &elf.section_by_name(".symtab").unwrap().data {
+        SectionData::Symbol(s) => s.symbols.clone(),
+        _ => panic!(".symtab is not a symbol table"),
+    };
+    let first_global = elf.section_by_name(".symtab").unwrap().header.sh_info as usize;
+    assert!(table[..first_global]
+        .iter()
+        .all(|s| s.symbol_binding == SymbolBinding::STB_LOCAL));
+    assert!(table[first_global..]
+        .iter()
+        .all(|s| s.symbol_binding != SymbolBinding::STB_LOCAL));
+    let relocated = match &elf.section_by_name(".rela.text").unwrap().data {
+        SectionData::Rela(r) => r
+            .rela_entries
+            .iter()
+            .map(|r| table[r.symbol_index as usize].name().to_string())
+            .collect(),
+        _ => panic!(".rela.text is not SHT_RELA"),
+    };
+    let named = table
+        .iter()
+        .filter(|s| s.symbol_type != SymbolType::STT_SECTION && !s.name().is_empty())
+        .map(|s| (s.name().to_string(), s.symbol_binding))
+        .collect();
+    (named, relocated)
+}
+
+fn binding(elf: &Elf64, name: &str) -> SymbolBinding {
+    elf.symbol_by_name(name).unwrap().binding()
+}
+
+#[test]
+fn bindings_move_symbols_and_relocations_follow() {
+    use SymbolBinding::{STB_GLOBAL, STB_LOCAL, STB_WEAK};
+    let mut elf = object();
+    assert_eq!(elf.localize_symbol("exported"), 1);
+    assert_eq!(elf.globalize_symbol("helper"), 1);
+    let (named, relocated) = symbols(&elf);
+    assert_eq!(
+        named,
+        vec![
+            ("a.c".to_string(), STB_LOCAL),
+            ("exported".to_string(), STB_LOCAL),
+            ("helper".to_string(), STB_GLOBAL),
+            ("_start".to_string(), STB_GLOBAL),
+            ("puts".to_string(), STB_GLOBAL),
+        ]
+    );
+    assert_eq!(relocated, vec!["helper", "exported", "puts"]);
+    assert_eq!(elf.weaken_symbol("puts"), 1);
+    assert_eq!(elf.weaken_symbol("_start"), 1);
+    assert_eq!(binding(&elf, "puts"), STB_WEAK);
+    assert_eq!(binding(&elf, "_start"), STB_WEAK);
+    /* nothing to do for these */
+    assert_eq!(elf.localize_symbol("puts"), 0);
+    assert_eq!(elf.globalize_symbol("a.c"), 0);
+    assert_eq!(elf.weaken_symbol("exported"), 0);
+    assert_eq!(elf.redefine_symbol("missing", "other"), 0);
+    assert_eq!(symbols(&elf).1, vec!["helper", "exported", "puts"]);
+}
+
+#[test]
+fn renamed_symbols() {
+    let mut elf = object();
+    assert_eq!(elf.redefine_symbol("puts", "write_line"), 1);
+    let (_, relocated) = symbols(&elf);
+    assert_eq!(relocated, vec!["helper", "exported", "write_line"]);
+    let (_, reparsed) = reparse(&elf);
+    assert!(reparsed.symbol_by_name("write_line").is_some());
+    assert!(reparsed.symbol_by_name("puts").is_none());
+    /* the file symbol and the functions, not the section symbols */
+    assert_eq!(elf.prefix_symbols("p_"), 5);
+    let (named, relocated) = symbols(&elf);
+    assert!(named.iter().all(|(n, _)| n.starts_with("p_")));
+    assert_eq!(relocated, vec!["p_helper", "p_exported", "p_write_line"]);
+    let sections = reparse(&elf)
+        .1
+        .symbols()
+        .filter(|s| s.symbol.symbol_type == SymbolType::STT_SECTION)
+        .all(|s| s.name().is_empty());
+    assert!(sections);
+}
+
+/*
+ * the linker sees the edited symbols
+ */
+#[test]
+fn edits_change_what_links() {
+    let other = ObjectBuilder::new()
+        .add_section(
+            ".text",
+            SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR,
+            16,
+            vec![0xc3; 2],
+        )
+        .add_symbol(
+            "write_line",
+            Some(".text"),
+            0,
+            1,
+            SymbolType::STT_FUNC,
+            SymbolBinding::STB_GLOBAL,
+        )
+        .add_symbol(
+            "exported",
+            None,
+            0,
+            0,
+            SymbolType::STT_NOTYPE,
+            SymbolBinding::STB_GLOBAL,
+        )
+        .add_relocation(
+            ".text",
+            "exported",
+            Elf64_Rela::new(1, RelocationType::R_X86_64_PC32, 0, -4i64 as u64),
+        )
+        .build()
+        .unwrap();
+    let link = |elf: &Elf64| {
+        let (_, elf) = reparse(elf);
+        Linker::new(LinkOptions::default())
+            .add_object("a.o", &elf)
+            .add_object("b.o", &other)
+            .link()
+            .map(|_| ())
+    };
+    let mut elf = object();
+    assert!(
+        matches!(link(&elf), Err(ElfError::Link(d)) if d.len() == 1 && d[0].ends_with("undefined reference to `puts'"))
+    );
+    elf.redefine_symbol("puts", "write_line");
+    assert!(link(&elf).is_ok());
+    elf.localize_symbol("exported");
+    assert!(
+        matches!(link(&elf), Err(ElfError::Link(d)) if d.len() == 1 && d[0].ends_with("undefined reference to `exported'"))
+    );
+}
@@ -0,0 +1,75 @@
+use simple_elf::builder::executable::ExecutableBuilder;
+use simple_elf::builder::object::{ObjectBuilder, ABS_SECTION};
+use simple_elf::section::symbol::{SymbolBinding, SymbolType};
+use simple_elf::Elf64;
+
+fn executable() -> Elf64 {
+    ExecutableBuilder::new()
+        .add_code(".text", 16, vec![0xc3; 32])
+        .add_data(".data", 8, vec![0; 16])
+        .add_symbol("_start", ".text", 0, 4, SymbolType::STT_FUNC)
+        .add_symbol("start_alias", ".text", 0, 0, SymbolType::STT_FUNC)
+        .add_symbol("tail", ".text", 8, 0, SymbolType::STT_FUNC)
+        .add_symbol("counter", ".data", 0, 8, SymbolType::STT_OBJECT)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn addresses_to_symbols() {
+    let elf = executable();
+    let text = elf.section_by_name(".text").unwrap().header.sh_addr;
+    let data = elf.section_by_name(".data").unwrap().header.sh_addr;
+    let symbolizer = elf.symbolizer();
+    /* the sized symbol wins over its zero sized alias */
+    assert_eq!(symbolizer.symbolize(text + 2), Some(("_start", 2)));
+    assert_eq!(symbolizer.symbolize(text + 5), None);
+    /* a zero sized symbol extends to the end of its section */
+    assert_eq!(symbolizer.symbolize(text + 31), Some(("tail", 23)));
+    assert_eq!(symbolizer.symbolize(text + 32), None);
+    assert_eq!(symbolizer.symbolize(data + 7), Some(("counter", 7)));
+    assert_eq!(symbolizer.symbolize(data + 8), None);
+    let starts: Vec<u64> = symbolizer.ranges.iter().map(|r| r.start).collect();
+    assert!(starts.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn load_bias_is_subtracted() {
+    let elf = executable();
+    let text = elf.section_by_name(".text").unwrap().header.sh_addr;
+    let symbolizer = elf.symbolizer().load_bias(0x7f0000000000);
+    let found: Vec<_> = symbolizer
+        .symbolize_all([
+            0x7f0000000000 + text + 1,
+            text + 1,
+            0x7f0000000000 + text + 9,
+        ])
+        .collect();
+    assert_eq!(found, vec![Some(("_start", 1)), None, Some(("tail", 1))]);
+}
+
+#[test]
+fn ranges_at_the_top_of_the_address_space() {
+    let elf = ObjectBuilder::new()
+        .add_symbol(
+            "top",
+            Some(ABS_SECTION),
+            u64::MAX - 8,
+            16,
+            SymbolType::STT_OBJECT,
+            SymbolBinding::STB_GLOBAL,
+        )
+        .add_symbol(
+            "last",
+            Some(ABS_SECTION),
+            u64::MAX,
+            0,
+            SymbolType::STT_FUNC,
+            SymbolBinding::STB_GLOBAL,
+        )
+        .build()
+        .unwrap();
+    let symbolizer = elf.symbolizer();
+    assert_eq!(symbolizer.symbolize(u64::MAX - 1), Some(("top", 7)));
+    assert_eq!(symbolizer.ranges.last().unwrap().end, u64::MAX);
+}
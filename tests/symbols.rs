@@ -0,0 +1,120 @@
+use simple_elf::builder::object::ObjectBuilder;
+use simple_elf::builder::shared::SharedLibraryBuilder;
+use simple_elf::section::sh_flags::SHFlags;
+use simple_elf::section::symbol::{
+    Elf64_Rela, RelocationType, SymbolBinding, SymbolType, SymbolVisibility,
+};
+use simple_elf::Elf64;
+
+fn library() -> Elf64 {
+    let function = |b: SharedLibraryBuilder, name: &str, offset: u64, binding, visibility| {
+        b.add_symbol(
+            name,
+            ".text",
+            offset,
+            4,
+            SymbolType::STT_FUNC,
+            binding,
+            visibility,
+        )
+    };
+    let b = SharedLibraryBuilder::new()
+        .soname("libversioned.so")
+        .add_code(".text", 16, vec![0xc3; 16])
+        .add_data(".got", 8, vec![0; 8])
+        .add_relocation(
+            ".got",
+            "puts",
+            Elf64_Rela::new(0, RelocationType::R_X86_64_GLOB_DAT, 0, 0),
+        );
+    let (global, weak) = (SymbolBinding::STB_GLOBAL, SymbolBinding::STB_WEAK);
+    let b = function(b, "foo@@V2", 0, global, SymbolVisibility::STV_DEFAULT);
+    let b = function(b, "foo@V1", 4, global, SymbolVisibility::STV_DEFAULT);
+    let b = function(b, "helper", 8, global, SymbolVisibility::STV_HIDDEN);
+    let b = function(b, "fallback", 12, weak, SymbolVisibility::STV_DEFAULT);
+    b.build().unwrap()
+}
+
+#[test]
+fn exported_and_imported_symbols() {
+    let elf = library();
+    let mut exported: Vec<String> = elf.exported_symbols().map(|s| s.versioned_name()).collect();
+    exported.sort();
+    assert_eq!(exported, vec!["fallback", "foo@@V2", "foo@V1"]);
+    let undefined: Vec<String> = elf
+        .undefined_symbols()
+        .map(|s| s.name().to_string())
+        .collect();
+    assert_eq!(undefined, vec!["puts"]);
+    assert!(elf
+        .defined_functions()
+        .all(|s| s.is_function() && s.is_defined()));
+    assert!(elf.defined_functions().any(|s| s.name() == "fallback"));
+    let puts = elf.dynamic_symbols().find(|s| s.name() == "puts").unwrap();
+    assert!(!puts.is_defined());
+    assert!(!puts.is_exported());
+}
+
+#[test]
+fn lookup_by_versioned_name() {
+    let elf = library();
+    let text = elf.section_by_name(".text").unwrap().header.sh_addr;
+    let value = |name: &str| elf.symbol_by_name(name).map(|s| s.symbol.st_value);
+    assert_eq!(value("foo@@V2"), Some(text));
+    assert_eq!(value("foo@V2"), Some(text));
+    assert_eq!(value("foo@V1"), Some(text + 4));
+    assert_eq!(value("foo@V3"), None);
+    assert!(matches!(value("foo"), Some(v) if v == text || v == text + 4));
+    let v1 = elf.symbol_by_name("foo@V1").unwrap();
+    assert!(v1.version.as_ref().unwrap().hidden);
+    assert_eq!(v1.versioned_name(), "foo@V1");
+    assert_eq!(
+        elf.symbol_by_name("fallback").unwrap().binding(),
+        SymbolBinding::STB_WEAK
+    );
+}
+
+#[test]
+fn lookup_in_relocatable_files() {
+    let text = SHFlags::SHF_ALLOC | SHFlags::SHF_EXECINSTR;
+    let symbol = |b: ObjectBuilder, name: &str, offset: u64, binding| {
+        b.add_symbol(
+            name,
+            Some(".text"),
+            offset,
+            1,
+            SymbolType::STT_FUNC,
+            binding,
+        )
+    };
+    let b = ObjectBuilder::new().add_section(".text", text, 16, vec![0xc3; 4]);
+    let b = symbol(b, "local", 0, SymbolBinding::STB_LOCAL);
+    let b = symbol(b, "bar", 1, SymbolBinding::STB_GLOBAL);
+    /* the name .symver gives a symbol in an object */
+    let b = symbol(b, "baz@VER_1", 2, SymbolBinding::STB_GLOBAL);
+    let b = b.add_symbol(
+        "baz",
+        None,
+        0,
+        0,
+        SymbolType::STT_NOTYPE,
+        SymbolBinding::STB_GLOBAL,
+    );
+    let elf = b.build().unwrap();
+    let bar = elf.symbol_by_name("bar").unwrap();
+    assert_eq!(
+        (bar.symbol.st_value, bar.binding()),
+        (1, SymbolBinding::STB_GLOBAL)
+    );
+    let versioned = elf.symbol_by_name("baz@VER_1").unwrap();
+    assert_eq!(
+        (versioned.name(), versioned.symbol.st_value),
+        ("baz@VER_1", 2)
+    );
+    assert!(!elf.symbol_by_name("baz").unwrap().is_defined());
+    let exported: Vec<String> = elf
+        .exported_symbols()
+        .map(|s| s.name().to_string())
+        .collect();
+    assert_eq!(exported, vec!["bar", "baz@VER_1"]);
+}